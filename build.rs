@@ -0,0 +1,90 @@
+fn main() {
+    // The C++ side of the `cxx` bridge is only generated and compiled with the `cxx` feature.
+    #[cfg(feature = "cxx")]
+    cxx::bridge();
+}
+
+// Generation of the `cxx` bridge. `cxx` cannot expand macros inside a bridge, so rather than
+// declaring the same functions by hand for every element type, the bridge module is written out
+// here, one block per entry of `TYPES`, and included by `src/cxx_bridge.rs`.
+#[cfg(feature = "cxx")]
+mod cxx {
+    use std::env;
+    use std::fmt::Write;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // Number of values each queue exposed to C++ can hold.
+    const CAPACITY: usize = 4096;
+
+    // Element types of the queues exposed to C++: opaque type, Rust and C++ element types.
+    const TYPES: &[(&str, &str, &str)] = &[
+        ("QueueU8", "u8", "uint8_t"),
+        ("QueueU32", "u32", "uint32_t"),
+        ("QueueU64", "u64", "uint64_t"),
+        ("QueueI32", "i32", "int32_t"),
+        ("QueueI64", "i64", "int64_t"),
+        ("QueueF32", "f32", "float"),
+        ("QueueF64", "f64", "double"),
+    ];
+
+    pub fn bridge() {
+        let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+        fs::create_dir_all(out_dir.join("src")).unwrap();
+        fs::write(out_dir.join("src/cxx_bridge.rs"), source()).unwrap();
+
+        // `cxx_build` names the generated header after the path it is given, so pass it relative
+        // to the output directory to keep C++ includes at `rust-queue/src/cxx_bridge.rs.h`.
+        env::set_current_dir(&out_dir).unwrap();
+        cxx_build::bridge("src/cxx_bridge.rs")
+            .flag_if_supported("-std=c++14")
+            .compile("rust-queue-cxx");
+        println!("cargo:rerun-if-changed=build.rs");
+    }
+
+    // Source of the bridge module, followed by the definitions of its types.
+    fn source() -> String {
+        let mut source = String::from("/// Capacity of the queues exposed to C++.\n");
+        writeln!(source, "pub const BRIDGE_CAPACITY: usize = {CAPACITY};\n").unwrap();
+        source.push_str("#[cxx::bridge(namespace = \"rust_queue\")]\n");
+        source.push_str("mod ffi {\n    extern \"Rust\" {\n");
+        for (queue, rust, cpp) in TYPES {
+            let new = format!("new_queue_{rust}");
+            write!(
+                source,
+                r#"
+        /// Thread-safe queue of `{cpp}`, holding up to {CAPACITY} values.
+        type {queue};
+        /// Create an empty `{queue}`.
+        fn {new}() -> Box<{queue}>;
+        /// Push a value. Returns false if the queue is full or closed.
+        fn push(self: &{queue}, value: {rust}) -> bool;
+        /// Push a value, overwriting the oldest one if the queue is full. Returns false if the
+        /// queue is closed.
+        fn push_overwrite(self: &{queue}, value: {rust}) -> bool;
+        /// Pop the oldest value into `value`. Returns false if the queue is empty.
+        fn pop(self: &{queue}, value: &mut {rust}) -> bool;
+        /// Get the number of queued values.
+        fn size(self: &{queue}) -> usize;
+        /// Check if the queue is empty.
+        fn is_empty(self: &{queue}) -> bool;
+        /// Check if the queue is full.
+        fn is_full(self: &{queue}) -> bool;
+        /// Get the maximum number of values the queue can hold, i.e. {CAPACITY}.
+        fn capacity(self: &{queue}) -> usize;
+        /// Close the queue to producers. Consumers can still pop the remaining values.
+        fn close(self: &{queue}) -> bool;
+        /// Check if the queue has been closed.
+        fn is_closed(self: &{queue}) -> bool;
+"#
+            )
+            .unwrap();
+        }
+        source.push_str("    }\n}\n\nbridge_queues! {\n");
+        for (queue, rust, _) in TYPES {
+            writeln!(source, "    {queue}({rust}) => new_queue_{rust};").unwrap();
+        }
+        source.push_str("}\n");
+        source
+    }
+}
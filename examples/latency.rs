@@ -0,0 +1,102 @@
+// Measures push-to-pop handoff latency between a producer and a consumer thread and prints a
+// histogram with power-of-two buckets. Run with:
+//
+//     cargo run --release --example latency
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rust_queue::thread_safe_typed_queue::ThreadSafeTypedQueue;
+use rust_queue::typed_queue::TypedQueue;
+
+const CAPACITY: usize = 1024;
+const COUNT: usize = 200_000;
+const BUCKETS: usize = 32;
+
+// Element carrying the time at which it was pushed, padded to a configurable size in bytes
+#[derive(Copy, Clone)]
+struct Stamped<const BYTES: usize> {
+    pushed_at: Instant,
+    _padding: [u8; BYTES],
+}
+
+// Histogram of latencies where bucket `n` counts samples in [2^n, 2^(n+1)) nanoseconds
+struct Histogram {
+    buckets: [u64; BUCKETS],
+    max: Duration,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Histogram {
+            buckets: [0; BUCKETS],
+            max: Duration::ZERO,
+        }
+    }
+
+    fn record(&mut self, latency: Duration) {
+        let nanos = latency.as_nanos().max(1) as u64;
+        let bucket = (63 - nanos.leading_zeros() as usize).min(BUCKETS - 1);
+        self.buckets[bucket] += 1;
+        self.max = self.max.max(latency);
+    }
+
+    fn print(&self) {
+        let total: u64 = self.buckets.iter().sum();
+        for (n, &count) in self.buckets.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let share = count as f64 / total as f64;
+            let bar = "#".repeat((share * 50.0).ceil() as usize);
+            println!(
+                "  {:>10?} .. {:>10?} {:>8} {}",
+                Duration::from_nanos(1 << n),
+                Duration::from_nanos(1 << (n + 1)),
+                count,
+                bar
+            );
+        }
+        println!("  max {:?}", self.max);
+    }
+}
+
+fn bench<const BYTES: usize>() {
+    let queue = ThreadSafeTypedQueue::<Stamped<BYTES>, CAPACITY>::new();
+    let mut histogram = Histogram::new();
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            let mut queue = &queue;
+            for _ in 0..COUNT {
+                let value = Stamped {
+                    pushed_at: Instant::now(),
+                    _padding: [0u8; BYTES],
+                };
+                while queue.push(value).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut queue = &queue;
+        for _ in 0..COUNT {
+            loop {
+                if let Ok(value) = queue.pop() {
+                    histogram.record(value.pushed_at.elapsed());
+                    break;
+                }
+                thread::yield_now();
+            }
+        }
+    });
+
+    println!("ThreadSafeTypedQueue, {} B elements:", BYTES);
+    histogram.print();
+}
+
+fn main() {
+    println!("{} elements per run, capacity {}", COUNT, CAPACITY);
+    bench::<8>();
+    bench::<256>();
+}
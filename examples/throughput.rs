@@ -0,0 +1,138 @@
+// Measures producer/consumer throughput across two threads for a range of element sizes.
+//
+// Compares `ThreadSafeTypedQueue` against a `BasicTypedQueue` wrapped in a plain `Mutex`, which is
+// the simplest alternative a user might reach for, and against the lock-free single-producer,
+// single-consumer ring of `shm_queue`, set up in a heap buffer instead of shared memory, which
+// shows what giving up multiple producers or consumers buys. Run with:
+//
+//     cargo run --release --example throughput
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rust_queue::basic_typed_queue::BasicTypedQueue;
+use rust_queue::shm_queue::{self, ShmConsumer, ShmProducer};
+use rust_queue::thread_safe_typed_queue::ThreadSafeTypedQueue;
+use rust_queue::typed_queue::TypedQueue;
+
+const CAPACITY: usize = 1024;
+const COUNT: usize = 1_000_000;
+
+// Element of a configurable size in bytes
+#[derive(Copy, Clone)]
+struct Payload<const BYTES: usize>([u8; BYTES]);
+
+// Cache line, so that a buffer of them is suitably aligned for a shared-memory queue region
+#[derive(Copy, Clone)]
+#[repr(C, align(64))]
+struct Line([u8; 64]);
+
+// Run a single producer and a single consumer over `queue`, returning the elapsed time.
+fn run<Q: Sync, T: Copy + Send>(
+    queue: &Q,
+    value: T,
+    push: fn(&Q, T) -> bool,
+    pop: fn(&Q) -> Option<T>,
+) -> Duration {
+    let start = Instant::now();
+
+    thread::scope(|s| {
+        s.spawn(move || {
+            for _ in 0..COUNT {
+                while !push(queue, value) {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        s.spawn(move || {
+            for _ in 0..COUNT {
+                while pop(queue).is_none() {
+                    thread::yield_now();
+                }
+            }
+        });
+    });
+
+    start.elapsed()
+}
+
+// Same as `run()`, over the two ends of an SPSC queue, each owned by its thread.
+fn run_spsc<T: Copy + Send>(value: T) -> Duration {
+    let len = shm_queue::region_size::<T>(CAPACITY);
+    let mut region = vec![Line([0; 64]); len.div_ceil(64)];
+    let ptr = region.as_mut_ptr().cast::<u8>();
+
+    // The region outlives both ends, each attached once after formatting
+    let (mut producer, mut consumer) = unsafe {
+        shm_queue::init::<T>(ptr, len).unwrap();
+        (
+            ShmProducer::<T>::attach(ptr, len).unwrap(),
+            ShmConsumer::<T>::attach(ptr, len).unwrap(),
+        )
+    };
+
+    let start = Instant::now();
+
+    thread::scope(|s| {
+        s.spawn(move || {
+            for _ in 0..COUNT {
+                while producer.push(value).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        s.spawn(move || {
+            for _ in 0..COUNT {
+                while consumer.pop().is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+    });
+
+    start.elapsed()
+}
+
+fn report(name: &str, bytes: usize, elapsed: Duration) {
+    let rate = COUNT as f64 / elapsed.as_secs_f64() / 1e6;
+    println!(
+        "{:<24} {:>6} B {:>10.2} Melem/s {:>10.2?}",
+        name, bytes, rate, elapsed
+    );
+}
+
+fn bench<const BYTES: usize>() {
+    let value = Payload([0u8; BYTES]);
+
+    let queue = ThreadSafeTypedQueue::<Payload<BYTES>, CAPACITY>::new();
+    let elapsed = run(
+        &queue,
+        value,
+        |mut q, v| q.push(v).is_ok(),
+        |mut q| q.pop().ok(),
+    );
+    report("ThreadSafeTypedQueue", BYTES, elapsed);
+
+    let queue = Mutex::new(BasicTypedQueue::<Payload<BYTES>, CAPACITY>::new());
+    let elapsed = run(
+        &queue,
+        value,
+        |q, v| q.lock().unwrap().push(v).is_ok(),
+        |q| q.lock().unwrap().pop().ok(),
+    );
+    report("Mutex<BasicTypedQueue>", BYTES, elapsed);
+
+    let elapsed = run_spsc(value);
+    report("SPSC ring (shm_queue)", BYTES, elapsed);
+}
+
+fn main() {
+    println!("{} elements per run, capacity {}", COUNT, CAPACITY);
+    bench::<8>();
+    bench::<64>();
+    bench::<256>();
+    bench::<1024>();
+}
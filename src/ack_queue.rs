@@ -0,0 +1,362 @@
+use std::array;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+/// Identifier of an element leased by `AckQueue::pop()`, to be passed to `ack()` or `nack()`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct LeaseId(u64);
+
+// Element waiting to be delivered, along with the number of times it was delivered before.
+#[derive(Copy, Clone)]
+struct Entry<T> {
+    value: T,
+    deliveries: u32,
+}
+
+// Element delivered to a consumer that has not acknowledged it yet.
+#[derive(Copy, Clone)]
+struct Lease<T> {
+    id: LeaseId,
+    entry: Entry<T>,
+    leased_at: Instant,
+}
+
+// State shared by the producers and consumers of an acknowledged queue.
+struct AckState<T: Copy, const CAPACITY: usize, D> {
+    pending: BasicTypedQueue<Entry<T>, CAPACITY>,
+    in_flight: [Option<Lease<T>>; CAPACITY],
+    next_id: u64,
+    dead_letters: Option<D>,
+    max_requeues: u32,
+}
+
+impl<T: Copy, const CAPACITY: usize, D: TypedQueue<T>> AckState<T, CAPACITY, D> {
+    fn in_flight_count(&self) -> usize {
+        self.in_flight.iter().flatten().count()
+    }
+
+    // Remove the lease with the given id from the in-flight set.
+    fn take_lease(&mut self, id: LeaseId) -> Option<Lease<T>> {
+        self.in_flight
+            .iter_mut()
+            .find(|lease| lease.is_some_and(|lease| lease.id == id))?
+            .take()
+    }
+
+    // Put a rejected element back at the front of the queue, or route it into the dead-letter
+    // queue if it has already been requeued `max_requeues` times. If the dead-letter queue fails,
+    // e.g. because it is a closed shared queue, the element is requeued rather than lost: the
+    // front of the queue always has room, as in-flight elements count towards the capacity.
+    fn requeue(&mut self, entry: Entry<T>) -> Result<(), QueueError> {
+        if let Some(dead_letters) = &mut self.dead_letters {
+            if entry.deliveries > self.max_requeues
+                && dead_letters.push_overwrite(entry.value).is_ok()
+            {
+                return Ok(());
+            }
+        }
+        self.pending.push_front(entry)
+    }
+}
+
+// Thread-safe queue with at-least-once delivery. `pop()` does not remove an element but leases
+// it: the element moves to an in-flight set until the consumer either acknowledges it with
+// `ack()`, removing it for good, or rejects it with `nack()`, putting it back at the front of the
+// queue for redelivery. Leases held by a consumer that crashed or hung can be reclaimed with
+// `requeue_expired()`, so no element is silently lost. In-flight elements count towards the
+// capacity, so that requeueing one never fails. With a dead-letter queue attached, elements
+// rejected too many times are routed into it instead of being redelivered forever, so that
+// poisoned messages can be inspected later.
+pub struct AckQueue<T: Copy, const CAPACITY: usize, D: TypedQueue<T> = BasicTypedQueue<T, CAPACITY>>
+{
+    state: Mutex<AckState<T, CAPACITY, D>>,
+}
+
+impl<T: Copy, const CAPACITY: usize, D: TypedQueue<T>> AckQueue<T, CAPACITY, D> {
+    /// Create a new acknowledged queue, redelivering rejected elements indefinitely.
+    pub fn new() -> Self {
+        AckQueue::from_state(None, 0)
+    }
+
+    /// Create a new acknowledged queue routing elements into `dead_letters` instead of
+    /// requeueing them once they have been requeued `max_requeues` times. A full dead-letter
+    /// queue is overwritten, keeping the most recent dead letters.
+    pub fn with_dead_letters(dead_letters: D, max_requeues: u32) -> Self {
+        AckQueue::from_state(Some(dead_letters), max_requeues)
+    }
+
+    fn from_state(dead_letters: Option<D>, max_requeues: u32) -> Self {
+        AckQueue {
+            state: Mutex::new(AckState {
+                pending: BasicTypedQueue::new(),
+                in_flight: array::from_fn(|_| None),
+                next_id: 0,
+                dead_letters,
+                max_requeues,
+            }),
+        }
+    }
+
+    /// Push an element to the queue. Fails if queue is full, counting in-flight elements.
+    pub fn push(&self, input: T) -> Result<(), QueueError> {
+        let mut guard = self.state.lock()?;
+        if guard.pending.size() + guard.in_flight_count() == CAPACITY {
+            return Err(QueueError::QueueFull);
+        }
+
+        guard.pending.push(Entry {
+            value: input,
+            deliveries: 0,
+        })
+    }
+
+    /// Lease the oldest element, moving it to the in-flight set until it is acknowledged or
+    /// rejected. Fails if no element is waiting for delivery.
+    pub fn pop(&self) -> Result<(LeaseId, T), QueueError> {
+        let mut guard = self.state.lock()?;
+        let mut entry = guard.pending.pop()?;
+        entry.deliveries += 1;
+
+        let id = LeaseId(guard.next_id);
+        guard.next_id += 1;
+        // In-flight elements count towards the capacity, so there is always a free slot
+        let slot = guard.in_flight.iter_mut().find(|lease| lease.is_none());
+        *slot.ok_or(QueueError::QueueFull)? = Some(Lease {
+            id,
+            entry,
+            leased_at: Instant::now(),
+        });
+
+        Ok((id, entry.value))
+    }
+
+    /// Acknowledge a leased element, removing it from the queue for good. Returns the element,
+    /// or `None` if the lease is unknown, e.g. because it already expired and was requeued.
+    pub fn ack(&self, id: LeaseId) -> Result<Option<T>, QueueError> {
+        let mut guard = self.state.lock()?;
+        Ok(guard.take_lease(id).map(|lease| lease.entry.value))
+    }
+
+    /// Reject a leased element, putting it back at the front of the queue to be delivered
+    /// again, or into the dead-letter queue if it was requeued too many times already. Returns
+    /// `false` if the lease is unknown.
+    pub fn nack(&self, id: LeaseId) -> Result<bool, QueueError> {
+        let mut guard = self.state.lock()?;
+        match guard.take_lease(id) {
+            Some(lease) => {
+                guard.requeue(lease.entry)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Requeue every element leased for longer than `timeout`, as if its consumer had rejected
+    /// it, oldest lease first. Returns the number of requeued elements.
+    pub fn requeue_expired(&self, timeout: Duration) -> Result<usize, QueueError> {
+        let mut guard = self.state.lock()?;
+        let mut count = 0;
+        // Pushed to the front newest first, so that the oldest lease is redelivered first
+        while let Some(id) = guard
+            .in_flight
+            .iter()
+            .flatten()
+            .filter(|lease| lease.leased_at.elapsed() >= timeout)
+            .map(|lease| lease.id)
+            .max_by_key(|id| id.0)
+        {
+            if let Some(lease) = guard.take_lease(id) {
+                guard.requeue(lease.entry)?;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Get the number of times the element of a lease has been delivered, including this lease.
+    pub fn deliveries(&self, id: LeaseId) -> Result<Option<u32>, QueueError> {
+        let guard = self.state.lock()?;
+        Ok(guard
+            .in_flight
+            .iter()
+            .flatten()
+            .find(|lease| lease.id == id)
+            .map(|lease| lease.entry.deliveries))
+    }
+
+    /// Get the number of elements waiting for delivery.
+    pub fn size(&self) -> Result<usize, QueueError> {
+        Ok(self.state.lock()?.pending.size())
+    }
+
+    /// Get the number of elements leased but not yet acknowledged or rejected.
+    pub fn in_flight(&self) -> Result<usize, QueueError> {
+        Ok(self.state.lock()?.in_flight_count())
+    }
+
+    /// Check if no element is waiting for delivery or in flight.
+    pub fn is_empty(&self) -> Result<bool, QueueError> {
+        let guard = self.state.lock()?;
+        Ok(guard.pending.is_empty() && guard.in_flight_count() == 0)
+    }
+
+    /// Pop the oldest element from the dead-letter queue. Fails if it is empty, or if no
+    /// dead-letter queue is attached.
+    pub fn pop_dead_letter(&self) -> Result<T, QueueError> {
+        let mut guard = self.state.lock()?;
+        match &mut guard.dead_letters {
+            Some(dead_letters) => dead_letters.pop(),
+            None => Err(QueueError::QueueEmpty),
+        }
+    }
+
+    /// Get the number of elements in the dead-letter queue, if one is attached.
+    pub fn dead_letters(&self) -> Result<usize, QueueError> {
+        let guard = self.state.lock()?;
+        Ok(guard
+            .dead_letters
+            .as_ref()
+            .map_or(0, |dead_letters| dead_letters.size()))
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, D: TypedQueue<T>> Default for AckQueue<T, CAPACITY, D> {
+    fn default() -> Self {
+        AckQueue::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AckQueue;
+    use crate::basic_typed_queue::BasicTypedQueue;
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::QueueError;
+    use std::thread;
+    use std::time::Duration;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn ack_nack() {
+        let queue = AckQueue::<u32, SIZE>::default();
+        for n in 0..3 {
+            assert!(queue.push(n).is_ok());
+        }
+
+        let (first, value) = queue.pop().unwrap();
+        assert_eq!(value, 0);
+        let (second, value) = queue.pop().unwrap();
+        assert_eq!(value, 1);
+        assert_eq!(queue.size(), Ok(1));
+        assert_eq!(queue.in_flight(), Ok(2));
+
+        // Acknowledged elements are gone, rejected ones are delivered again first
+        assert_eq!(queue.ack(first), Ok(Some(0)));
+        assert_eq!(queue.ack(first), Ok(None));
+        assert_eq!(queue.nack(second), Ok(true));
+        assert_eq!(queue.nack(second), Ok(false));
+
+        let (third, value) = queue.pop().unwrap();
+        assert_eq!(value, 1);
+        assert_ne!(third, second);
+        assert_eq!(queue.deliveries(third), Ok(Some(2)));
+        assert_eq!(queue.ack(third), Ok(Some(1)));
+
+        let (last, value) = queue.pop().unwrap();
+        assert_eq!(value, 2);
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(queue.is_empty(), Ok(false));
+        assert!(queue.ack(last).is_ok());
+        assert_eq!(queue.is_empty(), Ok(true));
+    }
+
+    #[test]
+    fn in_flight_capacity() {
+        let queue = AckQueue::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        let (id, _) = queue.pop().unwrap();
+
+        // The leased element still takes up room, which guarantees it can be requeued
+        assert_eq!(queue.push(0).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(queue.nack(id), Ok(true));
+        assert_eq!(queue.size(), Ok(SIZE));
+    }
+
+    #[test]
+    fn crashed_consumer() {
+        let queue = AckQueue::<u32, SIZE>::default();
+        for n in 0..2 {
+            assert!(queue.push(n).is_ok());
+        }
+
+        let result = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _first = queue.pop().unwrap();
+                    let _second = queue.pop().unwrap();
+                    panic!("consumer crashed before acknowledging");
+                })
+                .join()
+        });
+        assert!(result.is_err());
+        assert_eq!(queue.in_flight(), Ok(2));
+
+        // Expired leases are requeued in their original order
+        assert_eq!(queue.requeue_expired(Duration::from_secs(60)), Ok(0));
+        assert_eq!(queue.requeue_expired(Duration::ZERO), Ok(2));
+        assert_eq!(queue.pop().unwrap().1, 0);
+        assert_eq!(queue.pop().unwrap().1, 1);
+    }
+
+    #[test]
+    fn dead_letters() {
+        let queue = AckQueue::<u32, SIZE>::with_dead_letters(BasicTypedQueue::default(), 1);
+        assert!(queue.push(7).is_ok());
+        assert_eq!(queue.pop_dead_letter(), Err(QueueError::QueueEmpty));
+
+        // Requeued once, then routed into the dead-letter queue on the second rejection
+        let (id, _) = queue.pop().unwrap();
+        assert_eq!(queue.nack(id), Ok(true));
+        let (id, _) = queue.pop().unwrap();
+        assert_eq!(queue.nack(id), Ok(true));
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(queue.dead_letters(), Ok(1));
+        assert_eq!(queue.pop_dead_letter(), Ok(7));
+
+        // Expired leases count as rejections
+        assert!(queue.push(8).is_ok());
+        for _ in 0..2 {
+            assert!(queue.pop().is_ok());
+            assert_eq!(queue.requeue_expired(Duration::ZERO), Ok(1));
+        }
+        assert_eq!(queue.is_empty(), Ok(true));
+        assert_eq!(queue.pop_dead_letter(), Ok(8));
+    }
+
+    #[test]
+    fn failing_dead_letters() {
+        let dead_letters = ThreadSafeTypedQueue::<u32, SIZE>::new();
+        assert!(dead_letters.close().is_ok());
+        let queue = AckQueue::<u32, SIZE, _>::with_dead_letters(&dead_letters, 0);
+        assert!(queue.push(7).is_ok());
+
+        // The closed dead-letter queue rejects the element, which is requeued instead of lost
+        let (id, _) = queue.pop().unwrap();
+        assert_eq!(queue.nack(id), Ok(true));
+        assert_eq!(queue.dead_letters(), Ok(0));
+
+        let (id, value) = queue.pop().unwrap();
+        assert_eq!(value, 7);
+        assert_eq!(queue.deliveries(id), Ok(Some(2)));
+        assert_eq!(queue.requeue_expired(Duration::ZERO), Ok(1));
+        assert_eq!(queue.size(), Ok(1));
+    }
+}
@@ -0,0 +1,208 @@
+use std::cell::{Cell, RefCell};
+use std::future::poll_fn;
+use std::task::{Context, Poll, Waker};
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Queue whose pushes and pops can be awaited by tasks running on a single thread, e.g. futures
+// spawned with `wasm_bindgen_futures::spawn_local()` in a browser, where blocking is not an
+// option. A pop waits until an element is pushed, and a push waits until there is room. Every
+// task waiting on one side is woken when the other side makes progress, and the ones that lose
+// the race wait again. Closing the queue lets consumers drain the remaining elements before their
+// pops fail. Needs neither threads nor locks, and is neither `Send` nor `Sync`.
+pub struct AsyncQueue<T: Copy, const N: usize> {
+    queue: RefCell<BasicTypedQueue<T, N>>,
+    pop_wakers: RefCell<Vec<Waker>>,  // tasks waiting for an element
+    push_wakers: RefCell<Vec<Waker>>, // tasks waiting for room
+    closed: Cell<bool>,
+}
+
+impl<T: Copy, const N: usize> AsyncQueue<T, N> {
+    /// Create a new, open and empty queue.
+    pub fn new() -> Self {
+        AsyncQueue {
+            queue: RefCell::new(BasicTypedQueue::new()),
+            pop_wakers: RefCell::new(Vec::new()),
+            push_wakers: RefCell::new(Vec::new()),
+            closed: Cell::new(false),
+        }
+    }
+
+    /// Push an element, waiting for room while the queue is full. Fails with
+    /// `QueueError::Closed` if the queue is closed, including while waiting.
+    pub async fn push(&self, input: T) -> Result<(), QueueError> {
+        poll_fn(|cx| match self.try_push(input) {
+            Err(QueueError::QueueFull) => {
+                register(&self.push_wakers, cx);
+                Poll::Pending
+            }
+            result => Poll::Ready(result),
+        })
+        .await
+    }
+
+    /// Pop the oldest element, waiting for one while the queue is empty. Fails with
+    /// `QueueError::Closed` once the queue is closed and every element has been popped.
+    pub async fn pop(&self) -> Result<T, QueueError> {
+        poll_fn(|cx| match self.try_pop() {
+            Err(QueueError::QueueEmpty) if self.is_closed() => Poll::Ready(Err(QueueError::Closed)),
+            Err(QueueError::QueueEmpty) => {
+                register(&self.pop_wakers, cx);
+                Poll::Pending
+            }
+            result => Poll::Ready(result),
+        })
+        .await
+    }
+
+    /// Push an element without waiting. Fails if queue is full or closed.
+    pub fn try_push(&self, input: T) -> Result<(), QueueError> {
+        if self.is_closed() {
+            return Err(QueueError::Closed);
+        }
+
+        self.queue.borrow_mut().push(input)?;
+        wake_all(&self.pop_wakers);
+        Ok(())
+    }
+
+    /// Pop the oldest element without waiting. Fails if queue is empty.
+    pub fn try_pop(&self) -> Result<T, QueueError> {
+        let value = self.queue.borrow_mut().pop()?;
+        wake_all(&self.push_wakers);
+        Ok(value)
+    }
+
+    /// Close the queue: later pushes fail with `QueueError::Closed`, waiting producers give up,
+    /// and waiting consumers fail once the remaining elements are popped.
+    pub fn close(&self) {
+        self.closed.set(true);
+        wake_all(&self.push_wakers);
+        wake_all(&self.pop_wakers);
+    }
+
+    /// Check if the queue has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+
+    /// Get the number of queued elements.
+    pub fn size(&self) -> usize {
+        self.queue.borrow().size()
+    }
+
+    /// Check if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.queue.borrow().is_empty()
+    }
+
+    /// Get the maximum number of elements the queue can hold.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<T: Copy, const N: usize> Default for AsyncQueue<T, N> {
+    fn default() -> Self {
+        AsyncQueue::new()
+    }
+}
+
+// Remember the task of `cx` to be woken, unless it is already waiting.
+fn register(wakers: &RefCell<Vec<Waker>>, cx: &Context<'_>) {
+    let mut wakers = wakers.borrow_mut();
+    if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+        wakers.push(cx.waker().clone());
+    }
+}
+
+fn wake_all(wakers: &RefCell<Vec<Waker>>) {
+    // Taken out first, as a waker may poll the task again right away
+    let wakers = wakers.take();
+    for waker in wakers {
+        waker.wake();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncQueue;
+    use crate::typed_queue::QueueError;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 4;
+
+    // Waker counting how many times its task was woken.
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn wake_on_push() {
+        let queue = AsyncQueue::<u32, SIZE>::new();
+        let counter = Arc::new(CountingWaker::default());
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut pop = pin!(queue.pop());
+        assert_eq!(pop.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(counter.0.load(Ordering::Relaxed), 0);
+
+        assert!(queue.try_push(7).is_ok());
+        assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+        assert_eq!(pop.as_mut().poll(&mut cx), Poll::Ready(Ok(7)));
+    }
+
+    #[test]
+    fn wake_on_pop() {
+        let queue = AsyncQueue::<u32, SIZE>::new();
+        for n in 0..SIZE as u32 {
+            assert!(queue.try_push(n).is_ok());
+        }
+
+        let counter = Arc::new(CountingWaker::default());
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut push = pin!(queue.push(100));
+        assert_eq!(push.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(queue.try_pop(), Ok(0));
+        assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+        assert_eq!(push.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+        assert_eq!(queue.size(), SIZE);
+    }
+
+    #[test]
+    fn close() {
+        let queue = AsyncQueue::<u32, SIZE>::default();
+        assert!(queue.try_push(1).is_ok());
+
+        let counter = Arc::new(CountingWaker::default());
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        // Waiting consumers drain the queue, then fail
+        assert_eq!(pin!(queue.pop()).poll(&mut cx), Poll::Ready(Ok(1)));
+        let mut pop = pin!(queue.pop());
+        assert_eq!(pop.as_mut().poll(&mut cx), Poll::Pending);
+        queue.close();
+        assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+        assert_eq!(
+            pop.as_mut().poll(&mut cx),
+            Poll::Ready(Err(QueueError::Closed))
+        );
+        assert_eq!(queue.try_push(2), Err(QueueError::Closed));
+        assert!(queue.is_empty());
+    }
+}
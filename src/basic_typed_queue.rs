@@ -1,36 +1,149 @@
 use std::cmp::min;
-use std::mem::MaybeUninit;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::mem::{self, size_of, MaybeUninit};
+use std::ops::{Index, IndexMut, Range};
+use std::ptr;
+use std::slice;
 
+use crate::storage::{self, Storage};
 use crate::typed_queue::QueueError;
-use crate::typed_queue::TypedQueue;
+use crate::typed_queue::{OverflowPolicy, TypedQueue};
 
-// Basic typed queue struct with generic capacity. Not thread-safe.
+// Basic typed queue struct with generic capacity. Not thread-safe. Elements are stored inline by
+// default; other backing stores can be used through the `Storage` parameter, in which case the
+// capacity is taken from the storage and `CAPACITY` is conventionally 0.
 #[derive(Copy, Clone)]
-pub struct BasicTypedQueue<T: Copy, const CAPACITY: usize> {
+pub struct BasicTypedQueue<
+    T: Copy,
+    const CAPACITY: usize,
+    S: Storage<T> = [MaybeUninit<T>; CAPACITY],
+> {
     size: usize, // not strictly necessary, but simplifies logic
     head: usize,
     tail: usize,
-    buffer: [MaybeUninit<T>; CAPACITY],
+    soft_capacity: usize, // advisory limit below the hard capacity, for backpressure
+    overflow_policy: OverflowPolicy,
+    storage: S,
+    _marker: PhantomData<T>,
+}
+
+/// Queue backed by a heap allocation sized at runtime.
+#[cfg(feature = "alloc")]
+pub type BoxedTypedQueue<T> = BasicTypedQueue<T, 0, Box<[MaybeUninit<T>]>>;
+
+/// Queue over a buffer owned by the caller, such as a static buffer or a stack array sized at
+/// runtime.
+pub type SliceTypedQueue<'a, T> = BasicTypedQueue<T, 0, &'a mut [MaybeUninit<T>]>;
+
+// Compile-time check of the size of an inline buffer against a budget. The assertion is
+// evaluated when `OK` is referenced, so an oversized buffer fails the build.
+struct BufferBudget<T, const CAPACITY: usize, const MAX_BYTES: usize>(PhantomData<T>);
+
+impl<T, const CAPACITY: usize, const MAX_BYTES: usize> BufferBudget<T, CAPACITY, MAX_BYTES> {
+    const OK: () = assert!(
+        size_of::<T>() * CAPACITY <= MAX_BYTES,
+        "inline queue buffer exceeds its size budget"
+    );
+}
+
+/// Fail the build if an inline buffer of `CAPACITY` elements of type `T` would take more than
+/// `MAX_BYTES` bytes. Meant to be evaluated in a constant, e.g.
+/// `const _: () = assert_buffer_size_le::<Sample, 1024, 16384>();`, to catch accidentally huge
+/// inline queues on embedded targets.
+pub const fn assert_buffer_size_le<T, const CAPACITY: usize, const MAX_BYTES: usize>() {
+    BufferBudget::<T, CAPACITY, MAX_BYTES>::OK
 }
 
 impl<T: Copy, const CAPACITY: usize> BasicTypedQueue<T, CAPACITY> {
     /// Create a new inline queue for the specified type and of the specified capacity.
     pub fn new() -> Self {
+        BasicTypedQueue::from_storage([MaybeUninit::uninit(); CAPACITY])
+    }
+
+    /// Create a new inline queue, failing the build if its buffer would take more than
+    /// `MAX_BYTES` bytes.
+    pub fn new_within_budget<const MAX_BYTES: usize>() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = BufferBudget::<T, CAPACITY, MAX_BYTES>::OK;
+        BasicTypedQueue::new()
+    }
+
+    /// Move every element into a new queue, leaving this one empty with its configuration intact.
+    pub fn take(&mut self) -> Self {
+        let mut taken = BasicTypedQueue::new();
+        self.swap_contents(&mut taken);
+        taken
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Copy> BoxedTypedQueue<T> {
+    /// Create a new heap-backed queue for the specified type and of the specified capacity.
+    pub fn with_capacity(capacity: usize) -> Self {
+        BasicTypedQueue::from_storage(crate::storage::boxed(capacity))
+    }
+}
+
+impl<'a, T: Copy> SliceTypedQueue<'a, T> {
+    /// Create a new queue over a caller-provided buffer. The capacity of the queue is the length
+    /// of the buffer, and any previous contents of the buffer are ignored.
+    pub fn from_slice(buffer: &'a mut [MaybeUninit<T>]) -> Self {
+        BasicTypedQueue::from_storage(buffer)
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> BasicTypedQueue<T, CAPACITY, S> {
+    /// Create a new empty queue over the given storage. The capacity of the queue is the capacity
+    /// of the storage.
+    pub fn from_storage(storage: S) -> Self {
         BasicTypedQueue {
             size: 0,
             head: 0,
             tail: 0,
-            buffer: [MaybeUninit::uninit(); CAPACITY],
+            soft_capacity: storage.capacity(),
+            overflow_policy: OverflowPolicy::Reject,
+            storage,
+            _marker: PhantomData,
         }
     }
 
+    /// Set the soft capacity: the normal limit on the number of elements, which pushes may exceed
+    /// in short bursts up to the hard capacity. Producers can check `is_above_soft_limit()` to
+    /// apply backpressure. Clamped to the hard capacity, which is also the default.
+    pub fn set_soft_capacity(&mut self, soft_capacity: usize) {
+        self.soft_capacity = min(soft_capacity, self.capacity());
+    }
+
+    /// Get the soft capacity of the queue.
+    pub fn soft_capacity(&self) -> usize {
+        self.soft_capacity
+    }
+
+    /// Check if the queue holds more elements than its soft capacity.
+    pub fn is_above_soft_limit(&self) -> bool {
+        self.size > self.soft_capacity
+    }
+
+    /// Set what `push()` and `push_ref()` do when the queue is full. Defaults to
+    /// `OverflowPolicy::Reject`; the overwriting pushes always evict regardless of the policy.
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow_policy = policy;
+    }
+
+    /// Get the policy applied by `push()` when the queue is full.
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
     /// Try to get an immutable reference to the oldest element in the queue.
     pub fn front(&self) -> Result<&T, QueueError> {
         if self.is_empty() {
             return Err(QueueError::QueueEmpty);
         }
 
-        Ok(unsafe { self.buffer[self.head].assume_init_ref() })
+        Ok(unsafe { self.slot(self.head).assume_init_ref() })
     }
 
     /// Try to get an immutable reference to the newest element in the queue.
@@ -39,205 +152,2155 @@ impl<T: Copy, const CAPACITY: usize> BasicTypedQueue<T, CAPACITY> {
             return Err(QueueError::QueueEmpty);
         }
 
-        let back_idx = (self.tail + CAPACITY - 1) % CAPACITY;
-        Ok(unsafe { self.buffer[back_idx].assume_init_ref() })
+        let back_idx = self.wrap(self.tail + self.capacity() - 1);
+        Ok(unsafe { self.slot(back_idx).assume_init_ref() })
     }
-}
 
-impl<T: Copy, const CAPACITY: usize> Default for BasicTypedQueue<T, CAPACITY> {
-    fn default() -> Self {
-        BasicTypedQueue::new()
+    /// Try to get an immutable reference to the `n`-th element from the front of the queue, where
+    /// `n` = 0 is the oldest element. Fails if the queue holds `n` or fewer elements.
+    pub fn peek_at(&self, n: usize) -> Result<&T, QueueError> {
+        self.get(n).ok_or(QueueError::QueueEmpty)
     }
-}
 
-impl<T: Copy, const CAPACITY: usize> TypedQueue<T> for BasicTypedQueue<T, CAPACITY> {
-    fn push(&mut self, input: T) -> Result<(), QueueError> {
-        self.push_ref(&input)
+    /// Get an immutable reference to the element at logical index `index`, where index 0 is the
+    /// front (oldest element) of the queue. Returns `None` if out of bounds.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.size {
+            return None;
+        }
+
+        let buffer_idx = self.wrap(self.head + index);
+        Some(unsafe { self.slot(buffer_idx).assume_init_ref() })
     }
 
-    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
-        self.push_ref_overwrite(&input)
+    /// Get a mutable reference to the element at logical index `index`, where index 0 is the
+    /// front (oldest element) of the queue. Returns `None` if out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.size {
+            return None;
+        }
+
+        let buffer_idx = self.wrap(self.head + index);
+        Some(unsafe { self.slot_mut(buffer_idx).assume_init_mut() })
     }
 
-    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+    /// Find the logical index of the first element, from the front, matching `predicate`.
+    pub fn position<P: FnMut(&T) -> bool>(&self, predicate: P) -> Option<usize> {
+        let (first, second) = self.as_slices();
+        first.iter().chain(second).position(predicate)
+    }
+
+    /// Push an element unless an element with the same key, as computed by `key_fn`, is already
+    /// queued, e.g. so that retried work items are not queued twice. Returns whether the element
+    /// was pushed. Fails if queue is full and the element would have been pushed, regardless of
+    /// the overflow policy.
+    pub fn push_unique_by<K: PartialEq, F: Fn(&T) -> K>(
+        &mut self,
+        key_fn: F,
+        input: T,
+    ) -> Result<bool, QueueError> {
+        let key = key_fn(&input);
+        if self.position(|element| key_fn(element) == key).is_some() {
+            return Ok(false);
+        }
+
         if self.is_full() {
             return Err(QueueError::QueueFull);
         }
+        unsafe { self.push_unchecked(input) };
+        Ok(true)
+    }
 
-        unsafe {
-            *(self.buffer[self.tail].as_mut_ptr()) = *input;
+    /// Push an element, or replace in place the queued element with the same key, as computed by
+    /// `key_fn`. Returns the replaced element, if any. Fails if queue is full and no element was
+    /// replaced, regardless of the overflow policy.
+    pub fn push_or_replace_by<K: PartialEq, F: Fn(&T) -> K>(
+        &mut self,
+        key_fn: F,
+        input: T,
+    ) -> Result<Option<T>, QueueError> {
+        let key = key_fn(&input);
+        if let Some(index) = self.position(|element| key_fn(element) == key) {
+            if let Some(element) = self.get_mut(index) {
+                return Ok(Some(mem::replace(element, input)));
+            }
         }
 
-        self.tail = (self.tail + 1) % CAPACITY;
-        self.size += 1;
+        if self.is_full() {
+            return Err(QueueError::QueueFull);
+        }
+        unsafe { self.push_unchecked(input) };
+        Ok(None)
+    }
 
-        Ok(())
+    /// Discard elements from the back of the queue so that at most the `n` oldest remain. Has no
+    /// effect if the queue holds `n` or fewer elements.
+    pub fn truncate(&mut self, n: usize) {
+        if n < self.size {
+            self.tail = self.wrap(self.head + n);
+            self.size = n;
+        }
     }
 
-    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
-        unsafe {
-            *(self.buffer[self.tail].as_mut_ptr()) = *input;
+    /// Discard elements from the front of the queue so that at most the `n` newest remain. Has no
+    /// effect if the queue holds `n` or fewer elements.
+    pub fn truncate_front(&mut self, n: usize) {
+        if n < self.size {
+            self.head = self.wrap(self.head + self.size - n);
+            self.size = n;
+        }
+    }
+
+    /// Keep only the elements matching `predicate`, preserving the FIFO order of the remaining
+    /// elements. The ring is compacted in place.
+    pub fn retain<P: FnMut(&T) -> bool>(&mut self, mut predicate: P) {
+        if self.size == 0 {
+            return;
         }
 
-        self.tail = (self.tail + 1) % CAPACITY;
-        self.size = min(self.size + 1, CAPACITY);
+        let mut kept = 0;
+        for n in 0..self.size {
+            let value = unsafe { self.slot(self.wrap(self.head + n)).assume_init() };
+            if predicate(&value) {
+                let index = self.wrap(self.head + kept);
+                self.slot_mut(index).write(value);
+                kept += 1;
+            }
+        }
+
+        self.tail = self.wrap(self.head + kept);
+        self.size = kept;
+    }
+
+    /// Discard every other element, starting with the second oldest, halving the number of
+    /// elements while keeping the oldest one. Returns the number of discarded elements.
+    pub fn decimate(&mut self) -> usize {
+        self.decimate_first(self.size)
+    }
+
+    // Decimate the `count` oldest elements only, leaving the newer ones untouched.
+    pub(crate) fn decimate_first(&mut self, count: usize) -> usize {
+        let before = self.size;
+        let mut index = 0;
+        self.retain(|_| {
+            index += 1;
+            index > count || index % 2 == 1
+        });
+
+        before - self.size
+    }
+
+    /// Push an element to the queue, overwriting the oldest element if the queue is full. Returns
+    /// the evicted element, if any, so that it can be counted or persisted instead of being lost
+    /// silently.
+    pub fn push_evict(&mut self, input: T) -> Result<Option<T>, QueueError> {
+        let evicted = if self.is_full() {
+            self.front().ok().copied()
+        } else {
+            None
+        };
+        self.push_overwrite(input)?;
+
+        Ok(evicted)
+    }
+
+    /// Push an element by letting `init` write it directly into the tail slot, so that large
+    /// elements need not be built on the caller's stack first. The element is committed if
+    /// `init` succeeds, and the queue is left unchanged if it fails. Fails if queue is full.
+    ///
+    /// # Safety
+    ///
+    /// If `init` returns `Ok`, it must have initialized the slot.
+    pub unsafe fn push_with<E, F>(&mut self, init: F) -> Result<(), E>
+    where
+        E: From<QueueError>,
+        F: FnOnce(&mut MaybeUninit<T>) -> Result<(), E>,
+    {
+        if self.is_full() {
+            return Err(QueueError::QueueFull.into());
+        }
+
+        init(self.slot_mut(self.tail))?;
+        self.commit_spare(1);
 
         Ok(())
     }
 
-    fn pop(&mut self) -> Result<T, QueueError> {
-        let mut value = MaybeUninit::<T>::uninit();
-        // We can safely pass the uninit value into `pop_ref()` because we know `pop_ref()` will
-        // not read the value, only copy into it. If `pop_ref()` fails, we never access `value`.
-        unsafe {
-            match self.pop_ref(value.assume_init_mut()) {
-                Ok(()) => Ok(value.assume_init()),
-                Err(e) => Err(e),
+    /// Reserve the tail slot so that a producer can fill it in place, e.g. as the target of a DMA
+    /// transfer or a deserializer. The element becomes visible once the returned guard is
+    /// published. Fails if queue is full.
+    pub fn claim(&mut self) -> Result<SlotGuard<'_, T, CAPACITY, S>, QueueError> {
+        if self.is_full() {
+            return Err(QueueError::QueueFull);
+        }
+
+        Ok(SlotGuard { queue: self })
+    }
+
+    /// Insert an element at logical index `index`, where index 0 is the front of the queue, so
+    /// that it is popped after the `index` elements ahead of it. Whichever side of the ring is
+    /// shorter is shifted to make room. Fails if queue is full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of elements in the queue.
+    pub fn insert(&mut self, index: usize, input: T) -> Result<(), QueueError> {
+        assert!(index <= self.size, "queue index out of bounds");
+        if self.is_full() {
+            return Err(QueueError::QueueFull);
+        }
+
+        if index < self.size - index {
+            // Shift the older elements one slot towards the front
+            self.head = self.wrap(self.head + self.capacity() - 1);
+            for n in 0..index {
+                let moved = unsafe { self.slot(self.wrap(self.head + n + 1)).assume_init() };
+                let to = self.wrap(self.head + n);
+                self.slot_mut(to).write(moved);
+            }
+        } else {
+            // Shift the newer elements one slot towards the back
+            for n in (index..self.size).rev() {
+                let moved = unsafe { self.slot(self.wrap(self.head + n)).assume_init() };
+                let to = self.wrap(self.head + n + 1);
+                self.slot_mut(to).write(moved);
             }
+            self.tail = self.wrap(self.tail + 1);
         }
+
+        let index = self.wrap(self.head + index);
+        self.slot_mut(index).write(input);
+        self.size += 1;
+
+        Ok(())
     }
 
-    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
-        if self.is_empty() {
-            return Err(QueueError::QueueEmpty);
+    /// Remove and return the element at logical index `index`, where index 0 is the front of the
+    /// queue. Whichever side of the ring is shorter is shifted to close the gap. Returns `None` if
+    /// out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.size {
+            return None;
         }
 
-        *output = unsafe { *(self.buffer[self.head].as_mut_ptr()) };
-        self.head = (self.head + 1) % CAPACITY;
+        let value = unsafe { self.slot(self.wrap(self.head + index)).assume_init() };
+        if index < self.size - 1 - index {
+            // Shift the older elements one slot towards the back
+            for n in (0..index).rev() {
+                let moved = unsafe { self.slot(self.wrap(self.head + n)).assume_init() };
+                let to = self.wrap(self.head + n + 1);
+                self.slot_mut(to).write(moved);
+            }
+            self.head = self.wrap(self.head + 1);
+        } else {
+            // Shift the newer elements one slot towards the front
+            for n in index + 1..self.size {
+                let moved = unsafe { self.slot(self.wrap(self.head + n)).assume_init() };
+                let to = self.wrap(self.head + n - 1);
+                self.slot_mut(to).write(moved);
+            }
+            self.tail = self.wrap(self.tail + self.capacity() - 1);
+        }
         self.size -= 1;
 
-        Ok(())
+        Some(value)
     }
 
-    fn is_full(&self) -> bool {
-        self.size() == CAPACITY
+    /// Swap the elements at logical indices `i` and `j`, where index 0 is the front of the queue.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.size && j < self.size, "queue index out of bounds");
+
+        let (i, j) = (self.wrap(self.head + i), self.wrap(self.head + j));
+        self.storage.as_mut_slice().swap(i, j);
     }
 
-    fn is_empty(&self) -> bool {
-        self.size() == 0
+    /// Rotate the FIFO order `n` places to the left, so that the `n` oldest elements move to the
+    /// back of the queue in their original order. At most `min(n, size - n)` elements are moved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the number of elements in the queue.
+    pub fn rotate_left(&mut self, n: usize) {
+        assert!(n <= self.size, "queue index out of bounds");
+
+        if n <= self.size - n {
+            self.rotate_front_to_back(n);
+        } else {
+            self.rotate_back_to_front(self.size - n);
+        }
     }
 
-    fn size(&self) -> usize {
-        self.size
+    /// Rotate the FIFO order `n` places to the right, so that the `n` newest elements move to the
+    /// front of the queue in their original order. At most `min(n, size - n)` elements are moved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the number of elements in the queue.
+    pub fn rotate_right(&mut self, n: usize) {
+        assert!(n <= self.size, "queue index out of bounds");
+
+        if n <= self.size - n {
+            self.rotate_back_to_front(n);
+        } else {
+            self.rotate_front_to_back(self.size - n);
+        }
     }
 
-    fn capacity(&self) -> usize {
-        CAPACITY
+    // Move the `n` oldest elements, one by one, from the front to the back of the queue.
+    fn rotate_front_to_back(&mut self, n: usize) {
+        for _ in 0..n {
+            let value = unsafe { self.slot(self.head).assume_init() };
+            self.head = self.wrap(self.head + 1);
+            self.slot_mut(self.tail).write(value);
+            self.tail = self.wrap(self.tail + 1);
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::BasicTypedQueue;
-    use crate::typed_queue::{QueueError, TypedQueue};
+    // Move the `n` newest elements, one by one, from the back to the front of the queue.
+    fn rotate_back_to_front(&mut self, n: usize) {
+        for _ in 0..n {
+            self.tail = self.wrap(self.tail + self.capacity() - 1);
+            let value = unsafe { self.slot(self.tail).assume_init() };
+            self.head = self.wrap(self.head + self.capacity() - 1);
+            self.slot_mut(self.head).write(value);
+        }
+    }
 
-    // Arbitrary queue size for tests
-    const SIZE: usize = 16;
+    /// Push an element to the queue by value without checking for free space.
+    ///
+    /// # Safety
+    ///
+    /// The queue must not be full. With debug assertions enabled this is checked and a violation
+    /// panics; release builds perform no check.
+    pub unsafe fn push_unchecked(&mut self, input: T) {
+        debug_assert!(!self.is_full(), "push_unchecked() called on a full queue");
 
-    #[test]
-    fn push_pop() {
-        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        self.slot_mut(self.tail).write(input);
+        self.tail = self.wrap(self.tail + 1);
+        self.size += 1;
+    }
 
-        for n in 0..SIZE {
-            assert!(queue.push(n as u32).is_ok())
+    /// Pop an element from the queue by value without checking for available elements.
+    ///
+    /// # Safety
+    ///
+    /// The queue must not be empty. With debug assertions enabled this is checked and a violation
+    /// panics; release builds perform no check.
+    pub unsafe fn pop_unchecked(&mut self) -> T {
+        debug_assert!(!self.is_empty(), "pop_unchecked() called on an empty queue");
+
+        let value = self.slot(self.head).assume_init();
+        self.head = self.wrap(self.head + 1);
+        self.size -= 1;
+        value
+    }
+
+    /// Push as many elements from `input` as fit, in order. Returns the number of elements
+    /// pushed. The elements are copied into the buffer in at most two blocks, one on each side of
+    /// its end, which is much faster than pushing them one by one when they are large.
+    pub fn push_slice(&mut self, input: &[T]) -> usize {
+        let count = min(input.len(), self.capacity() - self.size);
+        if count == 0 {
+            return 0;
         }
 
-        for n in 0..SIZE {
-            let output = queue.pop();
-            assert!(output.is_ok());
-            assert_eq!(output.unwrap(), n as u32);
+        let first = min(count, self.capacity() - self.tail);
+        let tail = self.tail;
+        let slots = self.storage.as_mut_slice();
+        // The destination slots are free and MaybeUninit<T> has the same layout as T
+        unsafe {
+            let slots = slots.as_mut_ptr() as *mut T;
+            ptr::copy_nonoverlapping(input.as_ptr(), slots.add(tail), first);
+            ptr::copy_nonoverlapping(input.as_ptr().add(first), slots, count - first);
         }
-    }
+        self.commit_spare(count);
 
-    #[test]
-    fn push_overwrite_pop() {
-        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        count
+    }
 
-        for n in 0..SIZE {
-            assert!(queue.push(n as u32).is_ok())
+    /// Pop elements into `output` until it is filled or the queue is empty. Returns the number of
+    /// elements popped. The elements are copied out of the buffer in at most two blocks.
+    pub fn pop_slice(&mut self, output: &mut [T]) -> usize {
+        let count = min(output.len(), self.size);
+        if count == 0 {
+            return 0;
         }
 
-        // Overwrite oldest element (0) with SIZE
-        assert!(queue.push_overwrite(SIZE as u32).is_ok());
+        let (first, second) = self.as_slices();
+        let from_first = min(count, first.len());
+        output[..from_first].copy_from_slice(&first[..from_first]);
+        output[from_first..count].copy_from_slice(&second[..count - from_first]);
 
-        let output = queue.pop();
-        assert!(output.is_ok());
-        assert_eq!(output.unwrap(), SIZE as u32);
+        self.head = self.wrap(self.head + count);
+        self.size -= count;
+        count
     }
 
-    #[test]
-    fn push_ref_pop_ref() {
-        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+    // Push an element before the oldest one, for double-ended use. Fails if queue is full.
+    pub(crate) fn push_front(&mut self, input: T) -> Result<(), QueueError> {
+        if self.is_full() {
+            return Err(QueueError::QueueFull);
+        }
 
-        for n in 0..SIZE {
-            assert!(queue.push_ref(&(n as u32)).is_ok())
+        self.head = self.wrap(self.head + self.capacity() - 1);
+        self.slot_mut(self.head).write(input);
+        self.size += 1;
+
+        Ok(())
+    }
+
+    // Pop the newest element, for double-ended use. Fails if queue is empty.
+    pub(crate) fn pop_back(&mut self) -> Result<T, QueueError> {
+        if self.is_empty() {
+            return Err(QueueError::QueueEmpty);
         }
 
-        for n in 0..SIZE {
-            let mut output: u32 = 0;
-            let res = queue.pop_ref(&mut output);
-            assert!(res.is_ok());
-            assert_eq!(output, n as u32);
+        self.tail = self.wrap(self.tail + self.capacity() - 1);
+        self.size -= 1;
+
+        Ok(unsafe { self.slot(self.tail).assume_init() })
+    }
+
+    /// Get the contents of the queue as a pair of slices in FIFO order. The second slice is only
+    /// non-empty when the contents wrap around the end of the underlying buffer.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (first, second) = self.slice_ranges();
+        let buffer = self.storage.as_slice();
+        // Elements within the ranges are initialized, and MaybeUninit<T> has the same layout as T
+        unsafe {
+            (
+                &*(&buffer[first] as *const [MaybeUninit<T>] as *const [T]),
+                &*(&buffer[second] as *const [MaybeUninit<T>] as *const [T]),
+            )
         }
     }
 
-    #[test]
-    fn push_ref_overwrite_pop() {
-        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+    /// Get the contents of the queue as a pair of mutable slices in FIFO order. The second slice
+    /// is only non-empty when the contents wrap around the end of the underlying buffer.
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (first, second) = self.slice_ranges();
+        let (low, high) = self.storage.as_mut_slice().split_at_mut(first.start);
+        unsafe {
+            (
+                &mut *(&mut high[..first.len()] as *mut [MaybeUninit<T>] as *mut [T]),
+                &mut *(&mut low[second] as *mut [MaybeUninit<T>] as *mut [T]),
+            )
+        }
+    }
 
-        for n in 0..SIZE {
-            assert!(queue.push_ref(&(n as u32)).is_ok())
+    // Buffer index ranges holding the contents of the queue, front first.
+    fn slice_ranges(&self) -> (Range<usize>, Range<usize>) {
+        let capacity = self.capacity();
+        if self.head + self.size <= capacity {
+            (self.head..self.head + self.size, 0..0)
+        } else {
+            (self.head..capacity, 0..self.head + self.size - capacity)
         }
+    }
 
-        // Overwrite oldest element (0) with SIZE
-        assert!(queue.push_ref_overwrite(&(SIZE as u32)).is_ok());
+    /// Exchange the contents of two queues, e.g. to hand everything queued so far to a consumer
+    /// while producers carry on with an empty buffer. Heap-backed buffers are swapped in O(1),
+    /// inline ones are copied. Each queue keeps its own soft capacity and overflow policy, with the
+    /// soft capacity clamped to the capacity of its new buffer.
+    pub fn swap_contents(&mut self, other: &mut Self) {
+        mem::swap(&mut self.storage, &mut other.storage);
+        mem::swap(&mut self.head, &mut other.head);
+        mem::swap(&mut self.tail, &mut other.tail);
+        mem::swap(&mut self.size, &mut other.size);
 
-        let mut output: u32 = 0;
-        let res = queue.pop_ref(&mut output);
-        assert!(res.is_ok());
-        assert_eq!(output, SIZE as u32);
+        self.soft_capacity = min(self.soft_capacity, self.capacity());
+        other.soft_capacity = min(other.soft_capacity, other.capacity());
     }
 
-    #[test]
-    fn wrap() {
-        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+    /// Touch every page of the free part of the buffer, so that first-use page faults of lazily
+    /// allocated storage (heap or memory-mapped) happen now instead of in the middle of real-time
+    /// operation. Queued elements are left untouched.
+    pub fn prefault(&mut self) {
+        let free = self.capacity() - self.size;
+        let first = min(free, self.capacity() - self.tail);
+        let tail = self.tail;
 
-        // push/pop half capacity to move head/tail to SIZE/2
-        for n in 0..SIZE / 2 {
-            assert!(queue.push(n as u32).is_ok());
+        let slots = self.storage.as_mut_slice();
+        storage::prefault(&mut slots[tail..tail + first]);
+        storage::prefault(&mut slots[..free - first]);
+    }
+
+    /// Get an iterator over the elements of the queue in FIFO order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.iter_first(self.size)
+    }
+
+    // Iterate over the `count` oldest elements in FIFO order.
+    pub(crate) fn iter_first(&self, count: usize) -> Iter<'_, T> {
+        let (first, second) = self.as_slices();
+        let split = min(count, first.len());
+        Iter {
+            first: first[..split].iter(),
+            second: second[..min(count - split, second.len())].iter(),
         }
+    }
 
-        for _ in 0..SIZE / 2 {
-            assert!(queue.pop().is_ok());
+    /// Get a value implementing `Display` that formats the elements in FIFO order, separated by
+    /// `separator`.
+    pub fn display_with<'a>(&'a self, separator: &'a str) -> DisplayWith<'a, T> {
+        DisplayWith {
+            iter: self.iter(),
+            separator,
         }
+    }
 
-        // Now perform full push/pop check to verify wrap logic
-        for n in 0..SIZE {
-            assert!(queue.push(n as u32).is_ok())
+    /// Get an iterator over all overlapping windows of `n` consecutive elements, in FIFO order.
+    /// Windows spanning the wrap-around point of the buffer are handled transparently.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn windows(&self, n: usize) -> Windows<'_, T> {
+        assert!(n > 0, "window size must be non-zero");
+
+        let (first, second) = self.as_slices();
+        Windows {
+            first,
+            second,
+            size: n,
+            start: 0,
         }
+    }
 
-        for n in 0..SIZE {
-            let output = queue.pop();
-            assert!(output.is_ok());
-            assert_eq!(output.unwrap(), n as u32);
+    /// Get an iterator popping up to `n` elements at a time, in FIFO order. Each chunk stays
+    /// readable for as long as the iterator is alive, since nothing can be pushed meanwhile.
+    /// Elements in chunks that are not reached before the iterator is dropped stay queued.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn drain_chunks(&mut self, n: usize) -> DrainChunks<'_, T, CAPACITY, S> {
+        assert!(n > 0, "chunk size must be non-zero");
+
+        DrainChunks {
+            queue: self,
+            size: n,
         }
     }
 
-    #[test]
-    fn front_back() {
-        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
-        assert_eq!(queue.front().unwrap_err(), QueueError::QueueEmpty);
-        assert_eq!(queue.back().unwrap_err(), QueueError::QueueEmpty);
+    /// Copy the elements of the queue into a new `Vec`, in FIFO order.
+    #[cfg(feature = "alloc")]
+    pub fn to_vec(&self) -> Vec<T> {
+        let (first, second) = self.as_slices();
+        let mut vec = Vec::with_capacity(self.size);
+        vec.extend_from_slice(first);
+        vec.extend_from_slice(second);
+        vec
+    }
 
-        const VALUE: u32 = 123;
-        assert!(queue.push(VALUE).is_ok());
-        assert_eq!(*queue.front().unwrap(), VALUE);
-        assert_eq!(*queue.back().unwrap(), VALUE);
+    // Write an element into the free slot `offset` positions past the newest element, without
+    // making it part of the queue. Used to stage elements before committing them as a group.
+    #[cfg(feature = "threads")]
+    pub(crate) fn write_spare(&mut self, offset: usize, input: T) {
+        self.spare_mut(offset).write(input);
+    }
 
-        // Borrow checker prevents mutating while holding reference
-        let front = queue.front().unwrap();
-        // let res = queue.pop();  // not allowed
-        println!("{}", front);
+    // Get the slot `offset` positions past the tail, to stage an element in place.
+    pub(crate) fn spare_mut(&mut self, offset: usize) -> &mut MaybeUninit<T> {
+        debug_assert!(offset < self.capacity() - self.size);
+
+        let index = self.wrap(self.tail + offset);
+        self.slot_mut(index)
+    }
+
+    // Get the free slots past the tail, up to the end of the buffer or the oldest element,
+    // whichever comes first, to stage elements in place. Empty if the queue is full.
+    #[cfg(feature = "bytes")]
+    pub(crate) fn spare_contiguous_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        let capacity = self.capacity();
+        let end = min(self.tail + capacity - self.size, capacity);
+        let start = min(self.tail, end);
+        &mut self.storage.as_mut_slice()[start..end]
+    }
+
+    // Append the next `count` staged elements to the queue.
+    pub(crate) fn commit_spare(&mut self, count: usize) {
+        debug_assert!(count <= self.capacity() - self.size);
+        if count == 0 {
+            return;
+        }
+
+        self.tail = self.wrap(self.tail + count);
+        self.size += count;
+    }
+
+    // Map a position that may run past the end of the buffer back into it.
+    fn wrap(&self, index: usize) -> usize {
+        index % self.capacity()
+    }
+
+    fn slot(&self, index: usize) -> &MaybeUninit<T> {
+        &self.storage.as_slice()[index]
+    }
+
+    fn slot_mut(&mut self, index: usize) -> &mut MaybeUninit<T> {
+        &mut self.storage.as_mut_slice()[index]
+    }
+}
+
+impl<T: Copy + PartialEq, const CAPACITY: usize, S: Storage<T>> BasicTypedQueue<T, CAPACITY, S> {
+    /// Check if the queue contains an element equal to `value`.
+    pub fn contains(&self, value: &T) -> bool {
+        let (first, second) = self.as_slices();
+        first.contains(value) || second.contains(value)
+    }
+
+    /// Push an element to the queue by reference, unless it is equal to the current back element.
+    /// Returns whether the element was pushed. Fails if queue is full and the element would have
+    /// been pushed.
+    pub fn push_dedup(&mut self, input: &T) -> Result<bool, QueueError> {
+        if self.back().is_ok_and(|back| back == input) {
+            return Ok(false);
+        }
+
+        self.push_ref(input)?;
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> From<BasicTypedQueue<T, CAPACITY, S>>
+    for Vec<T>
+{
+    fn from(queue: BasicTypedQueue<T, CAPACITY, S>) -> Self {
+        queue.to_vec()
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> Index<usize>
+    for BasicTypedQueue<T, CAPACITY, S>
+{
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("queue index out of bounds")
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> IndexMut<usize>
+    for BasicTypedQueue<T, CAPACITY, S>
+{
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_mut(index).expect("queue index out of bounds")
+    }
+}
+
+// Tail slot reserved by `claim()`, to be filled in place. Dropping the guard without publishing
+// leaves the queue unchanged.
+pub struct SlotGuard<'a, T: Copy, const CAPACITY: usize, S: Storage<T>> {
+    queue: &'a mut BasicTypedQueue<T, CAPACITY, S>,
+}
+
+impl<'a, T: Copy, const CAPACITY: usize, S: Storage<T>> SlotGuard<'a, T, CAPACITY, S> {
+    /// Get the reserved slot, to be initialized in place.
+    pub fn as_uninit_mut(&mut self) -> &mut MaybeUninit<T> {
+        self.queue.spare_mut(0)
+    }
+
+    /// Get a raw pointer to the reserved slot.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.as_uninit_mut().as_mut_ptr()
+    }
+
+    /// Write an element into the slot and make it visible.
+    pub fn write(mut self, input: T) {
+        self.as_uninit_mut().write(input);
+        unsafe { self.publish() }
+    }
+
+    /// Make the element in the slot visible.
+    ///
+    /// # Safety
+    ///
+    /// The slot must have been fully initialized.
+    pub unsafe fn publish(self) {
+        self.queue.commit_spare(1);
+    }
+}
+
+impl<'a, T: Copy, const CAPACITY: usize, S: Storage<T>> fmt::Debug
+    for SlotGuard<'a, T, CAPACITY, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlotGuard").finish_non_exhaustive()
+    }
+}
+
+// Iterator over the elements of a queue in FIFO order, walking the two contiguous parts of the
+// ring in turn.
+pub struct Iter<'a, T> {
+    first: slice::Iter<'a, T>,
+    second: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Clone for Iter<'a, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            first: self.first.clone(),
+            second: self.second.clone(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.first.next().or_else(|| self.second.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.first.len() + self.second.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.second.next_back().or_else(|| self.first.next_back())
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+// View of consecutive queue elements, made of up to two contiguous parts of the ring.
+#[derive(Copy, Clone)]
+pub struct Window<'a, T> {
+    first: &'a [T],
+    second: &'a [T],
+}
+
+impl<'a, T> Window<'a, T> {
+    /// Get the number of elements in the window.
+    pub fn len(&self) -> usize {
+        self.first.len() + self.second.len()
+    }
+
+    /// Check if the window is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the element at index `index` of the window, or `None` if out of bounds.
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        match index.checked_sub(self.first.len()) {
+            None => self.first.get(index),
+            Some(index) => self.second.get(index),
+        }
+    }
+
+    /// Get the window as a pair of slices. The second slice is only non-empty when the window
+    /// spans the wrap-around point of the buffer.
+    pub fn as_slices(&self) -> (&'a [T], &'a [T]) {
+        (self.first, self.second)
+    }
+
+    /// Get an iterator over the elements of the window.
+    pub fn iter(&self) -> Iter<'a, T> {
+        Iter {
+            first: self.first.iter(),
+            second: self.second.iter(),
+        }
+    }
+}
+
+impl<'a, T> Index<usize> for Window<'a, T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("window index out of bounds")
+    }
+}
+
+// Iterator over overlapping windows of queue elements in FIFO order.
+#[derive(Clone)]
+pub struct Windows<'a, T> {
+    first: &'a [T],
+    second: &'a [T],
+    size: usize,
+    start: usize, // logical index of the first element of the next window
+}
+
+impl<'a, T> Iterator for Windows<'a, T> {
+    type Item = Window<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, end) = (self.start, self.start + self.size);
+        let split = self.first.len();
+        if end > split + self.second.len() {
+            return None;
+        }
+        self.start += 1;
+
+        Some(if end <= split {
+            Window {
+                first: &self.first[start..end],
+                second: &[],
+            }
+        } else if start >= split {
+            Window {
+                first: &self.second[start - split..end - split],
+                second: &[],
+            }
+        } else {
+            Window {
+                first: &self.first[start..],
+                second: &self.second[..end - split],
+            }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.first.len() + self.second.len() + 1).saturating_sub(self.start + self.size);
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Windows<'a, T> {}
+
+impl<'a, T> FusedIterator for Windows<'a, T> {}
+
+// Iterator popping chunks of elements from the front of a queue.
+pub struct DrainChunks<'a, T: Copy, const CAPACITY: usize, S: Storage<T>> {
+    queue: &'a mut BasicTypedQueue<T, CAPACITY, S>,
+    size: usize,
+}
+
+impl<'a, T: Copy, const CAPACITY: usize, S: Storage<T>> Iterator
+    for DrainChunks<'a, T, CAPACITY, S>
+{
+    type Item = Window<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let count = min(self.size, self.queue.size);
+        if count == 0 {
+            return None;
+        }
+
+        let (first, second) = self.queue.as_slices();
+        let split = min(count, first.len());
+        // The queue is exclusively borrowed for 'a and popping only moves the head, so the popped
+        // slots are neither moved nor overwritten while the chunk is alive
+        let chunk = unsafe {
+            Window {
+                first: &*(&first[..split] as *const [T]),
+                second: &*(&second[..count - split] as *const [T]),
+            }
+        };
+
+        self.queue.head = self.queue.wrap(self.queue.head + count);
+        self.queue.size -= count;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.queue.size.div_ceil(self.size);
+        (len, Some(len))
+    }
+}
+
+impl<'a, T: Copy, const CAPACITY: usize, S: Storage<T>> ExactSizeIterator
+    for DrainChunks<'a, T, CAPACITY, S>
+{
+}
+
+impl<'a, T: Copy, const CAPACITY: usize, S: Storage<T>> FusedIterator
+    for DrainChunks<'a, T, CAPACITY, S>
+{
+}
+
+// Owning iterator popping the elements of a queue in FIFO order.
+pub struct IntoIter<T: Copy, const CAPACITY: usize, S: Storage<T>> {
+    queue: BasicTypedQueue<T, CAPACITY, S>,
+}
+
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> Iterator for IntoIter<T, CAPACITY, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.queue.pop().ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.queue.size(), Some(self.queue.size()))
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> DoubleEndedIterator
+    for IntoIter<T, CAPACITY, S>
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let value = *self.queue.back().ok()?;
+        self.queue.truncate(self.queue.size() - 1);
+        Some(value)
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> ExactSizeIterator for IntoIter<T, CAPACITY, S> {}
+
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> FusedIterator for IntoIter<T, CAPACITY, S> {}
+
+impl<'a, T: Copy, const CAPACITY: usize, S: Storage<T>> IntoIterator
+    for &'a BasicTypedQueue<T, CAPACITY, S>
+{
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> IntoIterator
+    for BasicTypedQueue<T, CAPACITY, S>
+{
+    type Item = T;
+    type IntoIter = IntoIter<T, CAPACITY, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { queue: self }
+    }
+}
+
+// Formats the queued elements from front to back, separated by commas.
+impl<T: Copy + fmt::Display, const CAPACITY: usize, S: Storage<T>> fmt::Display
+    for BasicTypedQueue<T, CAPACITY, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_separated(f, self.iter(), ", ")
+    }
+}
+
+// Formatter for the elements of a queue with a custom separator.
+#[derive(Clone)]
+pub struct DisplayWith<'a, T> {
+    iter: Iter<'a, T>,
+    separator: &'a str,
+}
+
+impl<'a, T: fmt::Display> fmt::Display for DisplayWith<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_separated(f, self.iter.clone(), self.separator)
+    }
+}
+
+// Write each element with its own formatting options, with `separator` between elements.
+pub(crate) fn write_separated<'a, T: fmt::Display + 'a>(
+    f: &mut fmt::Formatter<'_>,
+    iter: impl Iterator<Item = &'a T>,
+    separator: &str,
+) -> fmt::Result {
+    for (n, value) in iter.enumerate() {
+        if n > 0 {
+            f.write_str(separator)?;
+        }
+        fmt::Display::fmt(value, f)?;
+    }
+    Ok(())
+}
+
+// Only the queued elements are printed, from front to back.
+impl<T: Copy + fmt::Debug, const CAPACITY: usize, S: Storage<T>> fmt::Debug
+    for BasicTypedQueue<T, CAPACITY, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize> Default for BasicTypedQueue<T, CAPACITY> {
+    fn default() -> Self {
+        BasicTypedQueue::new()
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> TypedQueue<T>
+    for BasicTypedQueue<T, CAPACITY, S>
+{
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref(&input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref_overwrite(&input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        if self.is_full() {
+            match self.overflow_policy {
+                OverflowPolicy::Reject => return Err(QueueError::QueueFull),
+                OverflowPolicy::OverwriteOldest if self.capacity() > 0 => {
+                    // Evict the oldest element; its slot is the one the tail is about to reuse
+                    self.head = self.wrap(self.head + 1);
+                    self.size -= 1;
+                }
+                OverflowPolicy::OverwriteOldest => return Err(QueueError::QueueFull),
+                OverflowPolicy::DropNewest => return Ok(()),
+                OverflowPolicy::Decimate if self.capacity() > 1 => {
+                    self.decimate();
+                }
+                OverflowPolicy::Decimate => return Err(QueueError::QueueFull),
+            }
+        }
+
+        self.slot_mut(self.tail).write(*input);
+        self.tail = self.wrap(self.tail + 1);
+        self.size += 1;
+
+        Ok(())
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        if self.capacity() == 0 {
+            return Err(QueueError::QueueFull);
+        }
+
+        if self.is_full() {
+            // Evict the oldest element; its slot is the one the tail is about to reuse
+            self.head = self.wrap(self.head + 1);
+            self.size -= 1;
+        }
+
+        self.push_ref(input)
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        let mut value = MaybeUninit::<T>::uninit();
+        self.pop_into_uninit(&mut value)?;
+        // `pop_into_uninit()` only succeeds after writing the value
+        Ok(unsafe { value.assume_init() })
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        *output = self.pop()?;
+        Ok(())
+    }
+
+    fn pop_into_uninit(&mut self, output: &mut MaybeUninit<T>) -> Result<(), QueueError> {
+        if self.is_empty() {
+            return Err(QueueError::QueueEmpty);
+        }
+
+        *output = *self.slot(self.head);
+        self.head = self.wrap(self.head + 1);
+        self.size -= 1;
+
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        self.head = 0;
+        self.tail = 0;
+        self.size = 0;
+
+        Ok(())
+    }
+
+    fn is_full(&self) -> bool {
+        self.size() == self.capacity()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn capacity(&self) -> usize {
+        self.storage.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BasicTypedQueue;
+    #[cfg(feature = "alloc")]
+    use super::BoxedTypedQueue;
+    #[cfg(feature = "mmap")]
+    use crate::storage::MmapStorage;
+    use crate::typed_queue::{OverflowPolicy, QueueError, TypedQueue};
+    use std::mem::MaybeUninit;
+    use std::num::NonZeroU32;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    // Get an empty queue whose head and tail sit halfway through the buffer, so that the contents
+    // wrap around its end once more than `SIZE / 2` elements are pushed.
+    fn wrapped_queue<T: Copy + Default>() -> BasicTypedQueue<T, SIZE> {
+        let mut queue = BasicTypedQueue::default();
+        for _ in 0..SIZE / 2 {
+            assert!(queue.push(T::default()).is_ok());
+            assert!(queue.pop().is_ok());
+        }
+        queue
+    }
+
+    #[test]
+    fn push_pop() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok())
+        }
+
+        for n in 0..SIZE {
+            let output = queue.pop();
+            assert!(output.is_ok());
+            assert_eq!(output.unwrap(), n as u32);
+        }
+    }
+
+    #[test]
+    fn push_overwrite_pop() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok())
+        }
+
+        // Overwrite oldest element (0) with SIZE
+        assert!(queue.push_overwrite(SIZE as u32).is_ok());
+        assert!(queue.is_full());
+
+        // Survivors keep their FIFO order, followed by the new element
+        for n in 1..=SIZE {
+            let output = queue.pop();
+            assert!(output.is_ok());
+            assert_eq!(output.unwrap(), n as u32);
+        }
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn push_ref_pop_ref() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+
+        for n in 0..SIZE {
+            assert!(queue.push_ref(&(n as u32)).is_ok())
+        }
+
+        for n in 0..SIZE {
+            let mut output: u32 = 0;
+            let res = queue.pop_ref(&mut output);
+            assert!(res.is_ok());
+            assert_eq!(output, n as u32);
+        }
+    }
+
+    #[test]
+    fn push_ref_overwrite_pop() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+
+        for n in 0..SIZE {
+            assert!(queue.push_ref(&(n as u32)).is_ok())
+        }
+
+        // Overwrite oldest elements (0 and 1) with SIZE and SIZE + 1
+        assert!(queue.push_ref_overwrite(&(SIZE as u32)).is_ok());
+        assert!(queue.push_ref_overwrite(&(SIZE as u32 + 1)).is_ok());
+
+        // Survivors keep their FIFO order, followed by the new elements
+        for n in 2..SIZE + 2 {
+            let mut output: u32 = 0;
+            let res = queue.pop_ref(&mut output);
+            assert!(res.is_ok());
+            assert_eq!(output, n as u32);
+        }
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn wrap() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+
+        // push/pop half capacity to move head/tail to SIZE/2
+        for n in 0..SIZE / 2 {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        for _ in 0..SIZE / 2 {
+            assert!(queue.pop().is_ok());
+        }
+
+        // Now perform full push/pop check to verify wrap logic
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok())
+        }
+
+        for n in 0..SIZE {
+            let output = queue.pop();
+            assert!(output.is_ok());
+            assert_eq!(output.unwrap(), n as u32);
+        }
+    }
+
+    #[test]
+    fn front_back() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.front().unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(queue.back().unwrap_err(), QueueError::QueueEmpty);
+
+        const VALUE: u32 = 123;
+        assert!(queue.push(VALUE).is_ok());
+        assert_eq!(*queue.front().unwrap(), VALUE);
+        assert_eq!(*queue.back().unwrap(), VALUE);
+
+        // Borrow checker prevents mutating while holding reference
+        let front = queue.front().unwrap();
+        // let res = queue.pop();  // not allowed
+        println!("{}", front);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn to_vec() {
+        let mut queue = wrapped_queue::<u32>();
+        assert!(queue.to_vec().is_empty());
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        let expected: Vec<u32> = (0..SIZE as u32).collect();
+        assert_eq!(queue.to_vec(), expected);
+        assert_eq!(Vec::from(queue), expected);
+    }
+
+    #[test]
+    fn as_slices() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.as_slices(), (&[][..], &[][..]));
+
+        for n in 0..SIZE / 2 {
+            assert!(queue.push(n as u32).is_ok());
+        }
+        let expected: Vec<u32> = (0..SIZE as u32 / 2).collect();
+        assert_eq!(queue.as_slices(), (&expected[..], &[][..]));
+
+        // Pop and push so that the contents wrap around the end of the buffer
+        for n in 0..SIZE / 2 {
+            assert!(queue.pop().is_ok());
+            assert!(queue.push((SIZE / 2 + n) as u32).is_ok());
+        }
+        for n in SIZE..SIZE + SIZE / 2 {
+            assert!(queue.push(n as u32).is_ok());
+        }
+        let (first, second) = queue.as_slices();
+        assert_eq!(
+            first,
+            &(SIZE as u32 / 2..SIZE as u32).collect::<Vec<_>>()[..]
+        );
+        assert_eq!(
+            second,
+            &(SIZE as u32..SIZE as u32 * 3 / 2).collect::<Vec<_>>()[..]
+        );
+
+        let (first, second) = queue.as_mut_slices();
+        first[0] = 0;
+        second[0] = 0;
+        assert_eq!(queue.pop().unwrap(), 0);
+        for _ in 1..SIZE / 2 {
+            assert!(queue.pop().is_ok());
+        }
+        assert_eq!(queue.pop().unwrap(), 0);
+    }
+
+    #[test]
+    fn push_pop_slice() {
+        // Large elements, for which bulk copies matter most
+        #[derive(Debug, Copy, Clone, PartialEq)]
+        struct Record([u64; 8]);
+
+        let input: Vec<Record> = (0..SIZE as u64 * 2).map(|n| Record([n; 8])).collect();
+        let mut queue = BasicTypedQueue::<Record, SIZE>::default();
+        assert_eq!(queue.push_slice(&input[..SIZE / 2]), SIZE / 2);
+        assert_eq!(queue.pop().unwrap(), input[0]);
+
+        // Only as many elements as fit are pushed, wrapping around the end of the buffer
+        assert_eq!(queue.push_slice(&input[SIZE / 2..]), SIZE / 2 + 1);
+        assert_eq!(queue.push_slice(&input[..1]), 0);
+        assert_eq!(queue.size(), SIZE);
+
+        let mut output = [Record([0; 8]); SIZE + 1];
+        assert_eq!(queue.pop_slice(&mut output[..3]), 3);
+        assert_eq!(&output[..3], &input[1..4]);
+        assert_eq!(queue.pop_slice(&mut output), SIZE - 3);
+        assert_eq!(&output[..SIZE - 3], &input[4..SIZE + 1]);
+        assert_eq!(queue.pop_slice(&mut output), 0);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn peek_at() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.peek_at(0).unwrap_err(), QueueError::QueueEmpty);
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        assert_eq!(*queue.peek_at(0).unwrap(), 0);
+        assert_eq!(*queue.peek_at(SIZE - 1).unwrap(), (SIZE - 1) as u32);
+        assert_eq!(queue.peek_at(SIZE).unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(queue.size(), SIZE);
+    }
+
+    #[test]
+    fn get_index() {
+        let mut queue = wrapped_queue::<u32>();
+        assert_eq!(queue.get(0), None);
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        for n in 0..SIZE {
+            assert_eq!(queue.get(n), Some(&(n as u32)));
+            assert_eq!(queue[n], n as u32);
+        }
+        assert_eq!(queue.get(SIZE), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "queue index out of bounds")]
+    fn index_out_of_bounds() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        assert!(queue.push(0).is_ok());
+        let _ = queue[1];
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.get_mut(0), None);
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        *queue.get_mut(1).unwrap() = 100;
+        queue[2] += 100;
+        assert_eq!(queue.get_mut(SIZE), None);
+
+        assert_eq!(queue.pop().unwrap(), 0);
+        assert_eq!(queue.pop().unwrap(), 100);
+        assert_eq!(queue.pop().unwrap(), 102);
+    }
+
+    #[test]
+    fn contains_position() {
+        let mut queue = wrapped_queue::<u32>();
+        assert!(!queue.contains(&0));
+        assert_eq!(queue.position(|_| true), None);
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32 * 10).is_ok());
+        }
+
+        assert!(queue.contains(&0));
+        assert!(queue.contains(&((SIZE as u32 - 1) * 10)));
+        assert!(!queue.contains(&5));
+        assert_eq!(queue.position(|&value| value >= 100), Some(10));
+        assert_eq!(queue.position(|&value| value == 5), None);
+    }
+
+    #[test]
+    fn truncate() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        queue.truncate(SIZE);
+        assert_eq!(queue.size(), SIZE);
+
+        queue.truncate(4);
+        assert_eq!(queue.size(), 4);
+        assert_eq!(*queue.back().unwrap(), 3);
+
+        // Freed slots at the back are reused
+        assert!(queue.push(100).is_ok());
+        assert_eq!(*queue.back().unwrap(), 100);
+        for n in 0..4 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert_eq!(queue.pop().unwrap(), 100);
+    }
+
+    #[test]
+    fn truncate_front() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        queue.truncate_front(4);
+        assert_eq!(queue.size(), 4);
+        for n in SIZE - 4..SIZE {
+            assert_eq!(queue.pop().unwrap(), n as u32);
+        }
+
+        queue.truncate_front(0);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn soft_capacity() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.soft_capacity(), SIZE);
+        queue.set_soft_capacity(SIZE * 2);
+        assert_eq!(queue.soft_capacity(), SIZE);
+
+        queue.set_soft_capacity(SIZE / 2);
+        for n in 0..SIZE {
+            assert_eq!(queue.is_above_soft_limit(), n > SIZE / 2);
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        // Bursts may use the whole hard capacity
+        assert!(queue.is_above_soft_limit());
+        assert_eq!(queue.push(0).unwrap_err(), QueueError::QueueFull);
+    }
+
+    #[test]
+    fn push_evict() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 {
+            assert_eq!(queue.push_evict(n).unwrap(), None);
+        }
+
+        assert_eq!(queue.push_evict(100).unwrap(), Some(0));
+        assert_eq!(queue.push_evict(101).unwrap(), Some(1));
+        for n in 2..SIZE as u32 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert_eq!(queue.pop().unwrap(), 100);
+        assert_eq!(queue.pop().unwrap(), 101);
+    }
+
+    #[test]
+    fn push_with() {
+        let mut queue = wrapped_queue::<[u32; 4]>();
+
+        for n in 0..SIZE as u32 {
+            let res = unsafe {
+                queue.push_with(|slot| {
+                    slot.write([n; 4]);
+                    Ok::<(), QueueError>(())
+                })
+            };
+            assert!(res.is_ok());
+        }
+        let res = unsafe { queue.push_with(|_| Ok::<(), QueueError>(())) };
+        assert_eq!(res.unwrap_err(), QueueError::QueueFull);
+
+        // A failed initialization leaves the queue unchanged
+        assert!(queue.pop().is_ok());
+        let res = unsafe { queue.push_with(|_| Err(QueueError::WouldBlock)) };
+        assert_eq!(res.unwrap_err(), QueueError::WouldBlock);
+        assert_eq!(queue.size(), SIZE - 1);
+
+        for n in 1..SIZE as u32 {
+            assert_eq!(queue.pop().unwrap(), [n; 4]);
+        }
+    }
+
+    #[test]
+    fn claim_publish() {
+        let mut queue = wrapped_queue::<u32>();
+
+        // Abandoned claims leave no trace
+        assert!(queue.claim().is_ok());
+        assert!(queue.is_empty());
+
+        for n in 0..SIZE as u32 - 1 {
+            let mut slot = queue.claim().unwrap();
+            unsafe {
+                slot.as_mut_ptr().write(n);
+                slot.publish();
+            }
+        }
+        queue.claim().unwrap().write(100);
+        assert_eq!(queue.claim().unwrap_err(), QueueError::QueueFull);
+
+        for n in 0..SIZE as u32 - 1 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert_eq!(queue.pop().unwrap(), 100);
+    }
+
+    #[test]
+    fn swap_contents() {
+        let mut queue = wrapped_queue::<u32>();
+        let mut other = BasicTypedQueue::<u32, SIZE>::default();
+
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(other.push(100).is_ok());
+
+        queue.swap_contents(&mut other);
+        assert_eq!(queue.size(), 1);
+        assert_eq!(queue.pop().unwrap(), 100);
+        for n in 0..SIZE as u32 {
+            assert_eq!(other.pop().unwrap(), n);
+        }
+        assert!(other.is_empty());
+    }
+
+    #[test]
+    fn take() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        queue.set_overflow_policy(OverflowPolicy::DropNewest);
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+
+        let mut taken = queue.take();
+        assert!(queue.is_empty());
+        assert_eq!(queue.overflow_policy(), OverflowPolicy::DropNewest);
+        for n in 0..SIZE as u32 {
+            assert_eq!(taken.pop().unwrap(), n);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn swap_contents_boxed() {
+        let mut queue = BoxedTypedQueue::<u32>::with_capacity(SIZE);
+        let mut other = BoxedTypedQueue::<u32>::with_capacity(SIZE / 2);
+        assert!(queue.push(1).is_ok());
+
+        queue.swap_contents(&mut other);
+        assert_eq!(
+            (queue.capacity(), queue.soft_capacity()),
+            (SIZE / 2, SIZE / 2)
+        );
+        assert_eq!((other.capacity(), other.soft_capacity()), (SIZE, SIZE / 2));
+        assert!(queue.is_empty());
+        assert_eq!(other.pop().unwrap(), 1);
+    }
+
+    #[test]
+    fn overflow_policy() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.overflow_policy(), OverflowPolicy::Reject);
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert_eq!(queue.push(100).unwrap_err(), QueueError::QueueFull);
+
+        // Newest elements are discarded without failing
+        queue.set_overflow_policy(OverflowPolicy::DropNewest);
+        assert!(queue.push(100).is_ok());
+        assert!(queue.push_ref(&101).is_ok());
+        assert_eq!(queue.size(), SIZE);
+        assert_eq!(queue[SIZE - 1], SIZE as u32 - 1);
+
+        // Oldest elements are evicted, keeping FIFO order
+        queue.set_overflow_policy(OverflowPolicy::OverwriteOldest);
+        assert!(queue.push(100).is_ok());
+        assert!(queue.push_ref(&101).is_ok());
+        for n in 2..SIZE as u32 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert_eq!(queue.pop().unwrap(), 100);
+        assert_eq!(queue.pop().unwrap(), 101);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn decimate() {
+        let mut queue = wrapped_queue::<u32>();
+        queue.set_overflow_policy(OverflowPolicy::Decimate);
+
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+
+        // Every odd element is discarded to make room, keeping the span from 0 onwards
+        assert!(queue.push(100).is_ok());
+        assert_eq!(queue.size(), SIZE / 2 + 1);
+        for n in 0..SIZE as u32 / 2 {
+            assert_eq!(queue[n as usize], n * 2);
+        }
+        assert_eq!(queue[SIZE / 2], 100);
+
+        assert_eq!(queue.decimate(), SIZE / 4);
+        assert_eq!((queue[0], queue[1], queue[2]), (0, 4, 8));
+
+        // A single slot cannot be decimated
+        let mut queue = BasicTypedQueue::<u32, 1>::default();
+        queue.set_overflow_policy(OverflowPolicy::Decimate);
+        assert!(queue.push(1).is_ok());
+        assert_eq!(queue.push(2).unwrap_err(), QueueError::QueueFull);
+
+        // Nor can an empty zero-capacity queue
+        let mut queue = BasicTypedQueue::<u32, 0>::new();
+        assert_eq!(queue.decimate(), 0);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn retain() {
+        let mut queue = wrapped_queue::<u32>();
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        queue.retain(|&value| value % 3 == 0);
+        assert_eq!(queue.size(), 6);
+        for n in (0..SIZE as u32).step_by(3) {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert!(queue.is_empty());
+
+        // Freed slots are reused
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+        queue.retain(|_| false);
+        assert!(queue.is_empty());
+
+        // An empty zero-capacity queue is left alone
+        let mut queue = BasicTypedQueue::<u32, 0>::new();
+        queue.retain(|_| true);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn remove() {
+        let mut queue = wrapped_queue::<u32>();
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+        assert_eq!(queue.remove(SIZE), None);
+
+        // Near the front, then near the back
+        assert_eq!(queue.remove(2), Some(2));
+        assert_eq!(queue.remove(SIZE - 3), Some(SIZE as u32 - 2));
+        assert_eq!(queue.size(), SIZE - 2);
+
+        let expected = (0..SIZE as u32).filter(|&n| n != 2 && n != SIZE as u32 - 2);
+        for n in expected {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+
+        // Freed slots are reused
+        assert!(queue.push(100).is_ok());
+        assert_eq!(queue.remove(0), Some(100));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn insert() {
+        let mut queue = wrapped_queue::<u32>();
+
+        for n in 0..SIZE as u32 - 3 {
+            assert!(queue.push(n).is_ok());
+        }
+
+        // At the front, near the front, and at the back
+        assert!(queue.insert(0, 100).is_ok());
+        assert!(queue.insert(2, 101).is_ok());
+        assert!(queue.insert(SIZE - 1, 102).is_ok());
+        assert!(queue.is_full());
+        assert_eq!(queue.insert(0, 103).unwrap_err(), QueueError::QueueFull);
+
+        assert_eq!(queue.pop().unwrap(), 100);
+        assert_eq!(queue.pop().unwrap(), 0);
+        assert_eq!(queue.pop().unwrap(), 101);
+        for n in 1..SIZE as u32 - 3 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert_eq!(queue.pop().unwrap(), 102);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "queue index out of bounds")]
+    fn insert_out_of_bounds() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        let _ = queue.insert(1, 0);
+    }
+
+    #[test]
+    fn swap() {
+        let mut queue = wrapped_queue::<u32>();
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        queue.swap(0, SIZE - 1);
+        queue.swap(3, 3);
+        assert_eq!(queue[0], SIZE as u32 - 1);
+        assert_eq!(queue[3], 3);
+        assert_eq!(queue[SIZE - 1], 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "queue index out of bounds")]
+    fn swap_out_of_bounds() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        assert!(queue.push(0).is_ok());
+        queue.swap(0, 1);
+    }
+
+    #[test]
+    fn rotate() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+
+        // Move head/tail so that the contents wrap around the end of the buffer
+        for n in 0..SIZE - 2 {
+            assert!(queue.push(n as u32).is_ok());
+            assert!(queue.pop().is_ok());
+        }
+
+        for n in 0..10 {
+            assert!(queue.push(n).is_ok());
+        }
+        let order =
+            |queue: &BasicTypedQueue<u32, SIZE>| -> [u32; 10] { std::array::from_fn(|n| queue[n]) };
+
+        queue.rotate_left(3);
+        assert_eq!(order(&queue), [3, 4, 5, 6, 7, 8, 9, 0, 1, 2]);
+        queue.rotate_left(8);
+        assert_eq!(order(&queue), [1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+        queue.rotate_right(1);
+        assert_eq!(order(&queue), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        queue.rotate_right(7);
+        assert_eq!(order(&queue), [3, 4, 5, 6, 7, 8, 9, 0, 1, 2]);
+        queue.rotate_left(0);
+        queue.rotate_right(10);
+        assert_eq!(order(&queue), [3, 4, 5, 6, 7, 8, 9, 0, 1, 2]);
+
+        // Rotating a full queue
+        for n in 10..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        queue.rotate_right(SIZE - 1);
+        assert_eq!(queue[0], 4);
+        assert_eq!(queue[SIZE - 1], 3);
+    }
+
+    #[test]
+    fn push_unique_by() {
+        let mut queue = BasicTypedQueue::<(u8, u32), SIZE>::default();
+        let key = |&(id, _): &(u8, u32)| id;
+        assert_eq!(queue.push_unique_by(key, (1, 10)), Ok(true));
+        assert_eq!(queue.push_unique_by(key, (2, 20)), Ok(true));
+        assert_eq!(queue.push_unique_by(key, (1, 11)), Ok(false));
+        assert_eq!(queue.size(), 2);
+
+        // Replacing keeps the position of the queued element
+        assert_eq!(queue.push_or_replace_by(key, (1, 12)), Ok(Some((1, 10))));
+        assert_eq!(queue.push_or_replace_by(key, (3, 30)), Ok(None));
+        assert_eq!(queue.pop().unwrap(), (1, 12));
+        assert_eq!(queue.pop().unwrap(), (2, 20));
+        assert_eq!(queue.pop().unwrap(), (3, 30));
+
+        for n in 0..SIZE as u8 {
+            assert_eq!(queue.push_unique_by(key, (n, 0)), Ok(true));
+        }
+        assert_eq!(queue.push_unique_by(key, (0, 0)), Ok(false));
+        assert_eq!(
+            queue.push_unique_by(key, (SIZE as u8, 0)),
+            Err(QueueError::QueueFull)
+        );
+    }
+
+    #[test]
+    fn push_unique_by_policies() {
+        let key = |&(id, _): &(u8, u32)| id;
+        for policy in [OverflowPolicy::DropNewest, OverflowPolicy::OverwriteOldest] {
+            let mut queue = BasicTypedQueue::<(u8, u32), SIZE>::default();
+            queue.set_overflow_policy(policy);
+            for n in 0..SIZE as u8 {
+                assert_eq!(queue.push_unique_by(key, (n, 0)), Ok(true));
+            }
+
+            // Neither dropped nor evicting: a full queue is reported as such
+            assert_eq!(
+                queue.push_unique_by(key, (SIZE as u8, 0)),
+                Err(QueueError::QueueFull)
+            );
+            assert_eq!(
+                queue.push_or_replace_by(key, (SIZE as u8, 0)),
+                Err(QueueError::QueueFull)
+            );
+            assert_eq!(queue.push_or_replace_by(key, (0, 1)), Ok(Some((0, 0))));
+            assert_eq!(queue.front(), Ok(&(0, 1)));
+            assert_eq!(queue.size(), SIZE);
+        }
+    }
+
+    #[test]
+    fn push_dedup() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.push_dedup(&1), Ok(true));
+        assert_eq!(queue.push_dedup(&1), Ok(false));
+        assert_eq!(queue.push_dedup(&2), Ok(true));
+        assert_eq!(queue.push_dedup(&1), Ok(true));
+        assert_eq!(queue.size(), 3);
+
+        // Only the back element is compared
+        assert_eq!(queue.pop().unwrap(), 1);
+        assert_eq!(queue.pop().unwrap(), 2);
+        assert_eq!(queue.push_dedup(&1), Ok(false));
+
+        for n in 1..SIZE as u32 {
+            assert_eq!(queue.push_dedup(&(n + 1)), Ok(true));
+        }
+        assert_eq!(queue.push_dedup(&(SIZE as u32)), Ok(false));
+        assert_eq!(queue.push_dedup(&0), Err(QueueError::QueueFull));
+    }
+
+    #[test]
+    fn pop_into_uninit() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        let mut slots = [MaybeUninit::<u32>::uninit(); 2];
+        assert_eq!(
+            queue.pop_into_uninit(&mut slots[0]).unwrap_err(),
+            QueueError::QueueEmpty
+        );
+
+        assert!(queue.push(1).is_ok());
+        assert!(queue.push(2).is_ok());
+        for slot in &mut slots {
+            assert!(queue.pop_into_uninit(slot).is_ok());
+        }
+        assert!(queue.is_empty());
+        assert_eq!(
+            unsafe { [slots[0].assume_init(), slots[1].assume_init()] },
+            [1, 2]
+        );
+    }
+
+    #[test]
+    fn prefault() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        queue.prefault();
+
+        // Move head/tail so that the free slots wrap around the end of the buffer
+        for n in 0..SIZE / 2 {
+            assert!(queue.push(n as u32).is_ok());
+            assert!(queue.pop().is_ok());
+        }
+        for n in 0..SIZE as u32 / 4 {
+            assert!(queue.push(n).is_ok());
+        }
+
+        queue.prefault();
+        for n in 0..SIZE as u32 / 4 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn boxed_prefault() {
+        let mut queue = BoxedTypedQueue::<u64>::with_capacity(1 << 16);
+        assert!(queue.push(1).is_ok());
+        queue.prefault();
+        assert_eq!(queue.pop().unwrap(), 1);
+    }
+
+    #[test]
+    fn size_budget() {
+        const _: () = super::assert_buffer_size_le::<u32, SIZE, { SIZE * 4 }>();
+
+        let mut queue = BasicTypedQueue::<u32, SIZE>::new_within_budget::<1024>();
+        assert!(queue.push(1).is_ok());
+        assert_eq!(queue.pop().unwrap(), 1);
+    }
+
+    #[test]
+    fn debug() {
+        let mut queue = BasicTypedQueue::<u32, 4>::default();
+        assert_eq!(format!("{:?}", queue), "[]");
+
+        // Move head/tail so that the contents wrap around the end of the buffer
+        for n in 0..3 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(queue.pop().is_ok());
+        assert!(queue.push(3).is_ok());
+        assert!(queue.push(4).is_ok());
+        assert_eq!(format!("{:?}", queue), "[1, 2, 3, 4]");
+    }
+
+    #[test]
+    fn display() {
+        let mut queue = BasicTypedQueue::<f32, 4>::default();
+        assert_eq!(queue.to_string(), "");
+
+        for value in [0.5, 1.25, 2.0] {
+            assert!(queue.push(value).is_ok());
+        }
+        assert_eq!(queue.to_string(), "0.5, 1.25, 2");
+        assert_eq!(format!("{:.1}", queue), "0.5, 1.2, 2.0");
+        assert_eq!(queue.display_with(" | ").to_string(), "0.5 | 1.25 | 2");
+    }
+
+    #[test]
+    fn pop_validity_invariant() {
+        // Types without an "uninitialized" bit pattern go through the same pop path
+        let mut queue = BasicTypedQueue::<NonZeroU32, SIZE>::default();
+        assert!(queue.push(NonZeroU32::MIN).is_ok());
+        assert_eq!(queue.pop().unwrap(), NonZeroU32::MIN);
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+
+        let mut queue = BasicTypedQueue::<bool, SIZE>::default();
+        assert!(queue.push(true).is_ok());
+        let mut output = false;
+        assert!(queue.pop_ref(&mut output).is_ok());
+        assert!(output);
+    }
+
+    #[test]
+    fn iter() {
+        let mut queue = wrapped_queue::<u32>();
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        let mut iter = queue.iter();
+        assert_eq!(iter.len(), SIZE);
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&(SIZE as u32 - 1)));
+        assert_eq!(iter.size_hint(), (SIZE - 2, Some(SIZE - 2)));
+        assert!(iter.copied().eq(1..SIZE as u32 - 1));
+
+        let mut count = 0;
+        for value in &queue {
+            assert_eq!(*value, count as u32);
+            count += 1;
+        }
+        assert_eq!(count, SIZE);
+        assert!(queue.iter().rev().copied().eq((0..SIZE as u32).rev()));
+
+        // Exhausted iterators keep returning `None`
+        let mut iter = queue.iter().skip(SIZE);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn windows() {
+        let mut queue = wrapped_queue::<u32>();
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        let windows = queue.windows(3);
+        assert_eq!(windows.len(), SIZE - 2);
+        for (start, window) in windows.enumerate() {
+            assert_eq!(window.len(), 3);
+            assert!(window.iter().copied().eq(start as u32..start as u32 + 3));
+            assert_eq!(window[2], start as u32 + 2);
+            assert_eq!(window.get(3), None);
+        }
+
+        // Windows as large as the queue, and larger
+        assert_eq!(queue.windows(SIZE).count(), 1);
+        assert_eq!(queue.windows(SIZE + 1).count(), 0);
+
+        let mut windows = queue.windows(SIZE);
+        assert!(windows.next().is_some());
+        assert!(windows.next().is_none());
+        assert!(windows.next().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "window size must be non-zero")]
+    fn windows_zero() {
+        let queue = BasicTypedQueue::<u32, SIZE>::default();
+        let _ = queue.windows(0);
+    }
+
+    #[test]
+    fn drain_chunks() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+
+        // Move head/tail so that the contents wrap around the end of the buffer
+        for n in 0..SIZE / 2 + 1 {
+            assert!(queue.push(n as u32).is_ok());
+            assert!(queue.pop().is_ok());
+        }
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        {
+            let mut chunks = queue.drain_chunks(5);
+            assert_eq!(chunks.len(), 4);
+            let chunk = chunks.next().unwrap();
+            let next = chunks.next().unwrap();
+
+            // Earlier chunks stay valid while later ones are drained
+            assert!(chunk.iter().copied().eq(0..5));
+            assert!(next.iter().copied().eq(5..10));
+            assert_eq!(next.as_slices(), (&[5, 6][..], &[7, 8, 9][..]));
+        }
+
+        // Unreached chunks stay queued
+        assert_eq!(queue.size(), SIZE - 10);
+        let sizes: Vec<usize> = queue.drain_chunks(4).map(|chunk| chunk.len()).collect();
+        assert_eq!(sizes, [4, 2]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.drain_chunks(1).next().map(|chunk| chunk.len()), None);
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE / 2 {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        let mut iter = queue.into_iter();
+        assert_eq!(iter.len(), SIZE / 2);
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next_back(), Some(SIZE as u32 / 2 - 1));
+        assert_eq!(iter.len(), SIZE / 2 - 2);
+        assert!(iter.eq(1..SIZE as u32 / 2 - 1));
+    }
+
+    #[test]
+    fn push_pop_unchecked() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+
+        for n in 0..SIZE {
+            unsafe { queue.push_unchecked(n as u32) };
+        }
+        assert!(queue.is_full());
+
+        for n in 0..SIZE {
+            assert_eq!(unsafe { queue.pop_unchecked() }, n as u32);
+        }
+        assert!(queue.is_empty());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "push_unchecked() called on a full queue")]
+    fn push_unchecked_full() {
+        let mut queue = BasicTypedQueue::<u32, 1>::default();
+        unsafe {
+            queue.push_unchecked(0);
+            queue.push_unchecked(1);
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "pop_unchecked() called on an empty queue")]
+    fn pop_unchecked_empty() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        unsafe { queue.pop_unchecked() };
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn boxed_storage() {
+        let mut queue = BoxedTypedQueue::<u32>::with_capacity(SIZE + 1);
+        assert_eq!(queue.capacity(), SIZE + 1);
+
+        for n in 0..SIZE + 1 {
+            assert!(queue.push(n as u32).is_ok());
+        }
+        assert!(queue.is_full());
+
+        for n in 0..SIZE + 1 {
+            assert_eq!(queue.pop().unwrap(), n as u32);
+        }
+        assert!(queue.is_empty());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn zero_capacity_overwrite() {
+        let mut queue = BoxedTypedQueue::<u32>::with_capacity(0);
+        assert_eq!(queue.push(1).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(queue.push_overwrite(1).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(
+            queue.push_ref_overwrite(&1).unwrap_err(),
+            QueueError::QueueFull
+        );
+        assert_eq!(queue.push_evict(1).unwrap_err(), QueueError::QueueFull);
+        assert!(queue.is_empty());
+
+        let mut queue = BasicTypedQueue::<u32, 0>::new();
+        assert_eq!(queue.push_overwrite(1).unwrap_err(), QueueError::QueueFull);
+    }
+
+    #[test]
+    fn slice_storage() {
+        let mut buffer = [MaybeUninit::<u32>::uninit(); SIZE];
+        let capacity = SIZE - 3; // e.g. only known at runtime
+
+        let mut queue = BasicTypedQueue::from_slice(&mut buffer[..capacity]);
+        assert_eq!(queue.capacity(), capacity);
+
+        for n in 0..capacity {
+            assert!(queue.push(n as u32).is_ok());
+        }
+        assert_eq!(queue.push(0).unwrap_err(), QueueError::QueueFull);
+
+        for n in 0..capacity {
+            assert_eq!(queue.pop().unwrap(), n as u32);
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_storage() {
+        let storage = MmapStorage::<u64>::anonymous(SIZE).unwrap();
+        let mut queue = BasicTypedQueue::<u64, 0, _>::from_storage(storage);
+        assert_eq!(queue.capacity(), SIZE);
+        queue.prefault();
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u64).is_ok());
+        }
+        for n in 0..SIZE {
+            assert_eq!(queue.pop().unwrap(), n as u64);
+        }
+    }
+
+    #[test]
+    fn clear() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        assert!(queue.clear().is_ok());
+        assert!(queue.is_empty());
+        assert_eq!(queue.front().unwrap_err(), QueueError::QueueEmpty);
+
+        assert!(queue.push(1).is_ok());
+        assert_eq!(queue.pop().unwrap(), 1);
     }
 
     #[test]
@@ -0,0 +1,321 @@
+use std::cell::UnsafeCell;
+use std::ops::{Deref, DerefMut};
+use std::slice;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::typed_queue::QueueError;
+
+// Single-producer, single-consumer bip-buffer of `BYTES` bytes. Unlike a plain byte ring, every
+// region handed out is contiguous: the producer asks for a writable grant of `n` bytes (e.g. as a
+// DMA target or to assemble a packet in place) and commits however much it used, while the consumer
+// gets the committed bytes back as one slice. When a grant does not fit before the end of the
+// buffer, it wraps to the start, and the unused tail is skipped until the consumer catches up. The
+// producer and the consumer only share atomic indices, so they may run on different threads (or in
+// an interrupt handler) without locks.
+pub struct BipBuffer<const BYTES: usize> {
+    buffer: UnsafeCell<[u8; BYTES]>,
+    write: AtomicUsize,   // end of the committed bytes
+    read: AtomicUsize,    // start of the bytes the consumer has yet to release
+    last: AtomicUsize,    // end of the valid bytes once the producer has wrapped
+    reserve: AtomicUsize, // end of the producer's current grant
+    producer_taken: AtomicBool,
+    consumer_taken: AtomicBool,
+}
+
+// The producer and the consumer only ever access disjoint regions of the buffer, as delimited by
+// the indices.
+unsafe impl<const BYTES: usize> Sync for BipBuffer<BYTES> {}
+
+impl<const BYTES: usize> BipBuffer<BYTES> {
+    /// Create a new, empty bip-buffer.
+    pub fn new() -> Self {
+        BipBuffer {
+            buffer: UnsafeCell::new([0; BYTES]),
+            write: AtomicUsize::new(0),
+            read: AtomicUsize::new(0),
+            last: AtomicUsize::new(BYTES),
+            reserve: AtomicUsize::new(0),
+            producer_taken: AtomicBool::new(false),
+            consumer_taken: AtomicBool::new(false),
+        }
+    }
+
+    /// Take the producer handle. Returns `None` if another producer handle is still alive.
+    pub fn producer(&self) -> Option<BipProducer<'_, BYTES>> {
+        if self.producer_taken.swap(true, Ordering::Acquire) {
+            None
+        } else {
+            Some(BipProducer { bip: self })
+        }
+    }
+
+    /// Take the consumer handle. Returns `None` if another consumer handle is still alive.
+    pub fn consumer(&self) -> Option<BipConsumer<'_, BYTES>> {
+        if self.consumer_taken.swap(true, Ordering::Acquire) {
+            None
+        } else {
+            Some(BipConsumer { bip: self })
+        }
+    }
+
+    /// Get the size of the buffer.
+    pub fn capacity(&self) -> usize {
+        BYTES
+    }
+
+    // Only called with regions that the caller has exclusive access to, as delimited by the
+    // indices.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn region(&self, start: usize, len: usize) -> &mut [u8] {
+        slice::from_raw_parts_mut((self.buffer.get() as *mut u8).add(start), len)
+    }
+}
+
+impl<const BYTES: usize> Default for BipBuffer<BYTES> {
+    fn default() -> Self {
+        BipBuffer::new()
+    }
+}
+
+// Exclusive write access to a bip-buffer. Dropping it allows a new producer to be taken.
+pub struct BipProducer<'a, const BYTES: usize> {
+    bip: &'a BipBuffer<BYTES>,
+}
+
+impl<'a, const BYTES: usize> BipProducer<'a, BYTES> {
+    /// Request a contiguous writable region of exactly `len` bytes. Fails if no such region is
+    /// free, even if as many bytes are free in total but split across the end of the buffer.
+    pub fn grant(&mut self, len: usize) -> Result<WriteGrant<'_, BYTES>, QueueError> {
+        let bip = self.bip;
+        let write = bip.write.load(Ordering::Acquire);
+        let read = bip.read.load(Ordering::Acquire);
+
+        // Once wrapped, the producer must stay strictly behind the consumer, as `write == read`
+        // means empty
+        let start = if write < read {
+            if write + len >= read {
+                return Err(QueueError::QueueFull);
+            }
+            write
+        } else if write + len <= BYTES {
+            write
+        } else if len < read {
+            0
+        } else {
+            return Err(QueueError::QueueFull);
+        };
+        bip.reserve.store(start + len, Ordering::Release);
+
+        Ok(WriteGrant {
+            bip,
+            buffer: unsafe { bip.region(start, len) },
+        })
+    }
+}
+
+impl<'a, const BYTES: usize> Drop for BipProducer<'a, BYTES> {
+    fn drop(&mut self) {
+        self.bip.producer_taken.store(false, Ordering::Release);
+    }
+}
+
+// Contiguous region granted to the producer. Dropping it without committing discards whatever
+// was written to it.
+pub struct WriteGrant<'a, const BYTES: usize> {
+    bip: &'a BipBuffer<BYTES>,
+    buffer: &'a mut [u8],
+}
+
+impl<'a, const BYTES: usize> WriteGrant<'a, BYTES> {
+    /// Make the first `used` bytes of the grant available to the consumer, at most the length of
+    /// the grant.
+    pub fn commit(self, used: usize) {
+        let bip = self.bip;
+        let used = used.min(self.buffer.len());
+        let write = bip.write.load(Ordering::Acquire);
+        let new_write = bip.reserve.load(Ordering::Acquire) - (self.buffer.len() - used);
+
+        if new_write < write && write != BYTES {
+            // Wrapped: the bytes past the old write index are skipped
+            bip.last.store(write, Ordering::Release);
+        } else if new_write > bip.last.load(Ordering::Acquire) {
+            // Passed the previous end of the valid bytes, which the consumer has moved past
+            bip.last.store(BYTES, Ordering::Release);
+        }
+        bip.write.store(new_write, Ordering::Release);
+    }
+}
+
+impl<'a, const BYTES: usize> Deref for WriteGrant<'a, BYTES> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.buffer
+    }
+}
+
+impl<'a, const BYTES: usize> DerefMut for WriteGrant<'a, BYTES> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.buffer
+    }
+}
+
+// Exclusive read access to a bip-buffer. Dropping it allows a new consumer to be taken.
+pub struct BipConsumer<'a, const BYTES: usize> {
+    bip: &'a BipBuffer<BYTES>,
+}
+
+impl<'a, const BYTES: usize> BipConsumer<'a, BYTES> {
+    /// Get the committed bytes as a contiguous region, up to the end of the buffer if the
+    /// producer has wrapped. Fails if no bytes are committed.
+    pub fn read(&mut self) -> Result<ReadGrant<'_, BYTES>, QueueError> {
+        let bip = self.bip;
+        let write = bip.write.load(Ordering::Acquire);
+        let last = bip.last.load(Ordering::Acquire);
+        let mut read = bip.read.load(Ordering::Relaxed);
+
+        // Done with the bytes before the end of the buffer, follow the producer to the start
+        if read == last && write < read {
+            read = 0;
+            bip.read.store(0, Ordering::Release);
+        }
+
+        let end = if write < read { last } else { write };
+        if end == read {
+            return Err(QueueError::QueueEmpty);
+        }
+
+        Ok(ReadGrant {
+            bip,
+            buffer: unsafe { bip.region(read, end - read) },
+        })
+    }
+}
+
+impl<'a, const BYTES: usize> Drop for BipConsumer<'a, BYTES> {
+    fn drop(&mut self) {
+        self.bip.consumer_taken.store(false, Ordering::Release);
+    }
+}
+
+// Contiguous region of committed bytes granted to the consumer. Dropping it without releasing
+// leaves the bytes to be read again.
+pub struct ReadGrant<'a, const BYTES: usize> {
+    bip: &'a BipBuffer<BYTES>,
+    buffer: &'a [u8],
+}
+
+impl<'a, const BYTES: usize> ReadGrant<'a, BYTES> {
+    /// Hand the first `used` bytes of the grant back to the producer, at most the length of the
+    /// grant.
+    pub fn release(self, used: usize) {
+        let used = used.min(self.buffer.len());
+        self.bip.read.fetch_add(used, Ordering::Release);
+    }
+}
+
+impl<'a, const BYTES: usize> Deref for ReadGrant<'a, BYTES> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BipBuffer;
+    use crate::typed_queue::QueueError;
+    use std::thread;
+
+    // Arbitrary buffer size for tests
+    const BYTES: usize = 16;
+
+    #[test]
+    fn grants() {
+        let bip = BipBuffer::<BYTES>::default();
+        let mut producer = bip.producer().unwrap();
+        let mut consumer = bip.consumer().unwrap();
+        assert!(bip.producer().is_none());
+        assert_eq!(consumer.read().err(), Some(QueueError::QueueEmpty));
+
+        // Only the committed part of a grant is readable
+        let mut grant = producer.grant(10).unwrap();
+        grant[..6].copy_from_slice(b"packet");
+        grant.commit(6);
+        let read = consumer.read().unwrap();
+        assert_eq!(&read[..], b"packet");
+        read.release(4);
+        assert_eq!(&consumer.read().unwrap()[..], b"et");
+        assert_eq!(producer.grant(BYTES + 1).err(), Some(QueueError::QueueFull));
+    }
+
+    #[test]
+    fn wrap_around() {
+        let bip = BipBuffer::<BYTES>::default();
+        let mut producer = bip.producer().unwrap();
+        let mut consumer = bip.consumer().unwrap();
+
+        producer.grant(12).unwrap().commit(12);
+        consumer.read().unwrap().release(8);
+
+        // 8 bytes are free in total, but only 4 at the end: a larger grant wraps to the start
+        let mut grant = producer.grant(7).unwrap();
+        grant.copy_from_slice(b"wrapped");
+        grant.commit(7);
+        assert_eq!(producer.grant(1).err(), Some(QueueError::QueueFull));
+
+        // The bytes before the end come out first, then the wrapped region as a whole
+        let read = consumer.read().unwrap();
+        assert_eq!(read.len(), 4);
+        read.release(4);
+        assert_eq!(&consumer.read().unwrap()[..], b"wrapped");
+    }
+
+    #[test]
+    fn producer_consumer() {
+        const COUNT: usize = 10_000;
+        let bip = BipBuffer::<64>::default();
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                let mut producer = bip.producer().unwrap();
+                for n in 0..COUNT {
+                    // Variable-size packets: a length byte followed by that many copies of it
+                    let len = n % 7 + 1;
+                    loop {
+                        if let Ok(mut grant) = producer.grant(len + 1) {
+                            grant[0] = len as u8;
+                            grant[1..].fill(len as u8);
+                            grant.commit(len + 1);
+                            break;
+                        }
+                        thread::yield_now();
+                    }
+                }
+            });
+
+            let mut consumer = bip.consumer().unwrap();
+            let mut received = 0;
+            while received < COUNT {
+                let Ok(read) = consumer.read() else {
+                    thread::yield_now();
+                    continue;
+                };
+
+                // Packets are never split across the end of the buffer
+                let mut offset = 0;
+                while offset < read.len() {
+                    let len = read[offset] as usize;
+                    assert_eq!(len, received % 7 + 1);
+                    assert!(read[offset + 1..offset + 1 + len]
+                        .iter()
+                        .all(|&b| b == len as u8));
+                    offset += len + 1;
+                    received += 1;
+                }
+                read.release(offset);
+            }
+        });
+    }
+}
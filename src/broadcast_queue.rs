@@ -0,0 +1,226 @@
+use std::mem::MaybeUninit;
+use std::sync::Mutex;
+
+use crate::typed_queue::QueueError;
+
+// State shared by the producers and subscribers of a broadcast queue.
+struct BroadcastState<T, const CAPACITY: usize, const SUBSCRIBERS: usize> {
+    slots: [MaybeUninit<T>; CAPACITY],
+    head: usize, // sequence number of the next element to be written
+    cursors: [Option<usize>; SUBSCRIBERS], // next sequence number to be read by each subscriber
+}
+
+impl<T, const CAPACITY: usize, const SUBSCRIBERS: usize> BroadcastState<T, CAPACITY, SUBSCRIBERS> {
+    // Sequence number of the oldest element some subscriber has yet to read.
+    fn tail(&self) -> usize {
+        self.cursors
+            .iter()
+            .flatten()
+            .copied()
+            .min()
+            .unwrap_or(self.head)
+    }
+}
+
+// Lossless broadcast queue: every subscriber has its own cursor and sees every element pushed
+// after it subscribed. A slot is only reused once every subscriber has read it, so the slowest
+// subscriber applies backpressure to the producers, which get `QueueError::QueueFull` until it
+// catches up. Up to `SUBSCRIBERS` subscribers may be registered at a time. Unlike the lock-free
+// `BroadcastRing`, which never blocks its writer and lets lagging readers skip ahead, any number
+// of producers may push, and no subscriber ever misses an element. Thread-safe.
+pub struct BroadcastQueue<T: Copy, const CAPACITY: usize, const SUBSCRIBERS: usize> {
+    state: Mutex<BroadcastState<T, CAPACITY, SUBSCRIBERS>>,
+}
+
+impl<T: Copy, const CAPACITY: usize, const SUBSCRIBERS: usize>
+    BroadcastQueue<T, CAPACITY, SUBSCRIBERS>
+{
+    /// Create a new broadcast queue without subscribers.
+    pub fn new() -> Self {
+        BroadcastQueue {
+            state: Mutex::new(BroadcastState {
+                slots: [MaybeUninit::uninit(); CAPACITY],
+                head: 0,
+                cursors: [None; SUBSCRIBERS],
+            }),
+        }
+    }
+
+    /// Register a subscriber that will see every element pushed from now on. Fails with
+    /// `QueueError::QueueFull` if `SUBSCRIBERS` subscribers are already registered.
+    pub fn subscribe(&self) -> Result<Subscriber<'_, T, CAPACITY, SUBSCRIBERS>, QueueError> {
+        let mut guard = self.state.lock()?;
+        let head = guard.head;
+        let id = guard
+            .cursors
+            .iter()
+            .position(Option::is_none)
+            .ok_or(QueueError::QueueFull)?;
+        guard.cursors[id] = Some(head);
+
+        Ok(Subscriber { queue: self, id })
+    }
+
+    /// Push an element to every current subscriber. Fails if the slowest subscriber has yet to
+    /// read `CAPACITY` elements. Without subscribers, the element is discarded.
+    pub fn push(&self, input: T) -> Result<(), QueueError> {
+        let mut guard = self.state.lock()?;
+        if guard.cursors.iter().all(Option::is_none) {
+            return Ok(());
+        }
+        if guard.head - guard.tail() == CAPACITY {
+            return Err(QueueError::QueueFull);
+        }
+
+        let head = guard.head;
+        guard.slots[head % CAPACITY].write(input);
+        guard.head += 1;
+
+        Ok(())
+    }
+
+    /// Get the number of elements the slowest subscriber has yet to read.
+    pub fn size(&self) -> Result<usize, QueueError> {
+        let guard = self.state.lock()?;
+        Ok(guard.head - guard.tail())
+    }
+
+    /// Get the number of registered subscribers.
+    pub fn subscribers(&self) -> Result<usize, QueueError> {
+        let guard = self.state.lock()?;
+        Ok(guard.cursors.iter().flatten().count())
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, const SUBSCRIBERS: usize> Default
+    for BroadcastQueue<T, CAPACITY, SUBSCRIBERS>
+{
+    fn default() -> Self {
+        BroadcastQueue::new()
+    }
+}
+
+// Reading end of a broadcast queue with its own cursor. Dropping it unregisters the subscriber,
+// releasing the slots it had yet to read.
+pub struct Subscriber<'a, T: Copy, const CAPACITY: usize, const SUBSCRIBERS: usize> {
+    queue: &'a BroadcastQueue<T, CAPACITY, SUBSCRIBERS>,
+    id: usize,
+}
+
+impl<'a, T: Copy, const CAPACITY: usize, const SUBSCRIBERS: usize>
+    Subscriber<'a, T, CAPACITY, SUBSCRIBERS>
+{
+    /// Read the next element. Fails if no new element is available.
+    pub fn pop(&mut self) -> Result<T, QueueError> {
+        let mut guard = self.queue.state.lock()?;
+        let cursor = guard.cursors[self.id].unwrap_or(guard.head);
+        if cursor == guard.head {
+            return Err(QueueError::QueueEmpty);
+        }
+
+        // Slots between the slowest cursor and the head are never overwritten
+        let value = unsafe { guard.slots[cursor % CAPACITY].assume_init() };
+        guard.cursors[self.id] = Some(cursor + 1);
+
+        Ok(value)
+    }
+
+    /// Get the number of elements this subscriber has yet to read.
+    pub fn pending(&self) -> Result<usize, QueueError> {
+        let guard = self.queue.state.lock()?;
+        Ok(guard.head - guard.cursors[self.id].unwrap_or(guard.head))
+    }
+}
+
+impl<'a, T: Copy, const CAPACITY: usize, const SUBSCRIBERS: usize> Drop
+    for Subscriber<'a, T, CAPACITY, SUBSCRIBERS>
+{
+    fn drop(&mut self) {
+        // A poisoned lock is recovered, so that the slot is released regardless
+        let mut guard = self
+            .queue
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        guard.cursors[self.id] = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BroadcastQueue;
+    use crate::typed_queue::QueueError;
+    use std::thread;
+
+    // Arbitrary queue size and number of subscribers for tests
+    const SIZE: usize = 16;
+    const SUBSCRIBERS: usize = 2;
+
+    #[test]
+    fn every_subscriber() {
+        let queue = BroadcastQueue::<u32, SIZE, SUBSCRIBERS>::default();
+        assert!(queue.push(0).is_ok());
+
+        let mut first = queue.subscribe().unwrap();
+        assert!(queue.push(1).is_ok());
+        let mut second = queue.subscribe().unwrap();
+        assert!(queue.push(2).is_ok());
+        assert_eq!(queue.subscribe().err(), Some(QueueError::QueueFull));
+        assert_eq!(queue.subscribers(), Ok(2));
+
+        // Subscribers only see elements pushed after they subscribed
+        assert_eq!(first.pending(), Ok(2));
+        assert_eq!(first.pop().unwrap(), 1);
+        assert_eq!(first.pop().unwrap(), 2);
+        assert_eq!(first.pop().unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(second.pop().unwrap(), 2);
+        assert_eq!(queue.size(), Ok(0));
+    }
+
+    #[test]
+    fn slowest_subscriber() {
+        let queue = BroadcastQueue::<u32, SIZE, SUBSCRIBERS>::default();
+        let mut fast = queue.subscribe().unwrap();
+        let slow = queue.subscribe().unwrap();
+
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+            assert_eq!(fast.pop().unwrap(), n);
+        }
+        assert_eq!(queue.push(0).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(queue.size(), Ok(SIZE));
+
+        // Unsubscribing the slow subscriber releases its slots
+        drop(slow);
+        assert_eq!(queue.size(), Ok(0));
+        assert!(queue.push(100).is_ok());
+        assert_eq!(fast.pop().unwrap(), 100);
+    }
+
+    #[test]
+    fn concurrent_subscribers() {
+        const COUNT: u32 = 10_000;
+        let queue = BroadcastQueue::<u32, SIZE, SUBSCRIBERS>::default();
+
+        thread::scope(|scope| {
+            for _ in 0..SUBSCRIBERS {
+                let mut subscriber = queue.subscribe().unwrap();
+                scope.spawn(move || {
+                    // Every element is seen, in order
+                    for n in 0..COUNT {
+                        loop {
+                            if let Ok(value) = subscriber.pop() {
+                                assert_eq!(value, n);
+                                break;
+                            }
+                        }
+                    }
+                });
+            }
+
+            for n in 0..COUNT {
+                while queue.push(n).is_err() {}
+            }
+        });
+    }
+}
@@ -0,0 +1,301 @@
+use std::array;
+use std::cell::UnsafeCell;
+use std::cmp::min;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+
+use crate::typed_queue::QueueError;
+
+// Ring slot guarded by a sequence stamp. The stamp is odd while the slot is being written and
+// `2 * sequence + 2` once the element with that sequence number is complete. Sequence numbers and
+// stamps wrap around, which 32-bit targets reach after 2^31 pushes, so they are only ever compared
+// for equality or through their wrapping difference.
+struct Slot<T> {
+    stamp: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// Single-writer, multiple-reader broadcast ring. The writer never blocks or fails: once the ring
+// is full, each push overwrites the oldest element. Every reader keeps its own cursor, detects
+// torn or overwritten slots via the sequence stamps, and skips ahead to the oldest element still
+// available when it falls more than a full ring behind the writer. Suited to fan-out where readers
+// tolerate loss but the writer must be wait-free. Unless `CAPACITY` is a power of two, elements
+// pushed as the sequence numbers wrap around may be reported as missed.
+pub struct BroadcastRing<T: Copy, const CAPACITY: usize> {
+    slots: [Slot<T>; CAPACITY],
+    head: AtomicUsize, // sequence number of the next element to be written
+    writer_taken: AtomicBool,
+}
+
+// Elements are only ever copied in and out of the slots, and torn reads are discarded.
+unsafe impl<T: Copy + Send, const CAPACITY: usize> Sync for BroadcastRing<T, CAPACITY> {}
+
+impl<T: Copy, const CAPACITY: usize> BroadcastRing<T, CAPACITY> {
+    // Evaluated by `new()`, so that a ring without slots fails to build.
+    const HAS_SLOTS: () = assert!(CAPACITY > 0, "broadcast ring capacity must be non-zero");
+
+    /// Create a new broadcast ring for the specified type and capacity. Fails to build if
+    /// `CAPACITY` is zero.
+    pub fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::HAS_SLOTS;
+
+        BroadcastRing {
+            slots: array::from_fn(|_| Slot {
+                stamp: AtomicUsize::new(0),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            }),
+            head: AtomicUsize::new(0),
+            writer_taken: AtomicBool::new(false),
+        }
+    }
+
+    /// Take the writer handle. Returns `None` if another writer handle is still alive.
+    pub fn writer(&self) -> Option<BroadcastWriter<'_, T, CAPACITY>> {
+        if self.writer_taken.swap(true, Ordering::Acquire) {
+            None
+        } else {
+            Some(BroadcastWriter { ring: self })
+        }
+    }
+
+    /// Create a reader that will see every element pushed from now on.
+    pub fn reader(&self) -> BroadcastReader<'_, T, CAPACITY> {
+        BroadcastReader {
+            ring: self,
+            cursor: self.head.load(Ordering::Acquire),
+            missed: 0,
+        }
+    }
+
+    /// Get the total number of elements pushed since creation, wrapping around at `usize::MAX`.
+    pub fn pushed(&self) -> usize {
+        self.head.load(Ordering::Acquire)
+    }
+
+    fn slot(&self, sequence: usize) -> &Slot<T> {
+        &self.slots[sequence % CAPACITY]
+    }
+
+    // Get the stamp of a slot holding the complete element `sequence`.
+    fn stamp(sequence: usize) -> usize {
+        sequence.wrapping_mul(2).wrapping_add(2)
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize> Default for BroadcastRing<T, CAPACITY> {
+    fn default() -> Self {
+        BroadcastRing::new()
+    }
+}
+
+// Exclusive write access to a broadcast ring. Dropping it allows a new writer to be taken.
+pub struct BroadcastWriter<'a, T: Copy, const CAPACITY: usize> {
+    ring: &'a BroadcastRing<T, CAPACITY>,
+}
+
+impl<'a, T: Copy, const CAPACITY: usize> BroadcastWriter<'a, T, CAPACITY> {
+    /// Push an element to every reader, overwriting the oldest element if the ring is full.
+    pub fn push(&mut self, input: T) {
+        let sequence = self.ring.head.load(Ordering::Relaxed);
+        let slot = self.ring.slot(sequence);
+
+        let stamp = BroadcastRing::<T, CAPACITY>::stamp(sequence);
+        slot.stamp.store(stamp.wrapping_sub(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+        // Readers may copy the slot concurrently, but discard the copy if the stamp changed
+        unsafe { ptr::write_volatile(slot.value.get(), MaybeUninit::new(input)) };
+        slot.stamp.store(stamp, Ordering::Release);
+
+        self.ring
+            .head
+            .store(sequence.wrapping_add(1), Ordering::Release);
+    }
+}
+
+impl<'a, T: Copy, const CAPACITY: usize> Drop for BroadcastWriter<'a, T, CAPACITY> {
+    fn drop(&mut self) {
+        self.ring.writer_taken.store(false, Ordering::Release);
+    }
+}
+
+// Reading end of a broadcast ring with its own cursor. Readers never affect the writer or each
+// other.
+pub struct BroadcastReader<'a, T: Copy, const CAPACITY: usize> {
+    ring: &'a BroadcastRing<T, CAPACITY>,
+    cursor: usize, // sequence number of the next element to be read
+    missed: u64,
+}
+
+impl<'a, T: Copy, const CAPACITY: usize> BroadcastReader<'a, T, CAPACITY> {
+    /// Read the next element. If the writer has lapped this reader, the overwritten elements are
+    /// skipped and counted in `missed()`. Fails if no new element is available.
+    pub fn pop(&mut self) -> Result<T, QueueError> {
+        loop {
+            let head = self.ring.head.load(Ordering::Acquire);
+            if self.cursor == head {
+                return Err(QueueError::QueueEmpty);
+            }
+            if head.wrapping_sub(self.cursor) > CAPACITY {
+                self.skip_to(head.wrapping_sub(CAPACITY));
+            }
+
+            let slot = self.ring.slot(self.cursor);
+            let stamp = BroadcastRing::<T, CAPACITY>::stamp(self.cursor);
+            if slot.stamp.load(Ordering::Acquire) != stamp {
+                // Overwritten (or being overwritten) since `head` was loaded
+                self.skip_to(self.cursor.wrapping_add(1));
+                continue;
+            }
+
+            let value = unsafe { ptr::read_volatile(slot.value.get()) };
+            fence(Ordering::Acquire);
+            if slot.stamp.load(Ordering::Relaxed) != stamp {
+                // Torn read
+                self.skip_to(self.cursor.wrapping_add(1));
+                continue;
+            }
+
+            self.cursor = self.cursor.wrapping_add(1);
+            return Ok(unsafe { value.assume_init() });
+        }
+    }
+
+    /// Get the number of elements this reader can still read before catching up with the writer.
+    pub fn pending(&self) -> usize {
+        min(
+            self.ring
+                .head
+                .load(Ordering::Acquire)
+                .wrapping_sub(self.cursor),
+            CAPACITY,
+        )
+    }
+
+    /// Get the number of elements this reader lost because the writer lapped it.
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+
+    fn skip_to(&mut self, sequence: usize) {
+        self.missed += sequence.wrapping_sub(self.cursor) as u64;
+        self.cursor = sequence;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BroadcastRing;
+    use crate::typed_queue::QueueError;
+    use std::sync::atomic::Ordering;
+    use std::thread;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn push_pop() {
+        let ring = BroadcastRing::<u32, SIZE>::default();
+        let mut writer = ring.writer().unwrap();
+        let mut first = ring.reader();
+        assert_eq!(first.pop().unwrap_err(), QueueError::QueueEmpty);
+
+        writer.push(1);
+        let mut second = ring.reader();
+        writer.push(2);
+
+        // Readers only see elements pushed after they were created
+        assert_eq!(first.pending(), 2);
+        assert_eq!(first.pop().unwrap(), 1);
+        assert_eq!(first.pop().unwrap(), 2);
+        assert_eq!(second.pop().unwrap(), 2);
+        assert_eq!(second.pop().unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(ring.pushed(), 2);
+    }
+
+    #[test]
+    fn single_writer() {
+        let ring = BroadcastRing::<u32, SIZE>::default();
+        let writer = ring.writer();
+        assert!(writer.is_some());
+        assert!(ring.writer().is_none());
+
+        drop(writer);
+        assert!(ring.writer().is_some());
+    }
+
+    #[test]
+    fn lapped_reader() {
+        let ring = BroadcastRing::<u32, SIZE>::default();
+        let mut writer = ring.writer().unwrap();
+        let mut reader = ring.reader();
+
+        for n in 0..SIZE * 2 + 3 {
+            writer.push(n as u32);
+        }
+
+        // The reader skips to the oldest element still in the ring
+        assert_eq!(reader.pending(), SIZE);
+        assert_eq!(reader.pop().unwrap(), SIZE as u32 + 3);
+        assert_eq!(reader.missed(), SIZE as u64 + 3);
+        for n in SIZE + 4..SIZE * 2 + 3 {
+            assert_eq!(reader.pop().unwrap(), n as u32);
+        }
+        assert_eq!(reader.pop().unwrap_err(), QueueError::QueueEmpty);
+    }
+
+    #[test]
+    fn sequence_wraparound() {
+        let ring = BroadcastRing::<u32, SIZE>::default();
+        ring.head.store(usize::MAX - 2, Ordering::Relaxed);
+        let mut writer = ring.writer().unwrap();
+        let mut reader = ring.reader();
+
+        // Sequence numbers and stamps wrap around without losing or reordering elements
+        for n in 0..6 {
+            writer.push(n);
+        }
+        assert_eq!(reader.pending(), 6);
+        for n in 0..6 {
+            assert_eq!(reader.pop().unwrap(), n);
+        }
+        assert_eq!(reader.missed(), 0);
+
+        // A reader lapped across the wraparound still skips to the oldest element
+        for n in 0..SIZE as u32 + 1 {
+            writer.push(n);
+        }
+        assert_eq!(reader.pop().unwrap(), 1);
+        assert_eq!(reader.missed(), 1);
+        assert_eq!(ring.pushed(), SIZE + 4);
+    }
+
+    #[test]
+    fn concurrent_readers() {
+        const COUNT: u64 = 100_000;
+        let ring = BroadcastRing::<[u64; 4], SIZE>::default();
+
+        thread::scope(|scope| {
+            for _ in 0..2 {
+                let mut reader = ring.reader();
+                scope.spawn(move || {
+                    let mut last = None;
+                    while last != Some(COUNT - 1) {
+                        if let Ok(value) = reader.pop() {
+                            // Never torn, and always in order
+                            assert!(value.iter().all(|&n| n == value[0]));
+                            assert!(last.is_none_or(|last| value[0] > last));
+                            last = Some(value[0]);
+                        }
+                    }
+                });
+            }
+
+            let mut writer = ring.writer().unwrap();
+            for n in 0..COUNT {
+                writer.push([n; 4]);
+            }
+        });
+    }
+}
@@ -0,0 +1,97 @@
+use bytes::buf::UninitSlice;
+use bytes::{Buf, BufMut};
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::storage::Storage;
+use crate::typed_queue::TypedQueue;
+
+// The queued bytes are the readable side of the queue and its free slots the writable side, so
+// codecs speaking `bytes` can decode from and encode into a byte queue directly. As with
+// `io::BufRead`, each chunk is the contiguous part up to the end of the ring, and the rest follows
+// once it is consumed.
+impl<const CAPACITY: usize, S: Storage<u8>> Buf for BasicTypedQueue<u8, CAPACITY, S> {
+    fn remaining(&self) -> usize {
+        self.size()
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.as_slices().0
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        assert!(cnt <= self.size(), "cannot advance past the queued bytes");
+        self.truncate_front(self.size() - cnt);
+    }
+}
+
+// Bytes written into a chunk only become part of the queue once `advance_mut()` commits them.
+unsafe impl<const CAPACITY: usize, S: Storage<u8>> BufMut for BasicTypedQueue<u8, CAPACITY, S> {
+    fn remaining_mut(&self) -> usize {
+        self.capacity() - self.size()
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        assert!(
+            cnt <= self.remaining_mut(),
+            "cannot advance past the free bytes"
+        );
+        self.commit_spare(cnt);
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        UninitSlice::uninit(self.spare_contiguous_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::presets::ByteQueue;
+    use crate::typed_queue::TypedQueue;
+    use bytes::{Buf, BufMut};
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn codec() {
+        let mut queue = ByteQueue::<SIZE>::new();
+        queue.put_u32(0xdead_beef);
+        queue.put_slice(b"payload");
+        assert_eq!(queue.remaining_mut(), SIZE - 11);
+        assert_eq!(queue.get_u32(), 0xdead_beef);
+
+        // Integers straddling the end of the ring are put and read across both chunks
+        queue.put_u16_le(0x1234);
+        queue.put_u32(u32::MAX - 1);
+        queue.put_slice(b"end");
+        assert!(!queue.has_remaining_mut());
+        assert_eq!(queue.chunk().len(), SIZE - 4);
+
+        assert_eq!(queue.copy_to_bytes(7), &b"payload"[..]);
+        assert_eq!(queue.get_u16_le(), 0x1234);
+        assert_eq!(queue.get_u32(), u32::MAX - 1);
+        assert_eq!(queue.chunk(), b"end");
+        queue.advance(3);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot advance past the queued bytes")]
+    fn advance_past_end() {
+        let mut queue = ByteQueue::<SIZE>::new();
+        queue.put_slice(b"abc");
+        queue.advance(4);
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut queue = ByteQueue::<0>::new();
+        assert_eq!(queue.remaining_mut(), 0);
+        assert_eq!(queue.chunk_mut().len(), 0);
+
+        // Advancing by nothing is allowed and leaves the queue empty
+        unsafe { queue.advance_mut(0) };
+        queue.advance(0);
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use std::io::{self, BufRead, Read, Write};
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::storage::Storage;
+#[cfg(feature = "threads")]
+use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Byte queues act as in-memory pipes: bytes written to a queue come out of it in the same order
+// when read. Writes take as many bytes as fit and fail with `io::ErrorKind::WouldBlock` once the
+// queue is full, so that `write_all()` into a full queue reports the back-pressure rather than a
+// zero-length write. Reads take as many bytes as are queued.
+
+// Reading an empty queue returns `Ok(0)`, i.e. end of input, like reading an empty `VecDeque<u8>`.
+impl<const CAPACITY: usize, S: Storage<u8>> Read for BasicTypedQueue<u8, CAPACITY, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(self.pop_slice(buf))
+    }
+}
+
+// The queue is its own buffer: `fill_buf()` exposes the queued bytes up to the end of the ring
+// without copying them, so that a parser looking ahead sees the rest once the first part is
+// consumed.
+impl<const CAPACITY: usize, S: Storage<u8>> BufRead for BasicTypedQueue<u8, CAPACITY, S> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.as_slices().0)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.truncate_front(self.size() - amt.min(self.size()));
+    }
+}
+
+impl<const CAPACITY: usize, S: Storage<u8>> Write for BasicTypedQueue<u8, CAPACITY, S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.push_slice(buf);
+        if written == 0 && !buf.is_empty() {
+            return Err(QueueError::QueueFull.into());
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Reading an empty queue fails with `io::ErrorKind::WouldBlock` while producers may still write to
+// it, and returns `Ok(0)`, i.e. end of input, once the queue has been closed and drained. Writing
+// to a closed queue fails with `io::ErrorKind::BrokenPipe`.
+#[cfg(feature = "threads")]
+impl<const CAPACITY: usize> Read for &ThreadSafeTypedQueue<u8, CAPACITY> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // No more bytes can be pushed once closed, so checking first rules out missing a late push
+        let closed = self.is_closed();
+        let read = self.pop_slice(buf)?;
+        if read == 0 && !buf.is_empty() && !closed {
+            return Err(QueueError::QueueEmpty.into());
+        }
+
+        Ok(read)
+    }
+}
+
+#[cfg(feature = "threads")]
+impl<const CAPACITY: usize> Write for &ThreadSafeTypedQueue<u8, CAPACITY> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.push_slice(buf)?;
+        if written == 0 && !buf.is_empty() {
+            return Err(QueueError::QueueFull.into());
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "threads")]
+impl<const CAPACITY: usize> Read for ThreadSafeTypedQueue<u8, CAPACITY> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self).read(buf)
+    }
+}
+
+#[cfg(feature = "threads")]
+impl<const CAPACITY: usize> Write for ThreadSafeTypedQueue<u8, CAPACITY> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::presets::ByteQueue;
+    #[cfg(feature = "threads")]
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::TypedQueue;
+    use std::io::{self, BufRead, Read, Write};
+    #[cfg(feature = "threads")]
+    use std::thread;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn round_trip() {
+        let mut queue = ByteQueue::<SIZE>::new();
+        assert_eq!(queue.write(b"hello, ").unwrap(), 7);
+        assert_eq!(queue.write(&[b'x'; SIZE]).unwrap(), SIZE - 7);
+        assert_eq!(
+            queue.write(b"!").unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+        assert_eq!(queue.write(b"").unwrap(), 0);
+
+        let mut buffer = [0; 10];
+        assert_eq!(queue.read(&mut buffer).unwrap(), 10);
+        assert_eq!(&buffer, b"hello, xxx");
+
+        // The written bytes wrap around the end of the ring
+        assert!(queue.write_all(b"world").is_ok());
+        let mut output = Vec::new();
+        assert_eq!(queue.read_to_end(&mut output).unwrap(), 11);
+        assert_eq!(output, b"xxxxxxworld");
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn lines() {
+        let mut queue = ByteQueue::<SIZE>::new();
+        assert!(queue.write_all(b"0123456789").is_ok());
+        queue.consume(8);
+
+        // The second line wraps around the end of the ring, so it is buffered in two parts
+        assert!(queue.write_all(b"ab\ncdefgh\nij").is_ok());
+        assert_eq!(queue.fill_buf().unwrap(), b"89ab\ncde");
+        let mut line = String::new();
+        assert_eq!(queue.read_line(&mut line).unwrap(), 5);
+        assert_eq!(line, "89ab\n");
+
+        let lines: Vec<String> = queue.lines().map(Result::unwrap).collect();
+        assert_eq!(lines, ["cdefgh", "ij"]);
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn pipe() {
+        const COUNT: usize = 10_000;
+        let queue = ThreadSafeTypedQueue::<u8, SIZE>::new();
+        let input: Vec<u8> = (0..COUNT).map(|n| n as u8).collect();
+
+        let output = thread::scope(|scope| {
+            scope.spawn(|| {
+                let mut writer = &queue;
+                let mut remaining = &input[..];
+                while !remaining.is_empty() {
+                    match writer.write(remaining) {
+                        Ok(written) => remaining = &remaining[written..],
+                        Err(error) => assert_eq!(error.kind(), io::ErrorKind::WouldBlock),
+                    }
+                    thread::yield_now();
+                }
+                assert!(queue.close().is_ok());
+            });
+
+            // Reads until the writer has closed the queue and every byte has been read
+            let mut reader = &queue;
+            let mut output = Vec::new();
+            let mut buffer = [0; 7];
+            loop {
+                match reader.read(&mut buffer) {
+                    Ok(0) => break output,
+                    Ok(read) => output.extend_from_slice(&buffer[..read]),
+                    Err(error) => assert_eq!(error.kind(), io::ErrorKind::WouldBlock),
+                }
+                thread::yield_now();
+            }
+        });
+
+        assert_eq!(output, input);
+        assert_eq!(
+            (&queue).write(b"late").unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe
+        );
+    }
+}
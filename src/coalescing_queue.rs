@@ -0,0 +1,146 @@
+use std::mem;
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Queue of keyed values holding at most one entry per key. Pushing a value whose key is already
+// queued replaces that entry's value in place, keeping its original position, so consumers only
+// see the latest value for each key, e.g. the latest state of each widget in a UI update queue.
+// Keys are looked up with a linear scan, so pushes are O(N). Not thread-safe.
+pub struct CoalescingQueue<K: Copy + Eq, V: Copy, const N: usize> {
+    queue: BasicTypedQueue<(K, V), N>,
+}
+
+impl<K: Copy + Eq, V: Copy, const N: usize> CoalescingQueue<K, V, N> {
+    /// Create an empty queue.
+    pub fn new() -> Self {
+        CoalescingQueue {
+            queue: BasicTypedQueue::new(),
+        }
+    }
+
+    /// Push a value for `key`. If the key is already queued, its value is replaced in place and
+    /// the previous value returned; otherwise the entry is added at the back. Fails if the key is
+    /// not queued and the queue is full.
+    pub fn push(&mut self, key: K, value: V) -> Result<Option<V>, QueueError> {
+        if let Some(index) = self.index_of(&key) {
+            if let Some(entry) = self.queue.get_mut(index) {
+                return Ok(Some(mem::replace(&mut entry.1, value)));
+            }
+        }
+
+        self.queue.push((key, value))?;
+        Ok(None)
+    }
+
+    /// Pop the oldest entry. Fails if queue is empty.
+    pub fn pop(&mut self) -> Result<(K, V), QueueError> {
+        self.queue.pop()
+    }
+
+    /// Try to get an immutable reference to the oldest entry.
+    pub fn front(&self) -> Result<&(K, V), QueueError> {
+        self.queue.front()
+    }
+
+    /// Get the value queued for `key`, if any.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.index_of(key)
+            .and_then(|index| self.queue.get(index))
+            .map(|(_, value)| value)
+    }
+
+    /// Check if a value is queued for `key`.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.index_of(key).is_some()
+    }
+
+    /// Remove the entry for `key`, returning its value, if any. Later entries keep their order.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let index = self.index_of(key)?;
+        self.queue.remove(index).map(|(_, value)| value)
+    }
+
+    /// Remove every entry.
+    pub fn clear(&mut self) -> Result<(), QueueError> {
+        self.queue.clear()
+    }
+
+    /// Check if the queue is full.
+    pub fn is_full(&self) -> bool {
+        self.queue.is_full()
+    }
+
+    /// Check if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Get the number of entries, i.e. of distinct keys, in the queue.
+    pub fn size(&self) -> usize {
+        self.queue.size()
+    }
+
+    /// Get the maximum number of entries the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
+
+    fn index_of(&self, key: &K) -> Option<usize> {
+        self.queue.position(|(queued, _)| queued == key)
+    }
+}
+
+impl<K: Copy + Eq, V: Copy, const N: usize> Default for CoalescingQueue<K, V, N> {
+    fn default() -> Self {
+        CoalescingQueue::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CoalescingQueue;
+    use crate::typed_queue::QueueError;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 4;
+
+    #[test]
+    fn coalesce() {
+        let mut queue = CoalescingQueue::<char, u32, SIZE>::default();
+        assert_eq!(queue.push('a', 1), Ok(None));
+        assert_eq!(queue.push('b', 2), Ok(None));
+        assert_eq!(queue.push('a', 3), Ok(Some(1)));
+        assert_eq!(queue.push('c', 4), Ok(None));
+        assert_eq!(queue.size(), 3);
+        assert_eq!(queue.get(&'a'), Some(&3));
+
+        // The replaced entry keeps its original position
+        assert_eq!(queue.pop().unwrap(), ('a', 3));
+        assert_eq!(queue.pop().unwrap(), ('b', 2));
+
+        // A popped key is queued again at the back
+        assert_eq!(queue.push('a', 5), Ok(None));
+        assert_eq!(queue.front().unwrap(), &('c', 4));
+        assert_eq!(queue.remove(&'c'), Some(4));
+        assert!(!queue.contains_key(&'c'));
+        assert_eq!(queue.pop().unwrap(), ('a', 5));
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+    }
+
+    #[test]
+    fn full() {
+        let mut queue = CoalescingQueue::<u8, u32, SIZE>::default();
+        for key in 0..SIZE as u8 {
+            assert_eq!(queue.push(key, 0), Ok(None));
+        }
+        assert!(queue.is_full());
+
+        // Existing keys can still be updated
+        assert_eq!(queue.push(SIZE as u8, 0), Err(QueueError::QueueFull));
+        assert_eq!(queue.push(0, 1), Ok(Some(0)));
+
+        assert!(queue.clear().is_ok());
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+use crate::typed_queue::TypedQueue;
+
+// Bridge exposing thread-safe queues of plain numeric types to C++ through `cxx`, so that C++
+// components can share the same ring buffer implementation as the Rust ones. Generics cannot cross
+// the bridge, so there is one opaque queue type per element type, all with the capacity
+// `BRIDGE_CAPACITY`, i.e. 4096 elements. The bridge module itself is generated by the build script,
+// which lists the element types and sets the capacity. From C++, after including the generated
+// `rust-queue/src/cxx_bridge.rs.h`:
+//
+//     rust::Box<rust_queue::QueueU32> queue = rust_queue::new_queue_u32();
+//     queue->push(42);
+//     uint32_t value;
+//     if (queue->pop(value)) { ... }
+//
+// The queue is shared between C++ threads by reference while the box owning it is alive. Operations
+// report failure, i.e. a full, empty, closed or poisoned queue, by returning `false` rather than
+// throwing, as callers typically poll.
+
+// Define the opaque queue types declared in the bridge, and their constructors.
+macro_rules! bridge_queues {
+    ($($Queue:ident($T:ty) => $new:ident;)*) => {
+        $(
+            pub struct $Queue(ThreadSafeTypedQueue<$T, BRIDGE_CAPACITY>);
+
+            fn $new() -> Box<$Queue> {
+                Box::new($Queue(ThreadSafeTypedQueue::new()))
+            }
+
+            impl $Queue {
+                fn push(&self, value: $T) -> bool {
+                    (&self.0).push(value).is_ok()
+                }
+
+                fn push_overwrite(&self, value: $T) -> bool {
+                    (&self.0).push_overwrite(value).is_ok()
+                }
+
+                fn pop(&self, value: &mut $T) -> bool {
+                    (&self.0).pop_ref(value).is_ok()
+                }
+
+                fn size(&self) -> usize {
+                    self.0.size()
+                }
+
+                fn is_empty(&self) -> bool {
+                    self.0.is_empty()
+                }
+
+                fn is_full(&self) -> bool {
+                    self.0.is_full()
+                }
+
+                fn capacity(&self) -> usize {
+                    self.0.capacity()
+                }
+
+                fn close(&self) -> bool {
+                    self.0.close().is_ok()
+                }
+
+                fn is_closed(&self) -> bool {
+                    self.0.is_closed()
+                }
+            }
+        )*
+    };
+}
+
+include!(concat!(env!("OUT_DIR"), "/src/cxx_bridge.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::{new_queue_f64, new_queue_u32, BRIDGE_CAPACITY};
+    use std::thread;
+
+    #[test]
+    fn calls() {
+        let queue = new_queue_u32();
+        assert_eq!(queue.capacity(), BRIDGE_CAPACITY);
+        assert!(queue.push(7));
+        assert!(queue.push(8));
+        assert_eq!(queue.size(), 2);
+
+        let mut value = 0;
+        assert!(queue.pop(&mut value));
+        assert_eq!(value, 7);
+        assert!(queue.close());
+        assert!(!queue.push(9));
+        assert!(queue.pop(&mut value));
+        assert!(!queue.pop(&mut value));
+        assert_eq!(value, 8);
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let queue = new_queue_f64();
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for n in 0..BRIDGE_CAPACITY * 2 {
+                    while !queue.push(n as f64) {
+                        thread::yield_now();
+                    }
+                }
+            });
+
+            let mut value = 0.0;
+            for n in 0..BRIDGE_CAPACITY * 2 {
+                while !queue.pop(&mut value) {
+                    thread::yield_now();
+                }
+                assert_eq!(value, n as f64);
+            }
+        });
+        assert!(queue.is_empty());
+    }
+}
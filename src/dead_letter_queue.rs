@@ -0,0 +1,213 @@
+use std::marker::PhantomData;
+
+use crate::typed_queue::{self, QueueError, TypedQueue};
+
+// Wrapper routing the elements an overwriting push evicts into an attached dead-letter queue
+// instead of dropping them, so that they can be inspected or replayed later. Plain pushes and pops
+// go to the underlying queue unchanged. The dead-letter queue itself is overwritten once full, so
+// that it keeps the most recent dead letters. Works with shared queues on either side, e.g.
+// `DeadLetterQueue<T, &ThreadSafeTypedQueue<T, N>, BasicTypedQueue<T, M>>`. An evicted element
+// the dead-letter queue rejects, e.g. because it is a closed shared queue, is held by the wrapper
+// until it can be delivered or is taken back with `take_undelivered()`.
+pub struct DeadLetterQueue<T: Copy, Q: TypedQueue<T>, D: TypedQueue<T>> {
+    queue: Q,
+    dead_letters: D,
+    dead_lettered: u64,
+    undelivered: Option<T>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy, Q: TypedQueue<T>, D: TypedQueue<T>> DeadLetterQueue<T, Q, D> {
+    /// Wrap a queue, routing evicted elements into `dead_letters`.
+    pub fn new(queue: Q, dead_letters: D) -> Self {
+        DeadLetterQueue {
+            queue,
+            dead_letters,
+            dead_lettered: 0,
+            undelivered: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the number of elements routed into the dead-letter queue so far.
+    pub fn dead_lettered(&self) -> u64 {
+        self.dead_lettered
+    }
+
+    /// Take back the evicted element the dead-letter queue last rejected, if any. While it is
+    /// held, overwriting pushes retry delivering it before evicting anything else.
+    pub fn take_undelivered(&mut self) -> Option<T> {
+        self.undelivered.take()
+    }
+
+    /// Get a reference to the dead-letter queue.
+    pub fn dead_letters(&self) -> &D {
+        &self.dead_letters
+    }
+
+    /// Get a mutable reference to the dead-letter queue, e.g. to drain it.
+    pub fn dead_letters_mut(&mut self) -> &mut D {
+        &mut self.dead_letters
+    }
+
+    /// Get a reference to the underlying queue.
+    pub fn inner(&self) -> &Q {
+        &self.queue
+    }
+
+    /// Get a mutable reference to the underlying queue.
+    pub fn inner_mut(&mut self) -> &mut Q {
+        &mut self.queue
+    }
+
+    /// Unwrap the underlying queue and the dead-letter queue.
+    pub fn into_inner(self) -> (Q, D) {
+        (self.queue, self.dead_letters)
+    }
+}
+
+impl<T: Copy, Q: TypedQueue<T>, D: TypedQueue<T>> TypedQueue<T> for DeadLetterQueue<T, Q, D> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.queue.push(input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref_overwrite(&input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        self.queue.push_ref(input)
+    }
+
+    // Evicts the oldest element into the dead-letter queue rather than overwriting it in place.
+    // Fails with `QueueError::QueueFull` if the queue is full but nothing can be evicted. If the
+    // dead-letter queue fails, the evicted element is kept as undelivered and the error returned;
+    // the queue is left untouched until that element has been delivered or taken back.
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        if let Some(undelivered) = self.undelivered {
+            self.dead_letters.push_overwrite(undelivered)?;
+            self.undelivered = None;
+            self.dead_lettered += 1;
+        }
+
+        typed_queue::push_evicting(&mut self.queue, input, |evicted| {
+            if let Err(err) = self.dead_letters.push_overwrite(evicted) {
+                self.undelivered = Some(evicted);
+                return Err(err);
+            }
+            self.dead_lettered += 1;
+            Ok(())
+        })
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        self.queue.pop()
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        self.queue.pop_ref(output)
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        self.queue.clear()
+    }
+
+    fn is_full(&self) -> bool {
+        self.queue.is_full()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    fn size(&self) -> usize {
+        self.queue.size()
+    }
+
+    fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeadLetterQueue;
+    use crate::basic_typed_queue::BasicTypedQueue;
+    #[cfg(feature = "threads")]
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::{QueueError, TypedQueue};
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn evictions() {
+        let mut queue = DeadLetterQueue::new(
+            BasicTypedQueue::<u32, SIZE>::default(),
+            BasicTypedQueue::<u32, 2>::default(),
+        );
+        for n in 0..SIZE as u32 {
+            assert!(queue.push_overwrite(n).is_ok());
+        }
+        assert_eq!(queue.push(100), Err(QueueError::QueueFull));
+        assert!(queue.dead_letters().is_empty());
+
+        // Evicted elements are kept, the most recent ones once the dead-letter queue is full
+        for n in 100..103 {
+            assert!(queue.push_overwrite(n).is_ok());
+        }
+        assert_eq!(queue.dead_lettered(), 3);
+        assert_eq!(queue.dead_letters_mut().pop().unwrap(), 1);
+        assert_eq!(queue.dead_letters_mut().pop().unwrap(), 2);
+
+        assert_eq!(queue.pop().unwrap(), 3);
+        assert_eq!(queue.size(), SIZE - 1);
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn shared_queues() {
+        let shared = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        let dead_letters = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        let mut queue = DeadLetterQueue::new(&shared, &dead_letters);
+        for n in 0..=SIZE as u32 {
+            assert!(queue.push_overwrite(n).is_ok());
+        }
+
+        let mut dead_letters = &dead_letters;
+        assert_eq!(dead_letters.pop().unwrap(), 0);
+        assert!(shared.is_full());
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn nothing_to_evict() {
+        let shared = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 {
+            assert!(shared.push_unpublished(n).is_ok());
+        }
+
+        let mut queue = DeadLetterQueue::new(&shared, BasicTypedQueue::<u32, SIZE>::default());
+        assert_eq!(queue.push_overwrite(100), Err(QueueError::QueueFull));
+        assert_eq!(queue.dead_lettered(), 0);
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn closed_dead_letters() {
+        let dead_letters = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert!(dead_letters.close().is_ok());
+        let mut queue = DeadLetterQueue::new(BasicTypedQueue::<u32, 2>::default(), &dead_letters);
+        assert!(queue.push(1).is_ok());
+        assert!(queue.push(2).is_ok());
+
+        // The rejected element is held rather than lost, and nothing else is evicted meanwhile
+        assert_eq!(queue.push_overwrite(3), Err(QueueError::Closed));
+        assert_eq!(queue.push_overwrite(4), Err(QueueError::Closed));
+        assert_eq!(queue.size(), 1);
+        assert_eq!(queue.dead_lettered(), 0);
+        assert_eq!(queue.take_undelivered(), Some(1));
+        assert_eq!(queue.take_undelivered(), None);
+        assert_eq!(queue.pop().unwrap(), 2);
+    }
+}
@@ -0,0 +1,140 @@
+use defmt::{Format, Formatter};
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::storage::Storage;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+impl Format for QueueError {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            QueueError::QueueEmpty => defmt::write!(f, "QueueEmpty"),
+            QueueError::QueueFull => defmt::write!(f, "QueueFull"),
+            QueueError::MutexPoisoned => defmt::write!(f, "MutexPoisoned"),
+            QueueError::Timeout => defmt::write!(f, "Timeout"),
+            QueueError::Closed => defmt::write!(f, "Closed"),
+            QueueError::WouldBlock => defmt::write!(f, "WouldBlock"),
+            // Rare enough that going through `core::fmt` is acceptable
+            QueueError::Io(kind) => defmt::write!(f, "Io({})", defmt::Debug2Format(kind)),
+            QueueError::TypeMismatch => defmt::write!(f, "TypeMismatch"),
+            QueueError::NoRoute => defmt::write!(f, "NoRoute"),
+            QueueError::Unordered => defmt::write!(f, "Unordered"),
+        }
+    }
+}
+
+// Compact form: occupancy followed by the elements from front to back, e.g. `2/8 [1, 2]`.
+impl<T: Copy + Format, const CAPACITY: usize, S: Storage<T>> Format
+    for BasicTypedQueue<T, CAPACITY, S>
+{
+    fn format(&self, f: Formatter<'_>) {
+        defmt::write!(f, "{=usize}/{=usize} ", self.size(), self.capacity());
+        write_elements(f, self.iter());
+    }
+}
+
+// Write elements from front to back as a bracketed, comma-separated list.
+pub(crate) fn write_elements<'a, T: Format + 'a>(
+    f: Formatter<'_>,
+    iter: impl Iterator<Item = &'a T>,
+) {
+    defmt::write!(f, "[");
+    for (n, value) in iter.enumerate() {
+        if n > 0 {
+            defmt::write!(f, ", ");
+        }
+        defmt::write!(f, "{}", value);
+    }
+    defmt::write!(f, "]");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::basic_typed_queue::BasicTypedQueue;
+    #[cfg(feature = "threads")]
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::{QueueError, TypedQueue};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    // Logger collecting the encoded frames in memory, in place of a probe on a real target.
+    #[defmt::global_logger]
+    struct TestLogger;
+
+    static TAKEN: AtomicBool = AtomicBool::new(false);
+    static FRAMES: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+    // Held while encoding, so that tests running in parallel do not mix their frames
+    static ENCODING: Mutex<()> = Mutex::new(());
+
+    unsafe impl defmt::Logger for TestLogger {
+        fn acquire() {
+            while TAKEN.swap(true, Ordering::Acquire) {
+                std::hint::spin_loop();
+            }
+        }
+
+        unsafe fn flush() {}
+
+        unsafe fn release() {
+            TAKEN.store(false, Ordering::Release);
+        }
+
+        unsafe fn write(bytes: &[u8]) {
+            FRAMES.lock().unwrap().extend_from_slice(bytes);
+        }
+    }
+
+    defmt::timestamp!("");
+
+    // Encode `value` through its `Format` implementation, returning the frame.
+    fn encode<T: defmt::Format>(value: &T) -> Vec<u8> {
+        let _encoding = ENCODING.lock().unwrap();
+        FRAMES.lock().unwrap().clear();
+        defmt::println!("{}", value);
+        FRAMES.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn encode_queues() {
+        let mut queue = BasicTypedQueue::<u32, 4>::new();
+        let empty = encode(&queue);
+        assert!(!empty.is_empty());
+
+        assert!(queue.push(0xdead_beef).is_ok());
+        let one = encode(&queue);
+        assert_ne!(one, empty);
+        assert!(queue.push(7).is_ok());
+        let two = encode(&queue);
+        assert_ne!(two, one);
+
+        // The encoding only depends on the contents
+        let mut other = BasicTypedQueue::<u32, 4>::new();
+        assert!(other.push(0xdead_beef).is_ok());
+        assert!(other.push(7).is_ok());
+        assert_eq!(encode(&other), two);
+        assert!(other.pop().is_ok());
+        assert!(other.push(8).is_ok());
+        assert_ne!(encode(&other), two);
+
+        #[cfg(feature = "threads")]
+        {
+            let shared = ThreadSafeTypedQueue::<u32, 4>::new();
+            assert!((&shared).push(0xdead_beef).is_ok());
+            assert!(!encode(&shared).is_empty());
+        }
+    }
+
+    #[test]
+    fn encode_errors() {
+        let errors = [
+            QueueError::QueueEmpty,
+            QueueError::QueueFull,
+            QueueError::Closed,
+            QueueError::NoRoute,
+            QueueError::Io(std::io::ErrorKind::NotFound),
+        ];
+        let frames: Vec<_> = errors.iter().map(encode).collect();
+        for (n, frame) in frames.iter().enumerate() {
+            assert!(frames[..n].iter().all(|other| other != frame));
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::time::{Duration, Instant};
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Element along with the deadline after which it may be popped.
+#[derive(Copy, Clone)]
+struct Delayed<T, D> {
+    deadline: D,
+    value: T,
+}
+
+// Queue whose elements only become available once their deadline has passed. Deadlines are
+// `Instant`s by default, but any ordered type works, such as a tick counter on targets without a
+// clock. Elements are kept sorted by deadline, elements with equal deadlines in FIFO order, so a
+// push costs O(N) while popping and `next_deadline()` are O(1). Not thread-safe.
+pub struct DelayQueue<T: Copy, const N: usize, D: Copy + Ord = Instant> {
+    queue: BasicTypedQueue<Delayed<T, D>, N>,
+}
+
+impl<T: Copy, const N: usize, D: Copy + Ord> DelayQueue<T, N, D> {
+    /// Create an empty queue.
+    pub fn new() -> Self {
+        DelayQueue {
+            queue: BasicTypedQueue::new(),
+        }
+    }
+
+    /// Push an element that becomes available once `now` reaches `deadline`. Fails if queue is
+    /// full.
+    pub fn push(&mut self, input: T, deadline: D) -> Result<(), QueueError> {
+        if self.queue.is_full() {
+            return Err(QueueError::QueueFull);
+        }
+
+        let index = self
+            .queue
+            .position(|delayed| delayed.deadline > deadline)
+            .unwrap_or(self.queue.size());
+        self.queue.insert(
+            index,
+            Delayed {
+                deadline,
+                value: input,
+            },
+        )
+    }
+
+    /// Pop the element with the earliest deadline if that deadline is at or before `now`. Fails
+    /// with `QueueError::WouldBlock` if no element is due yet, or `QueueError::QueueEmpty` if the
+    /// queue is empty.
+    pub fn pop(&mut self, now: D) -> Result<T, QueueError> {
+        if self.queue.is_empty() {
+            return Err(QueueError::QueueEmpty);
+        }
+        if !self.is_ready(now) {
+            return Err(QueueError::WouldBlock);
+        }
+
+        self.queue.pop().map(|delayed| delayed.value)
+    }
+
+    /// Check if an element is due at `now`.
+    pub fn is_ready(&self, now: D) -> bool {
+        self.next_deadline().is_some_and(|deadline| deadline <= now)
+    }
+
+    /// Get the earliest deadline in the queue, so that a scheduler knows how long to sleep for,
+    /// or `None` if the queue is empty.
+    pub fn next_deadline(&self) -> Option<D> {
+        self.queue.front().ok().map(|delayed| delayed.deadline)
+    }
+
+    /// Try to get an immutable reference to the element with the earliest deadline, whether it
+    /// is due or not.
+    pub fn front(&self) -> Result<&T, QueueError> {
+        self.queue.front().map(|delayed| &delayed.value)
+    }
+
+    /// Remove every element, due or not.
+    pub fn clear(&mut self) -> Result<(), QueueError> {
+        self.queue.clear()
+    }
+
+    /// Check if the queue is full.
+    pub fn is_full(&self) -> bool {
+        self.queue.is_full()
+    }
+
+    /// Check if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Get the number of elements in the queue, due or not.
+    pub fn size(&self) -> usize {
+        self.queue.size()
+    }
+
+    /// Get the maximum number of elements the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
+}
+
+impl<T: Copy, const N: usize> DelayQueue<T, N> {
+    /// Push an element that becomes available after `delay` from now. Fails if queue is full.
+    pub fn push_after(&mut self, input: T, delay: Duration) -> Result<(), QueueError> {
+        self.push(input, Instant::now() + delay)
+    }
+
+    /// Pop the element with the earliest deadline if it has passed, as `pop(Instant::now())`.
+    pub fn pop_due(&mut self) -> Result<T, QueueError> {
+        self.pop(Instant::now())
+    }
+
+    /// Get the time left until the earliest deadline, zero if it has passed, or `None` if the
+    /// queue is empty.
+    pub fn time_to_next(&self) -> Option<Duration> {
+        self.next_deadline()
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+impl<T: Copy, const N: usize, D: Copy + Ord> Default for DelayQueue<T, N, D> {
+    fn default() -> Self {
+        DelayQueue::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DelayQueue;
+    use crate::typed_queue::QueueError;
+    use std::thread;
+    use std::time::Duration;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn ticks() {
+        let mut queue = DelayQueue::<char, SIZE, u32>::default();
+        assert_eq!(queue.pop(0).unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(queue.next_deadline(), None);
+
+        assert!(queue.push('c', 30).is_ok());
+        assert!(queue.push('a', 10).is_ok());
+        assert!(queue.push('b', 20).is_ok());
+        assert!(queue.push('d', 20).is_ok());
+        assert_eq!(queue.next_deadline(), Some(10));
+        assert_eq!(queue.front().unwrap(), &'a');
+
+        assert_eq!(queue.pop(5).unwrap_err(), QueueError::WouldBlock);
+        assert_eq!(queue.pop(10).unwrap(), 'a');
+        assert_eq!(queue.pop(15).unwrap_err(), QueueError::WouldBlock);
+
+        // Equal deadlines come out in FIFO order
+        assert_eq!(queue.pop(25).unwrap(), 'b');
+        assert_eq!(queue.pop(25).unwrap(), 'd');
+        assert_eq!(queue.next_deadline(), Some(30));
+        assert!(!queue.is_ready(29));
+        assert_eq!(queue.size(), 1);
+
+        for n in 0..SIZE as u32 - 1 {
+            assert!(queue.push('x', n).is_ok());
+        }
+        assert_eq!(queue.push('y', 0).unwrap_err(), QueueError::QueueFull);
+    }
+
+    #[test]
+    fn instants() {
+        let mut queue = DelayQueue::<u32, SIZE>::default();
+        assert!(queue.push_after(2, Duration::from_millis(50)).is_ok());
+        assert!(queue.push_after(1, Duration::ZERO).is_ok());
+
+        assert_eq!(queue.pop_due().unwrap(), 1);
+        assert_eq!(queue.pop_due().unwrap_err(), QueueError::WouldBlock);
+        assert!(queue.time_to_next().unwrap() > Duration::ZERO);
+
+        thread::sleep(queue.time_to_next().unwrap());
+        assert_eq!(queue.pop_due().unwrap(), 2);
+        assert!(queue.is_empty());
+    }
+}
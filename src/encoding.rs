@@ -0,0 +1,237 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::storage::Storage;
+#[cfg(feature = "threads")]
+use crate::thread_safe_typed_queue::IterGuard;
+use crate::typed_queue::TypedQueue;
+
+// Queues are encoded as a sequence of their elements in FIFO order, independent of where the head
+// happens to be in the buffer.
+impl<T: Copy + Serialize, const CAPACITY: usize, S: Storage<T>> Serialize
+    for BasicTypedQueue<T, CAPACITY, S>
+{
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+// A locked thread-safe queue is encoded like a basic queue holding its visible elements.
+#[cfg(feature = "threads")]
+impl<'a, T: Copy + Serialize, const CAPACITY: usize> Serialize for IterGuard<'a, T, CAPACITY> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Copy + Deserialize<'de>, const CAPACITY: usize> Deserialize<'de>
+    for BasicTypedQueue<T, CAPACITY>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(QueueVisitor(PhantomData))
+    }
+}
+
+struct QueueVisitor<T, const CAPACITY: usize>(PhantomData<T>);
+
+impl<'de, T: Copy + Deserialize<'de>, const CAPACITY: usize> Visitor<'de>
+    for QueueVisitor<T, CAPACITY>
+{
+    type Value = BasicTypedQueue<T, CAPACITY>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a sequence of at most {} elements", CAPACITY)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut queue = BasicTypedQueue::new();
+        while let Some(value) = seq.next_element()? {
+            if queue.push(value).is_err() {
+                return Err(de::Error::invalid_length(CAPACITY + 1, &self));
+            }
+        }
+        Ok(queue)
+    }
+}
+
+#[cfg(feature = "postcard")]
+mod compact {
+    use std::io;
+
+    #[cfg(feature = "alloc")]
+    use serde::de::DeserializeOwned;
+    use serde::{Deserialize, Serialize};
+
+    use crate::basic_typed_queue::BasicTypedQueue;
+    use crate::storage::Storage;
+    #[cfg(all(feature = "alloc", feature = "threads"))]
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::QueueError;
+
+    impl<T: Copy + Serialize, const CAPACITY: usize, S: Storage<T>> BasicTypedQueue<T, CAPACITY, S> {
+        /// Encode the elements of the queue in FIFO order into `buffer`, using the compact
+        /// postcard format. Returns the number of bytes written. Fails if `buffer` is too small.
+        pub fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize, QueueError> {
+            Ok(postcard::to_slice(self, buffer)?.len())
+        }
+    }
+
+    impl<'de, T: Copy + Deserialize<'de>, const CAPACITY: usize> BasicTypedQueue<T, CAPACITY> {
+        /// Decode a queue previously encoded with `to_bytes()`. Fails if the data is malformed
+        /// or holds more elements than the capacity.
+        pub fn from_bytes(bytes: &'de [u8]) -> Result<Self, QueueError> {
+            Ok(postcard::from_bytes(bytes)?)
+        }
+    }
+
+    // Snapshots let services checkpoint the contents of their queues, e.g. on graceful shutdown,
+    // and restore them on startup. They use the `to_bytes()` format, so a snapshot of a
+    // thread-safe queue can be loaded into a basic queue of the same element type and vice versa.
+    #[cfg(feature = "alloc")]
+    impl<T: Copy + Serialize, const CAPACITY: usize, S: Storage<T>> BasicTypedQueue<T, CAPACITY, S> {
+        /// Write the elements of the queue in FIFO order to `writer`.
+        pub fn save_snapshot<W: io::Write>(&self, mut writer: W) -> Result<(), QueueError> {
+            Ok(writer.write_all(&postcard::to_extend(self, Vec::new())?)?)
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    impl<T: Copy + DeserializeOwned, const CAPACITY: usize> BasicTypedQueue<T, CAPACITY> {
+        /// Read a queue from a snapshot written by `save_snapshot()`, consuming `reader` to its
+        /// end. Fails if the snapshot is malformed or holds more elements than the capacity.
+        pub fn load_snapshot<R: io::Read>(mut reader: R) -> Result<Self, QueueError> {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+            Ok(postcard::from_bytes(&bytes)?)
+        }
+    }
+
+    #[cfg(all(feature = "alloc", feature = "threads"))]
+    impl<T: Copy + Serialize, const CAPACITY: usize> ThreadSafeTypedQueue<T, CAPACITY> {
+        /// Write the elements visible to consumers in FIFO order to `writer`. The queue is only
+        /// locked while the elements are encoded, not while they are written.
+        pub fn save_snapshot<W: io::Write>(&self, mut writer: W) -> Result<(), QueueError> {
+            let bytes = postcard::to_extend(&self.iter()?, Vec::new())?;
+            Ok(writer.write_all(&bytes)?)
+        }
+    }
+
+    #[cfg(all(feature = "alloc", feature = "threads"))]
+    impl<T: Copy + DeserializeOwned, const CAPACITY: usize> ThreadSafeTypedQueue<T, CAPACITY> {
+        /// Read a queue from a snapshot written by `save_snapshot()`, consuming `reader` to its
+        /// end. Fails if the snapshot is malformed or holds more elements than the capacity.
+        pub fn load_snapshot<R: io::Read>(reader: R) -> Result<Self, QueueError> {
+            let restored = BasicTypedQueue::<T, CAPACITY>::load_snapshot(reader)?;
+            let (first, second) = restored.as_slices();
+            let queue = ThreadSafeTypedQueue::new();
+            queue.push_slice(first)?;
+            queue.push_slice(second)?;
+            Ok(queue)
+        }
+    }
+
+    impl From<postcard::Error> for QueueError {
+        fn from(e: postcard::Error) -> Self {
+            match e {
+                postcard::Error::SerializeBufferFull => QueueError::Io(io::ErrorKind::WriteZero),
+                postcard::Error::DeserializeUnexpectedEnd => {
+                    QueueError::Io(io::ErrorKind::UnexpectedEof)
+                }
+                _ => QueueError::Io(io::ErrorKind::InvalidData),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "postcard"))]
+mod tests {
+    use crate::basic_typed_queue::BasicTypedQueue;
+    #[cfg(all(feature = "alloc", feature = "threads"))]
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::{QueueError, TypedQueue};
+    use std::io;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn round_trip() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+
+        // Move head/tail so that the contents wrap around the end of the buffer
+        for n in 0..SIZE / 2 {
+            assert!(queue.push(n as u32).is_ok());
+            assert!(queue.pop().is_ok());
+        }
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n * 1000).is_ok());
+        }
+
+        let mut buffer = [0; 64];
+        let len = queue.to_bytes(&mut buffer).unwrap();
+        let mut restored = BasicTypedQueue::<u32, SIZE>::from_bytes(&buffer[..len]).unwrap();
+        for n in 0..SIZE as u32 {
+            assert_eq!(restored.pop().unwrap(), n * 1000);
+        }
+        assert!(restored.is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let mut queue = BasicTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+
+        let mut buffer = [0; 4];
+        assert_eq!(
+            queue.to_bytes(&mut buffer).unwrap_err(),
+            QueueError::Io(io::ErrorKind::WriteZero)
+        );
+
+        // Too many elements for the target capacity, and truncated data
+        let mut buffer = [0; 64];
+        let len = queue.to_bytes(&mut buffer).unwrap();
+        assert_eq!(
+            BasicTypedQueue::<u32, 4>::from_bytes(&buffer[..len]).err(),
+            Some(QueueError::Io(io::ErrorKind::InvalidData))
+        );
+        assert_eq!(
+            BasicTypedQueue::<u32, SIZE>::from_bytes(&buffer[..len - 1]).err(),
+            Some(QueueError::Io(io::ErrorKind::UnexpectedEof))
+        );
+    }
+
+    #[cfg(all(feature = "alloc", feature = "threads"))]
+    #[test]
+    fn snapshots() {
+        let mut shared = ThreadSafeTypedQueue::<u32, SIZE>::new();
+        for n in 0..SIZE as u32 {
+            assert!(shared.push(n * 3).is_ok());
+        }
+        for _ in 0..SIZE / 2 {
+            assert!(shared.pop().is_ok());
+        }
+
+        // Checkpoint the shared queue on shutdown, then restore it as either kind of queue
+        let mut snapshot = Vec::new();
+        assert!(shared.save_snapshot(&mut snapshot).is_ok());
+        let restored = ThreadSafeTypedQueue::<u32, SIZE>::load_snapshot(&snapshot[..]).unwrap();
+        assert_eq!(restored.snapshot(), shared.snapshot());
+
+        let mut basic = BasicTypedQueue::<u32, SIZE>::load_snapshot(&snapshot[..]).unwrap();
+        assert_eq!(basic.size(), SIZE / 2);
+        assert_eq!(basic.pop(), Ok(SIZE as u32 / 2 * 3));
+
+        let mut resaved = Vec::new();
+        assert!(basic.save_snapshot(&mut resaved).is_ok());
+        assert_eq!(
+            BasicTypedQueue::<u32, 4>::load_snapshot(&resaved[..]).err(),
+            Some(QueueError::Io(io::ErrorKind::InvalidData))
+        );
+    }
+}
@@ -0,0 +1,139 @@
+// Generates an extension trait with one typed push method and one typed pop method per variant
+// of a message enum, implemented for `BasicTypedQueue`s of that enum (over any storage). Event
+// loops can then push a variant without naming the enum, and pop only the variant they expect
+// without matching on every message:
+//
+//     #[derive(Copy, Clone)]
+//     enum Event {
+//         Tick(u32),
+//         Button(u8, bool),
+//         Shutdown,
+//     }
+//
+//     rust_queue::enum_queue! {
+//         trait EventQueue for Event {
+//             Tick(count: u32) => push_tick, pop_if_tick;
+//             Button(id: u8, pressed: bool) => push_button, pop_if_button;
+//             Shutdown => push_shutdown, pop_if_shutdown;
+//         }
+//     }
+//
+// Fields are named so that they can be taken as push arguments. A pop method returns the single
+// field of its variant, a tuple of its fields, or `()` for a unit variant, and fails with
+// `QueueError::TypeMismatch` without removing anything if the oldest message is another variant.
+// The enum must be `Copy` and in scope under the given name.
+#[macro_export]
+macro_rules! enum_queue {
+    (
+        $(#[$meta:meta])*
+        $vis:vis trait $Trait:ident for $Enum:ident {
+            $(
+                $Variant:ident $( ( $( $field:ident : $ty:ty ),* $(,)? ) )? => $push:ident, $pop:ident;
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[allow(unused_parens)]
+        $vis trait $Trait {
+            $(
+                #[doc = concat!(
+                    "Push a `", stringify!($Variant), "` message. Fails if queue is full."
+                )]
+                fn $push(
+                    &mut self $( $( , $field: $ty )* )?
+                ) -> Result<(), $crate::typed_queue::QueueError>;
+
+                #[doc = concat!(
+                    "Pop the oldest message if it is a `", stringify!($Variant), "`, returning ",
+                    "its fields. Fails if queue is empty, or with `QueueError::TypeMismatch` if ",
+                    "the message is another variant, leaving it in the queue."
+                )]
+                fn $pop(&mut self) -> Result<( $( $( $ty ),* )? ), $crate::typed_queue::QueueError>;
+            )*
+        }
+
+        #[allow(unused_parens)]
+        impl<const N: usize, S: $crate::storage::Storage<$Enum>> $Trait
+            for $crate::basic_typed_queue::BasicTypedQueue<$Enum, N, S>
+        {
+            $(
+                fn $push(
+                    &mut self $( $( , $field: $ty )* )?
+                ) -> Result<(), $crate::typed_queue::QueueError> {
+                    $crate::typed_queue::TypedQueue::push(
+                        self,
+                        $Enum::$Variant $( ( $( $field ),* ) )?,
+                    )
+                }
+
+                #[allow(unreachable_patterns)]
+                fn $pop(&mut self) -> Result<( $( $( $ty ),* )? ), $crate::typed_queue::QueueError> {
+                    match *self.front()? {
+                        $Enum::$Variant $( ( $( $field ),* ) )? => {
+                            $crate::typed_queue::TypedQueue::pop(self)?;
+                            Ok(( $( $( $field ),* )? ))
+                        }
+                        _ => Err($crate::typed_queue::QueueError::TypeMismatch),
+                    }
+                }
+            )*
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::basic_typed_queue::BasicTypedQueue;
+    use crate::typed_queue::{QueueError, TypedQueue};
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    enum Event {
+        Tick(u32),
+        Button(u8, bool),
+        Shutdown,
+    }
+
+    crate::enum_queue! {
+        /// Typed helpers for queues of `Event`s.
+        trait EventQueue for Event {
+            Tick(count: u32) => push_tick, pop_if_tick;
+            Button(id: u8, pressed: bool,) => push_button, pop_if_button;
+            Shutdown => push_shutdown, pop_if_shutdown;
+        }
+    }
+
+    #[test]
+    fn variants() {
+        let mut queue = BasicTypedQueue::<Event, SIZE>::new();
+        assert!(queue.push_tick(1).is_ok());
+        assert!(queue.push_button(3, true).is_ok());
+        assert!(queue.push_shutdown().is_ok());
+        assert_eq!(queue.size(), 3);
+
+        // Popping another variant leaves the message in place
+        assert_eq!(queue.pop_if_button(), Err(QueueError::TypeMismatch));
+        assert_eq!(queue.pop_if_tick(), Ok(1));
+        assert_eq!(queue.pop_if_button(), Ok((3, true)));
+        assert_eq!(queue.pop_if_shutdown(), Ok(()));
+        assert_eq!(queue.pop_if_tick(), Err(QueueError::QueueEmpty));
+    }
+
+    #[test]
+    fn event_loop() {
+        let mut queue = BasicTypedQueue::<Event, SIZE>::new();
+        for n in 0..3 {
+            assert!(queue.push_tick(n).is_ok());
+        }
+        assert!(queue.push_shutdown().is_ok());
+
+        let mut ticks = 0;
+        while let Ok(count) = queue.pop_if_tick() {
+            ticks += count;
+        }
+        assert_eq!(ticks, 3);
+        assert_eq!(queue.pop().unwrap(), Event::Shutdown);
+    }
+}
@@ -0,0 +1,187 @@
+use std::time::Duration;
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::presets::Timestamped;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Queue discarding elements that have been queued for longer than a time-to-live, so that a
+// consumer which fell behind does not act on stale commands. Elements are timestamped on push and
+// expire lazily: whenever the queue is accessed through a push, `pop()` or `front()`, expired
+// elements at the front are discarded first, so that they never take up room needed by new ones. As
+// elements are queued in push order, the expired ones are always the oldest. `size()` includes
+// expired elements not discarded yet; call `expire_now()` first for an exact count. Not
+// thread-safe.
+pub struct ExpiringQueue<T: Copy, const N: usize> {
+    queue: BasicTypedQueue<Timestamped<T>, N>,
+    ttl: Duration,
+    expired: u64,
+}
+
+impl<T: Copy, const N: usize> ExpiringQueue<T, N> {
+    /// Create an empty queue whose elements expire `ttl` after being pushed.
+    pub fn new(ttl: Duration) -> Self {
+        ExpiringQueue {
+            queue: BasicTypedQueue::new(),
+            ttl,
+            expired: 0,
+        }
+    }
+
+    /// Set the time-to-live of the elements, including those already queued.
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+    }
+
+    /// Get the time-to-live of the elements.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Discard every expired element, returning how many were discarded.
+    pub fn expire_now(&mut self) -> usize {
+        let mut count = 0;
+        while self
+            .queue
+            .front()
+            .is_ok_and(|element| element.timestamp.elapsed() >= self.ttl)
+        {
+            let _ = self.queue.pop();
+            count += 1;
+        }
+
+        self.expired += count as u64;
+        count
+    }
+
+    /// Get the total number of elements discarded because they expired.
+    pub fn expired(&self) -> u64 {
+        self.expired
+    }
+
+    /// Try to get an immutable reference to the oldest element that has not expired, discarding
+    /// the expired ones before it.
+    pub fn front(&mut self) -> Result<&T, QueueError> {
+        self.expire_now();
+        self.queue.front().map(|element| &element.value)
+    }
+
+    /// Get the time left before the oldest element expires, zero if it already has, or `None` if
+    /// the queue is empty.
+    pub fn time_to_expiry(&self) -> Option<Duration> {
+        let element = self.queue.front().ok()?;
+        Some(self.ttl.saturating_sub(element.timestamp.elapsed()))
+    }
+}
+
+impl<T: Copy, const N: usize> TypedQueue<T> for ExpiringQueue<T, N> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.expire_now();
+        self.queue.push(Timestamped::now(input))
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.expire_now();
+        self.queue.push_overwrite(Timestamped::now(input))
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        self.push(*input)
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        self.push_overwrite(*input)
+    }
+
+    // Fails with `QueueError::QueueEmpty` if every queued element has expired.
+    fn pop(&mut self) -> Result<T, QueueError> {
+        self.expire_now();
+        self.queue.pop().map(|element| element.value)
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        *output = self.pop()?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        self.queue.clear()
+    }
+
+    fn is_full(&self) -> bool {
+        self.queue.is_full()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    fn size(&self) -> usize {
+        self.queue.size()
+    }
+
+    fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExpiringQueue;
+    use crate::typed_queue::{QueueError, TypedQueue};
+    use std::thread;
+    use std::time::Duration;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn lazy_expiry() {
+        let mut queue = ExpiringQueue::<u32, SIZE>::new(Duration::from_millis(20));
+        assert!(queue.push(1).is_ok());
+        assert!(queue.push(2).is_ok());
+        thread::sleep(Duration::from_millis(25));
+
+        // Expired elements are only discarded on access
+        assert_eq!(queue.size(), 2);
+        assert_eq!(queue.time_to_expiry(), Some(Duration::ZERO));
+        assert!(queue.push(3).is_ok());
+        assert_eq!(queue.size(), 1);
+        assert_eq!(queue.expired(), 2);
+        assert_eq!(queue.front().unwrap(), &3);
+
+        thread::sleep(Duration::from_millis(25));
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(queue.expired(), 3);
+    }
+
+    #[test]
+    fn expire_now() {
+        let mut queue = ExpiringQueue::<u32, SIZE>::new(Duration::from_secs(60));
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert_eq!(queue.expire_now(), 0);
+        assert!(queue.time_to_expiry().unwrap() > Duration::from_secs(59));
+
+        queue.set_ttl(Duration::ZERO);
+        assert_eq!(queue.ttl(), Duration::ZERO);
+        assert_eq!(queue.expire_now(), SIZE);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn full_of_expired() {
+        let mut queue = ExpiringQueue::<u32, SIZE>::new(Duration::from_secs(60));
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert_eq!(queue.push(100), Err(QueueError::QueueFull));
+
+        // Expired elements make room for new ones instead of rejecting them
+        queue.set_ttl(Duration::ZERO);
+        assert!(queue.is_full());
+        assert!(queue.push(100).is_ok());
+        assert_eq!(queue.expired(), SIZE as u64);
+        assert_eq!(queue.size(), 1);
+    }
+}
@@ -0,0 +1,237 @@
+use std::marker::PhantomData;
+
+use crate::typed_queue::{self, QueueError, TypedQueue};
+
+/// Order in which a `FanIn` serves its source queues.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum FanInOrder {
+    /// Take turns between the non-empty sources, so that none of them can starve the others.
+    #[default]
+    RoundRobin,
+    /// Always serve the first non-empty source, in the order the sources were given.
+    Priority,
+}
+
+// Adapter merging `N` source queues into a single stream of elements, so that one consumer can
+// service several producers' queues uniformly. Each source keeps its own FIFO order. Sources are
+// usually shared queues, e.g. `FanIn<T, &ThreadSafeTypedQueue<T, CAPACITY>, N>`, with producers
+// pushing to them directly. A fan-in can stand in for a single queue: elements pushed through
+// `TypedQueue` go to the first source, which thus decides whether it counts as full.
+pub struct FanIn<T: Copy, Q: TypedQueue<T>, const N: usize> {
+    sources: [Q; N],
+    order: FanInOrder,
+    next: usize, // source served first by the next round-robin pop
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy, Q: TypedQueue<T>, const N: usize> FanIn<T, Q, N> {
+    /// Merge the given sources, serving them round-robin.
+    pub fn new(sources: [Q; N]) -> Self {
+        FanIn {
+            sources,
+            order: FanInOrder::RoundRobin,
+            next: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Set the order in which the sources are served.
+    pub fn set_order(&mut self, order: FanInOrder) {
+        self.order = order;
+    }
+
+    /// Get the order in which the sources are served.
+    pub fn order(&self) -> FanInOrder {
+        self.order
+    }
+
+    /// Pop the next element along with the index of the source it came from. Fails if every
+    /// source is empty, or with the error of the first source failing for another reason, e.g. a
+    /// poisoned shared queue, which is skipped by the next call in round-robin order.
+    pub fn pop_with_source(&mut self) -> Result<(usize, T), QueueError> {
+        if self.order == FanInOrder::Priority {
+            self.next = 0;
+        }
+
+        let sources = &mut self.sources;
+        typed_queue::pop_round_robin(&mut self.next, N, |index| Some(sources[index].pop()))
+    }
+
+    /// Get a reference to the sources.
+    pub fn sources(&self) -> &[Q; N] {
+        &self.sources
+    }
+
+    /// Get a mutable reference to the sources.
+    pub fn sources_mut(&mut self) -> &mut [Q; N] {
+        &mut self.sources
+    }
+
+    /// Unwrap the sources.
+    pub fn into_inner(self) -> [Q; N] {
+        self.sources
+    }
+}
+
+impl<T: Copy, Q: TypedQueue<T>, const N: usize> TypedQueue<T> for FanIn<T, Q, N> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref(&input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref_overwrite(&input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        self.sources
+            .first_mut()
+            .ok_or(QueueError::NoRoute)?
+            .push_ref(input)
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        self.sources
+            .first_mut()
+            .ok_or(QueueError::NoRoute)?
+            .push_ref_overwrite(input)
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        self.pop_with_source().map(|(_, value)| value)
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        *output = self.pop()?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        self.sources
+            .iter_mut()
+            .try_for_each(|source| source.clear())
+    }
+
+    fn is_full(&self) -> bool {
+        self.sources.first().is_none_or(|source| source.is_full())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.sources.iter().all(|source| source.is_empty())
+    }
+
+    fn size(&self) -> usize {
+        self.sources.iter().map(|source| source.size()).sum()
+    }
+
+    fn capacity(&self) -> usize {
+        self.sources.iter().map(|source| source.capacity()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FanIn, FanInOrder};
+    use crate::basic_typed_queue::BasicTypedQueue;
+    #[cfg(feature = "threads")]
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::{QueueError, TypedQueue};
+    #[cfg(feature = "threads")]
+    use std::thread;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn round_robin() {
+        let mut fan_in = FanIn::new([BasicTypedQueue::<u32, SIZE>::new(); 3]);
+        assert_eq!(fan_in.order(), FanInOrder::RoundRobin);
+        for n in 0..3 {
+            assert!(fan_in.sources_mut()[0].push(n).is_ok());
+        }
+        assert!(fan_in.sources_mut()[2].push(20).is_ok());
+        assert_eq!(fan_in.size(), 4);
+
+        assert_eq!(fan_in.pop_with_source().unwrap(), (0, 0));
+        assert_eq!(fan_in.pop_with_source().unwrap(), (2, 20));
+        assert_eq!(fan_in.pop().unwrap(), 1);
+        assert_eq!(fan_in.pop().unwrap(), 2);
+        assert_eq!(fan_in.pop().unwrap_err(), QueueError::QueueEmpty);
+    }
+
+    #[test]
+    fn priority() {
+        let mut fan_in = FanIn::new([BasicTypedQueue::<u32, SIZE>::new(); 2]);
+        fan_in.set_order(FanInOrder::Priority);
+        for n in 0..2 {
+            assert!(fan_in.sources_mut()[0].push(n).is_ok());
+            assert!(fan_in.sources_mut()[1].push(n + 10).is_ok());
+        }
+
+        // The first source is drained before the second is served
+        let order: Vec<u32> = (0..4).map(|_| fan_in.pop().unwrap()).collect();
+        assert_eq!(order, [0, 1, 10, 11]);
+        assert!(fan_in.is_empty());
+    }
+
+    #[test]
+    fn typed_queue() {
+        // Drain any queue through the trait
+        fn drain<Q: TypedQueue<u32>>(queue: &mut Q) -> Vec<u32> {
+            let mut output = Vec::new();
+            while let Ok(value) = queue.pop() {
+                output.push(value);
+            }
+            output
+        }
+
+        let mut fan_in = FanIn::new([BasicTypedQueue::<u32, SIZE>::new(); 2]);
+        assert_eq!(fan_in.capacity(), SIZE * 2);
+        for n in 0..SIZE as u32 {
+            assert!(fan_in.push(n).is_ok());
+        }
+        assert!(fan_in.sources_mut()[1].push(100).is_ok());
+
+        // Pushes go to the first source, which is full
+        assert!(fan_in.is_full());
+        assert_eq!(fan_in.push(0).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(fan_in.sources()[0].size(), SIZE);
+        assert_eq!(drain(&mut fan_in)[..3], [0, 100, 1]);
+
+        assert!(fan_in.push(1).is_ok());
+        assert!(fan_in.clear().is_ok());
+        assert!(fan_in.is_empty());
+        let mut empty = FanIn::<u32, BasicTypedQueue<u32, SIZE>, 0>::new([]);
+        assert_eq!(empty.push(0).unwrap_err(), QueueError::NoRoute);
+        assert_eq!(empty.pop().unwrap_err(), QueueError::QueueEmpty);
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn producers() {
+        const COUNT: u32 = 1000;
+        let queues = [
+            ThreadSafeTypedQueue::<u32, SIZE>::new(),
+            ThreadSafeTypedQueue::<u32, SIZE>::new(),
+        ];
+
+        thread::scope(|scope| {
+            for mut queue in &queues {
+                scope.spawn(move || {
+                    for n in 0..COUNT {
+                        while queue.push(n).is_err() {}
+                    }
+                });
+            }
+
+            // Each source keeps its own order
+            let mut fan_in = FanIn::new([&queues[0], &queues[1]]);
+            let mut next = [0; 2];
+            while next != [COUNT; 2] {
+                if let Ok((source, value)) = fan_in.pop_with_source() {
+                    assert_eq!(value, next[source]);
+                    next[source] += 1;
+                }
+            }
+        });
+    }
+}
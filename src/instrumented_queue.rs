@@ -0,0 +1,372 @@
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::typed_queue::{QueueError, QueueStats, TypedQueue};
+
+/// Callbacks run by an `InstrumentedQueue` after each operation on the wrapped queue. Every
+/// method does nothing by default, so implementations only override the events they need.
+pub trait QueueHooks<T> {
+    /// Called after `value` was pushed, with the resulting number of elements.
+    fn on_push(&mut self, _value: &T, _size: usize) {}
+
+    /// Called after `value` was popped, with the resulting number of elements.
+    fn on_pop(&mut self, _value: &T, _size: usize) {}
+
+    /// Called after `count` elements were discarded by an overwriting push or `clear()`.
+    fn on_discard(&mut self, _count: usize) {}
+
+    /// Called after pushing `value` failed with `error`.
+    fn on_reject(&mut self, _value: &T, _error: &QueueError) {}
+}
+
+// No hooks: only the counters are maintained.
+impl<T> QueueHooks<T> for () {}
+
+// Number of time slices a rate window is divided into.
+const RATE_SLICES: usize = 10;
+
+/// Throughput of a queue over a recent time window, in elements per second.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Rate {
+    /// Elements pushed per second.
+    pub pushes: f64,
+    /// Elements popped per second.
+    pub pops: f64,
+}
+
+// Element counts of one time slice of a rate window.
+#[derive(Copy, Clone)]
+struct Slice {
+    start: Instant,
+    pushed: u64,
+    popped: u64,
+}
+
+// Sliding window of push and pop counts, divided into slices so that old events expire one
+// slice at a time instead of all at once.
+struct RateMeter {
+    window: Duration,
+    created: Instant,
+    slices: BasicTypedQueue<Slice, RATE_SLICES>,
+}
+
+impl RateMeter {
+    fn new(window: Duration) -> Self {
+        RateMeter {
+            window,
+            created: Instant::now(),
+            slices: BasicTypedQueue::new(),
+        }
+    }
+
+    fn record(&mut self, pushed: u64, popped: u64) {
+        let now = Instant::now();
+        let slice_len = self.window / RATE_SLICES as u32;
+        let newest = self.slices.size().checked_sub(1);
+        match newest.and_then(|index| self.slices.get_mut(index)) {
+            Some(slice) if now.duration_since(slice.start) < slice_len => {
+                slice.pushed += pushed;
+                slice.popped += popped;
+            }
+            _ => {
+                let _ = self.slices.push_overwrite(Slice {
+                    start: now,
+                    pushed,
+                    popped,
+                });
+            }
+        }
+    }
+
+    fn rate(&self) -> Rate {
+        let now = Instant::now();
+        let (pushed, popped) = self
+            .slices
+            .iter()
+            .filter(|slice| now.duration_since(slice.start) < self.window)
+            .fold((0, 0), |(pushed, popped), slice| {
+                (pushed + slice.pushed, popped + slice.popped)
+            });
+
+        // Before a full window has passed, only the time since creation counts
+        let elapsed = self
+            .window
+            .min(now.duration_since(self.created))
+            .as_secs_f64();
+        if elapsed == 0.0 {
+            return Rate::default();
+        }
+
+        Rate {
+            pushes: pushed as f64 / elapsed,
+            pops: popped as f64 / elapsed,
+        }
+    }
+}
+
+// Decorator layering counters and hooks over any queue, so that instrumentation does not have to
+// be built into every queue type. It is itself a `TypedQueue`, and so composes with the other
+// wrappers. Elements dropped or evicted by the wrapped queue's own overflow policy are not
+// visible to the decorator.
+pub struct InstrumentedQueue<T: Copy, Q: TypedQueue<T>, H: QueueHooks<T> = ()> {
+    queue: Q,
+    hooks: H,
+    stats: QueueStats,
+    rejected: u64,
+    rates: RateMeter,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy, Q: TypedQueue<T>> InstrumentedQueue<T, Q> {
+    /// Wrap a queue, maintaining counters only.
+    pub fn new(queue: Q) -> Self {
+        InstrumentedQueue::with_hooks(queue, ())
+    }
+}
+
+impl<T: Copy, Q: TypedQueue<T>, H: QueueHooks<T>> InstrumentedQueue<T, Q, H> {
+    /// Wrap a queue, maintaining counters and running `hooks` after each operation.
+    pub fn with_hooks(queue: Q, hooks: H) -> Self {
+        InstrumentedQueue {
+            queue,
+            hooks,
+            stats: QueueStats::default(),
+            rejected: 0,
+            rates: RateMeter::new(Duration::from_secs(1)),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the counters of elements pushed, popped and discarded through this wrapper.
+    pub fn stats(&self) -> QueueStats {
+        self.stats
+    }
+
+    /// Get the number of pushes that failed.
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+
+    /// Get the push and pop throughput over the rate window, one second by default.
+    pub fn rate(&self) -> Rate {
+        self.rates.rate()
+    }
+
+    /// Set the length of the sliding window `rate()` is measured over, discarding the counts
+    /// recorded so far. Shorter windows react faster to changes, longer ones are smoother.
+    pub fn set_rate_window(&mut self, window: Duration) {
+        self.rates = RateMeter::new(window);
+    }
+
+    /// Get a reference to the hooks.
+    pub fn hooks(&self) -> &H {
+        &self.hooks
+    }
+
+    /// Get a mutable reference to the hooks.
+    pub fn hooks_mut(&mut self) -> &mut H {
+        &mut self.hooks
+    }
+
+    /// Get a reference to the underlying queue.
+    pub fn inner(&self) -> &Q {
+        &self.queue
+    }
+
+    /// Get a mutable reference to the underlying queue. Operations performed through it bypass
+    /// the counters and hooks.
+    pub fn inner_mut(&mut self) -> &mut Q {
+        &mut self.queue
+    }
+
+    /// Unwrap the underlying queue.
+    pub fn into_inner(self) -> Q {
+        self.queue
+    }
+}
+
+impl<T: Copy, Q: TypedQueue<T>, H: QueueHooks<T>> TypedQueue<T> for InstrumentedQueue<T, Q, H> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref(&input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref_overwrite(&input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        match self.queue.push_ref(input) {
+            Ok(()) => {
+                self.stats.pushed += 1;
+                self.rates.record(1, 0);
+                self.hooks.on_push(input, self.queue.size());
+                Ok(())
+            }
+            Err(e) => {
+                self.rejected += 1;
+                self.hooks.on_reject(input, &e);
+                Err(e)
+            }
+        }
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        let evicting = self.queue.is_full();
+        if let Err(e) = self.queue.push_ref_overwrite(input) {
+            self.rejected += 1;
+            self.hooks.on_reject(input, &e);
+            return Err(e);
+        }
+
+        if evicting {
+            self.stats.discarded += 1;
+            self.hooks.on_discard(1);
+        }
+        self.stats.pushed += 1;
+        self.rates.record(1, 0);
+        self.hooks.on_push(input, self.queue.size());
+
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        let value = self.queue.pop()?;
+        self.stats.popped += 1;
+        self.rates.record(0, 1);
+        self.hooks.on_pop(&value, self.queue.size());
+
+        Ok(value)
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        *output = self.pop()?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        let count = self.queue.size();
+        self.queue.clear()?;
+        self.stats.discarded += count as u64;
+        self.hooks.on_discard(count);
+
+        Ok(())
+    }
+
+    fn is_full(&self) -> bool {
+        self.queue.is_full()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    fn size(&self) -> usize {
+        self.queue.size()
+    }
+
+    fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InstrumentedQueue;
+    #[cfg(feature = "threads")]
+    use super::QueueHooks;
+    use crate::basic_typed_queue::BasicTypedQueue;
+    #[cfg(feature = "threads")]
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::{QueueError, QueueStats, TypedQueue};
+    use std::thread;
+    use std::time::Duration;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    // Hooks tracking the largest size seen and the sum of popped values.
+    #[cfg(feature = "threads")]
+    #[derive(Default)]
+    struct Watermark {
+        high: usize,
+        popped_sum: u32,
+        rejected: Vec<QueueError>,
+    }
+
+    #[cfg(feature = "threads")]
+    impl QueueHooks<u32> for Watermark {
+        fn on_push(&mut self, _value: &u32, size: usize) {
+            self.high = self.high.max(size);
+        }
+
+        fn on_pop(&mut self, value: &u32, _size: usize) {
+            self.popped_sum += value;
+        }
+
+        fn on_reject(&mut self, _value: &u32, error: &QueueError) {
+            self.rejected.push(error.clone());
+        }
+    }
+
+    #[test]
+    fn counters() {
+        let mut queue = InstrumentedQueue::new(BasicTypedQueue::<u32, SIZE>::default());
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert_eq!(queue.push(100).unwrap_err(), QueueError::QueueFull);
+        assert!(queue.push_overwrite(100).is_ok());
+        assert_eq!(queue.pop().unwrap(), 1);
+        assert!(queue.clear().is_ok());
+
+        assert_eq!(
+            queue.stats(),
+            QueueStats {
+                pushed: SIZE as u64 + 1,
+                popped: 1,
+                discarded: SIZE as u64,
+            }
+        );
+        assert_eq!(queue.rejected(), 1);
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn hooks() {
+        let shared = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        let mut queue = InstrumentedQueue::with_hooks(&shared, Watermark::default());
+        for n in 1..=SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(queue.push(0).is_err());
+        while queue.pop().is_ok() {}
+
+        let hooks = queue.hooks();
+        assert_eq!(hooks.high, SIZE);
+        assert_eq!(hooks.popped_sum, (1..=SIZE as u32).sum());
+        assert_eq!(hooks.rejected, [QueueError::QueueFull]);
+    }
+
+    #[test]
+    fn rate() {
+        let mut queue = InstrumentedQueue::new(BasicTypedQueue::<u32, SIZE>::default());
+        queue.set_rate_window(Duration::from_millis(100));
+        assert_eq!(queue.rate().pushes, 0.0);
+
+        thread::sleep(Duration::from_millis(20));
+        for n in 0..10 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(queue.pop().is_ok());
+
+        // 10 pushes within at least 20ms, i.e. at most 500 per second
+        let rate = queue.rate();
+        assert!(rate.pushes > 0.0 && rate.pushes <= 500.0);
+        assert!((rate.pushes - rate.pops * 10.0).abs() < 1e-6);
+
+        // Counts expire once they fall out of the window
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(queue.rate().pushes, 0.0);
+    }
+}
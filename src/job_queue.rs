@@ -0,0 +1,224 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+/// Unit of work run by a `JobWorker`.
+pub type Job = Box<dyn FnOnce() + Send + 'static>;
+
+// Owning pointer to a boxed job. Queues require `Copy` elements, so jobs are stored as raw
+// pointers and only turned back into boxes once popped, or when the queue is dropped.
+#[derive(Copy, Clone)]
+struct JobHandle(*mut Job);
+
+// The handle owns its job, which is `Send`, and only ever leaves the queue once.
+unsafe impl Send for JobHandle {}
+
+// Thread-safe queue of boxed closures, turning `ThreadSafeTypedQueue` into a ready-made work
+// dispatcher: any thread can push jobs, and `JobWorker::run()` executes them on the threads that
+// should do the work. Jobs never overwrite each other, so a full queue rejects new jobs instead of
+// losing old ones. Jobs still queued when the queue is dropped are dropped without running.
+pub struct JobQueue<const CAPACITY: usize> {
+    queue: ThreadSafeTypedQueue<JobHandle, CAPACITY>,
+}
+
+impl<const CAPACITY: usize> JobQueue<CAPACITY> {
+    /// Create a new job queue.
+    pub fn new() -> Self {
+        JobQueue {
+            queue: ThreadSafeTypedQueue::new(),
+        }
+    }
+
+    /// Push a closure to be run by a worker. Fails if queue is full or closed, in which case the
+    /// closure is dropped without running.
+    pub fn push<F: FnOnce() + Send + 'static>(&self, job: F) -> Result<(), QueueError> {
+        self.push_boxed(Box::new(job))
+    }
+
+    /// Push an already boxed job. Fails if queue is full or closed, in which case the job is
+    /// dropped without running.
+    pub fn push_boxed(&self, job: Job) -> Result<(), QueueError> {
+        let handle = JobHandle(Box::into_raw(Box::new(job)));
+        let result = (&self.queue).push(handle);
+        if result.is_err() {
+            // The queue did not take ownership
+            drop(unsafe { Box::from_raw(handle.0) });
+        }
+
+        result
+    }
+
+    /// Pop the oldest job without running it. Fails if queue is empty.
+    pub fn pop(&self) -> Result<Job, QueueError> {
+        let handle = (&self.queue).pop()?;
+        Ok(*unsafe { Box::from_raw(handle.0) })
+    }
+
+    /// Pop the oldest job and run it on the current thread. Fails if queue is empty.
+    pub fn run_next(&self) -> Result<(), QueueError> {
+        let job = self.pop()?;
+        job();
+        Ok(())
+    }
+
+    /// Close the queue: later pushes fail with `QueueError::Closed`, and workers return once the
+    /// remaining jobs have been run.
+    pub fn close(&self) -> Result<(), QueueError> {
+        self.queue.close()
+    }
+
+    /// Check if the queue has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.queue.is_closed()
+    }
+
+    /// Get the number of queued jobs.
+    pub fn size(&self) -> usize {
+        self.queue.size()
+    }
+
+    /// Check if no jobs are queued.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Get the capacity of the queue.
+    pub fn capacity(&self) -> usize {
+        CAPACITY
+    }
+}
+
+impl<const CAPACITY: usize> Default for JobQueue<CAPACITY> {
+    fn default() -> Self {
+        JobQueue::new()
+    }
+}
+
+impl<const CAPACITY: usize> Drop for JobQueue<CAPACITY> {
+    // Free the jobs that were never run.
+    fn drop(&mut self) {
+        while let Ok(job) = self.pop() {
+            drop(job);
+        }
+    }
+}
+
+/// Loop executing jobs from a `JobQueue` on the current thread.
+pub struct JobWorker;
+
+impl JobWorker {
+    // How long an idle worker waits before polling the queue again
+    const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+    /// Run jobs from `queue` until it is closed and empty, returning the number of jobs run.
+    /// Panics raised by a job propagate to the caller.
+    pub fn run<const CAPACITY: usize>(queue: &JobQueue<CAPACITY>) -> Result<usize, QueueError> {
+        JobWorker::run_with_interval(queue, JobWorker::POLL_INTERVAL)
+    }
+
+    /// Same as `run()`, waiting `poll_interval` before polling again while the queue is empty.
+    pub fn run_with_interval<const CAPACITY: usize>(
+        queue: &JobQueue<CAPACITY>,
+        poll_interval: Duration,
+    ) -> Result<usize, QueueError> {
+        let mut count = 0;
+        loop {
+            // Once closed, nothing more can be pushed, so an empty queue stays empty
+            let closed = queue.is_closed();
+            match queue.run_next() {
+                Ok(()) => count += 1,
+                Err(QueueError::QueueEmpty) if closed => return Ok(count),
+                Err(QueueError::QueueEmpty) => thread::sleep(poll_interval),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JobQueue, JobWorker};
+    use crate::typed_queue::QueueError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn run_next() {
+        let queue = JobQueue::<SIZE>::default();
+        let counter = Arc::new(AtomicUsize::new(0));
+        for n in 1..=SIZE {
+            let counter = Arc::clone(&counter);
+            assert!(queue
+                .push(move || {
+                    counter.fetch_add(n, Ordering::Relaxed);
+                })
+                .is_ok());
+        }
+        assert_eq!(queue.push(|| {}).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(queue.size(), SIZE);
+
+        assert!(queue.run_next().is_ok());
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+        let job = queue.pop().unwrap();
+        job();
+        assert_eq!(counter.load(Ordering::Relaxed), 3);
+
+        // Queued jobs are freed without running when the queue is dropped
+        drop(queue);
+        assert_eq!(counter.load(Ordering::Relaxed), 3);
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn closed() {
+        let queue = JobQueue::<SIZE>::default();
+        assert!(queue.push(|| {}).is_ok());
+        assert!(queue.close().is_ok());
+        assert!(queue.is_closed());
+        assert_eq!(queue.push(|| {}).unwrap_err(), QueueError::Closed);
+
+        // Remaining jobs still run before the worker returns
+        assert_eq!(JobWorker::run(&queue), Ok(1));
+    }
+
+    #[test]
+    fn workers() {
+        const COUNT: usize = 1000;
+        let queue = JobQueue::<SIZE>::default();
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let runs = thread::scope(|scope| {
+            let workers: Vec<_> = (0..3)
+                .map(|_| scope.spawn(|| JobWorker::run(&queue).unwrap()))
+                .collect();
+
+            for _ in 0..COUNT {
+                // A rejected job is dropped, so a fresh one is built for every attempt
+                loop {
+                    let counter = Arc::clone(&counter);
+                    let job = move || {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                    };
+                    if queue.push(job).is_ok() {
+                        break;
+                    }
+                }
+            }
+            assert!(queue.close().is_ok());
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .sum::<usize>()
+        });
+
+        assert_eq!(runs, COUNT);
+        assert_eq!(counter.load(Ordering::Relaxed), COUNT);
+    }
+}
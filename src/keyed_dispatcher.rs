@@ -0,0 +1,241 @@
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+/// Identifier of a consumer registered with a `KeyedDispatcher`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ConsumerId(u64);
+
+// Dispatcher giving each consumer of a parallel pool its own queue, and routing elements by key
+// so that elements with the same key always reach the same consumer, in order. Keys are mapped to
+// consumers by consistent hashing: each consumer owns `replicas` points on a hash ring, so adding
+// or removing a consumer only moves the keys adjacent to its points.
+pub struct KeyedDispatcher<T: Copy, const CAPACITY: usize> {
+    consumers: Vec<(ConsumerId, Arc<ThreadSafeTypedQueue<T, CAPACITY>>)>,
+    ring: Vec<(u64, ConsumerId)>, // sorted by hash
+    replicas: usize,
+    next_id: u64,
+}
+
+impl<T: Copy, const CAPACITY: usize> KeyedDispatcher<T, CAPACITY> {
+    /// Create a dispatcher without consumers, placing `replicas` ring points per consumer. More
+    /// replicas spread keys more evenly at the cost of a larger ring.
+    pub fn new(replicas: usize) -> Self {
+        KeyedDispatcher {
+            consumers: Vec::new(),
+            ring: Vec::new(),
+            replicas: replicas.max(1),
+            next_id: 0,
+        }
+    }
+
+    /// Register a new consumer, returning its identifier and the queue it should pop from.
+    pub fn add_consumer(&mut self) -> (ConsumerId, Arc<ThreadSafeTypedQueue<T, CAPACITY>>) {
+        let id = ConsumerId(self.next_id);
+        self.next_id += 1;
+
+        let queue = Arc::new(ThreadSafeTypedQueue::new());
+        self.consumers.push((id, Arc::clone(&queue)));
+        for replica in 0..self.replicas {
+            self.ring.push((hash(&(id, replica)), id));
+        }
+        self.ring.sort_unstable();
+
+        (id, queue)
+    }
+
+    /// Unregister a consumer, returning its queue so that any remaining elements can be drained.
+    /// Keys that mapped to it are spread over the remaining consumers.
+    pub fn remove_consumer(
+        &mut self,
+        id: ConsumerId,
+    ) -> Option<Arc<ThreadSafeTypedQueue<T, CAPACITY>>> {
+        let index = self.consumers.iter().position(|(other, _)| *other == id)?;
+        self.ring.retain(|(_, other)| *other != id);
+        Some(self.consumers.swap_remove(index).1)
+    }
+
+    /// Get the number of registered consumers.
+    pub fn consumers(&self) -> usize {
+        self.consumers.len()
+    }
+
+    /// Get the consumer that elements with the given key are dispatched to, or `None` if there
+    /// are no consumers.
+    pub fn route<K: Hash + ?Sized>(&self, key: &K) -> Option<ConsumerId> {
+        if self.ring.is_empty() {
+            return None;
+        }
+
+        let key_hash = hash(key);
+        let index = self.ring.partition_point(|(point, _)| *point < key_hash);
+        Some(self.ring[index % self.ring.len()].1)
+    }
+
+    /// Push an element to the queue of the consumer owning `key`, returning that consumer. Fails
+    /// with `QueueError::NoRoute` if there are no consumers, or if the consumer's queue fails.
+    pub fn dispatch<K: Hash + ?Sized>(&self, key: &K, input: T) -> Result<ConsumerId, QueueError> {
+        let id = self.route(key).ok_or(QueueError::NoRoute)?;
+        let mut queue = self.queue(id).ok_or(QueueError::NoRoute)?;
+        queue.push(input)?;
+        Ok(id)
+    }
+
+    /// Get the queue of a registered consumer.
+    pub fn queue(&self, id: ConsumerId) -> Option<&ThreadSafeTypedQueue<T, CAPACITY>> {
+        self.consumers
+            .iter()
+            .find(|(other, _)| *other == id)
+            .map(|(_, queue)| &**queue)
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize> Default for KeyedDispatcher<T, CAPACITY> {
+    fn default() -> Self {
+        KeyedDispatcher::new(64)
+    }
+}
+
+// Deterministic hash, so that key placement is stable across runs and Rust releases, unlike with
+// the standard library's `DefaultHasher`, whose algorithm is unspecified.
+fn hash<K: Hash + ?Sized>(key: &K) -> u64 {
+    let mut hasher = FnvHasher::default();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+// 64-bit FNV-1a hasher. Short keys differing in their last bytes only spread over a few bits of
+// FNV-1a, so the result is finalized with MurmurHash3's mixer to spread them around the ring.
+// Integers are fed as little-endian bytes, with `usize` widened to 64 bits, so that the ring is
+// the same on 32-bit and big-endian targets.
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, value: u128) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = self.0;
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        hash ^ (hash >> 33)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash, FnvHasher, KeyedDispatcher};
+    use crate::typed_queue::{QueueError, TypedQueue};
+    use std::hash::Hasher;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn dispatch_per_key_order() {
+        let mut dispatcher = KeyedDispatcher::<u32, SIZE>::default();
+        assert_eq!(
+            dispatcher.dispatch("a", 0).unwrap_err(),
+            QueueError::NoRoute
+        );
+
+        let consumers: Vec<_> = (0..4).map(|_| dispatcher.add_consumer()).collect();
+        let keys = ["a", "b", "c", "d", "e"];
+        for n in 0..4 {
+            for (k, key) in keys.iter().enumerate() {
+                let id = dispatcher.dispatch(key, (k * 10 + n) as u32).unwrap();
+                assert_eq!(Some(id), dispatcher.route(key));
+            }
+        }
+
+        // Each key's elements sit in the queue of its consumer, in order
+        let mut next = [0; 5];
+        for (id, queue) in &consumers {
+            queue
+                .drain(|value| {
+                    let (k, n) = (value as usize / 10, value as usize % 10);
+                    assert_eq!(dispatcher.route(keys[k]), Some(*id));
+                    assert_eq!(n, next[k]);
+                    next[k] += 1;
+                })
+                .unwrap();
+        }
+        assert_eq!(next, [4; 5]);
+    }
+
+    #[test]
+    fn consistent_remap() {
+        let mut dispatcher = KeyedDispatcher::<u32, SIZE>::default();
+        let ids: Vec<_> = (0..4).map(|_| dispatcher.add_consumer().0).collect();
+
+        let before: Vec<_> = (0..1000)
+            .map(|key| dispatcher.route(&key).unwrap())
+            .collect();
+        for id in &ids {
+            assert!(before.contains(id));
+        }
+
+        let removed = dispatcher.remove_consumer(ids[0]).unwrap();
+        assert!(removed.is_empty());
+        assert_eq!(dispatcher.consumers(), 3);
+        assert!(dispatcher.remove_consumer(ids[0]).is_none());
+
+        // Only keys of the removed consumer move
+        for (key, id) in (0..1000).zip(before) {
+            let now = dispatcher.route(&key).unwrap();
+            if id == ids[0] {
+                assert_ne!(now, id);
+            } else {
+                assert_eq!(now, id);
+            }
+        }
+    }
+
+    #[test]
+    fn stable_hash() {
+        // Reference values, which must not change between builds
+        assert_eq!(hash(&0u8), 0xb903_4ad3_7056_f5fb);
+        let mut hasher = FnvHasher::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0x82a2_a958_a9be_ce5b);
+
+        // Integers hash the same regardless of the target's endianness and pointer width
+        assert_eq!(hash(&1u32), 0xdc07_7c17_952d_9c36);
+        assert_eq!(hash(&1usize), hash(&1u64));
+        assert_eq!(hash(&1u64), 0x4a3a_3a4b_a652_3826);
+    }
+}
@@ -0,0 +1,191 @@
+use std::time::{Duration, Instant};
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::typed_queue::TypedQueue;
+
+/// Number of most recent handoff latency samples kept by a probe.
+pub const PROBE_WINDOW: usize = 64;
+
+// Opt-in probe measuring push-to-pop handoff latency. Every `interval`-th pushed element serves
+// as a marker: its push time is recorded, and the time until it is popped is added to a rolling
+// window of samples. Only one marker is in flight at a time, so elements never need to be
+// wrapped and the cost for unmarked elements is a couple of counter updates.
+#[derive(Copy, Clone)]
+pub struct LatencyProbe {
+    interval: usize,
+    pushed: u64,
+    popped: u64,
+    marker: Option<(u64, Instant)>,
+    samples: BasicTypedQueue<Duration, PROBE_WINDOW>,
+}
+
+impl LatencyProbe {
+    /// Create a probe marking every `interval`-th pushed element. An interval of zero disables
+    /// the probe.
+    pub fn new(interval: usize) -> Self {
+        LatencyProbe {
+            interval,
+            pushed: 0,
+            popped: 0,
+            marker: None,
+            samples: BasicTypedQueue::new(),
+        }
+    }
+
+    /// Check if the probe is measuring.
+    pub fn is_enabled(&self) -> bool {
+        self.interval > 0
+    }
+
+    /// Get the interval between marked elements.
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    /// Get the number of latency samples currently in the rolling window.
+    pub fn sample_count(&self) -> usize {
+        self.samples.size()
+    }
+
+    /// Estimate the given percentile (0.0 to 1.0) of the recent handoff latencies, or `None` if
+    /// no samples have been recorded yet.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        window_percentile(&self.samples, percentile)
+    }
+}
+
+// Hooks called by the thread-safe queue, the only queue a probe can be attached to.
+#[cfg_attr(not(feature = "threads"), allow(dead_code))]
+impl LatencyProbe {
+    // Record `count` elements entering the queue, marking one if it is due.
+    pub(crate) fn on_push(&mut self, count: usize) {
+        if self.is_enabled() && self.marker.is_none() && count > 0 {
+            let interval = self.interval as u64;
+            let next = self.pushed.div_ceil(interval) * interval;
+            if next < self.pushed + count as u64 {
+                self.marker = Some((next, Instant::now()));
+            }
+        }
+        self.pushed += count as u64;
+    }
+
+    // Start counting afresh from a queue holding `queued` elements, e.g. because they were
+    // swapped in or queued before the probe was attached. Their push times are unknown, so the
+    // next marker is placed after them, and any marker in flight is dropped.
+    pub(crate) fn restart(&mut self, queued: usize) {
+        self.pushed = queued as u64;
+        self.popped = 0;
+        self.marker = None;
+    }
+
+    // Record `count` elements being popped, sampling the marker if it was among them.
+    pub(crate) fn on_pop(&mut self, count: usize) {
+        self.leave(count, true);
+    }
+
+    // Record `count` elements being discarded without reaching a consumer.
+    pub(crate) fn on_discard(&mut self, count: usize) {
+        self.leave(count, false);
+    }
+
+    fn leave(&mut self, count: usize, sample: bool) {
+        let end = self.popped + count as u64;
+        if let Some((seq, pushed_at)) = self.marker {
+            if seq < end {
+                if sample {
+                    let _ = self.samples.push_overwrite(pushed_at.elapsed());
+                }
+                self.marker = None;
+            }
+        }
+        self.popped = end;
+    }
+}
+
+// Estimate the given percentile (0.0 to 1.0) of a window of latency samples, or `None` if it is
+// empty.
+pub(crate) fn window_percentile(
+    samples: &BasicTypedQueue<Duration, PROBE_WINDOW>,
+    percentile: f64,
+) -> Option<Duration> {
+    let count = samples.size();
+    if count == 0 {
+        return None;
+    }
+
+    let mut sorted = [Duration::ZERO; PROBE_WINDOW];
+    let (first, second) = samples.as_slices();
+    sorted[..first.len()].copy_from_slice(first);
+    sorted[first.len()..count].copy_from_slice(second);
+    sorted[..count].sort_unstable();
+
+    let rank = (percentile.clamp(0.0, 1.0) * (count - 1) as f64).round() as usize;
+    Some(sorted[rank])
+}
+
+impl Default for LatencyProbe {
+    fn default() -> Self {
+        LatencyProbe::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LatencyProbe, PROBE_WINDOW};
+    use crate::typed_queue::TypedQueue;
+    use std::time::Duration;
+
+    #[test]
+    fn disabled() {
+        let mut probe = LatencyProbe::default();
+        assert!(!probe.is_enabled());
+
+        probe.on_push(100);
+        probe.on_pop(100);
+        assert_eq!(probe.sample_count(), 0);
+        assert_eq!(probe.percentile(0.5), None);
+    }
+
+    #[test]
+    fn marks_every_interval() {
+        let mut probe = LatencyProbe::new(4);
+
+        // Element 0 is marked and sampled when popped
+        probe.on_push(1);
+        probe.on_pop(1);
+        assert_eq!(probe.sample_count(), 1);
+
+        // Elements 1-3 are not marked, element 4 is
+        probe.on_push(3);
+        probe.on_pop(3);
+        assert_eq!(probe.sample_count(), 1);
+        probe.on_push(2);
+        probe.on_pop(2);
+        assert_eq!(probe.sample_count(), 2);
+    }
+
+    #[test]
+    fn discarded_marker() {
+        let mut probe = LatencyProbe::new(1);
+        probe.on_push(2);
+        probe.on_discard(1);
+        assert_eq!(probe.sample_count(), 0);
+
+        // A new marker is placed once the discarded one is gone
+        probe.on_push(1);
+        probe.on_pop(2);
+        assert_eq!(probe.sample_count(), 1);
+    }
+
+    #[test]
+    fn percentile() {
+        let mut probe = LatencyProbe::new(1);
+        for n in 0..PROBE_WINDOW as u64 {
+            let _ = probe.samples.push_overwrite(Duration::from_millis(n));
+        }
+
+        assert_eq!(probe.percentile(0.0), Some(Duration::from_millis(0)));
+        assert_eq!(probe.percentile(1.0), Some(Duration::from_millis(63)));
+        assert_eq!(probe.percentile(0.5), Some(Duration::from_millis(32)));
+    }
+}
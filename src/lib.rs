@@ -1,3 +1,68 @@
+#[cfg(feature = "threads")]
+pub mod ack_queue;
+#[cfg(feature = "alloc")]
+pub mod async_queue;
 pub mod basic_typed_queue;
+pub mod bip_buffer;
+#[cfg(feature = "threads")]
+pub mod broadcast_queue;
+pub mod broadcast_ring;
+#[cfg(feature = "bytes")]
+mod byte_buf;
+mod byte_io;
+pub mod coalescing_queue;
+#[cfg(feature = "cxx")]
+mod cxx_bridge;
+pub mod dead_letter_queue;
+#[cfg(feature = "defmt")]
+mod defmt_format;
+pub mod delay_queue;
+#[cfg(feature = "serde")]
+mod encoding;
+mod enum_queue;
+pub mod expiring_queue;
+pub mod fan_in;
+pub mod instrumented_queue;
+#[cfg(all(feature = "alloc", feature = "threads"))]
+pub mod job_queue;
+#[cfg(feature = "threads")]
+pub mod keyed_dispatcher;
+pub mod latency_probe;
+pub mod lossy_queue;
+pub mod median_filter;
+pub mod message_queue;
+pub mod monotonic_queue;
+#[cfg(feature = "persistent")]
+pub mod persistent_queue;
+pub mod presets;
+pub mod priority_levels;
+pub mod queue_builder;
+#[cfg(feature = "metrics")]
+mod queue_metrics;
+pub mod queue_router;
+#[cfg(feature = "tracing")]
+mod queue_tracing;
+#[cfg(feature = "threads")]
+pub mod reclaimer;
+pub mod retry_queue;
+pub mod rolling_stats;
+pub mod sequenced_queue;
+#[cfg(feature = "threads")]
+pub mod sharded_queue;
+pub mod shm_queue;
+pub mod stable_priority_queue;
+pub mod storage;
+#[cfg(feature = "threads")]
 pub mod thread_safe_typed_queue;
+pub mod timed_queue;
+pub mod typed_deque;
+pub mod typed_priority_queue;
 pub mod typed_queue;
+pub mod typed_stack;
+#[cfg(feature = "alloc")]
+pub mod untyped_queue;
+#[cfg(feature = "threads")]
+pub mod watch;
+pub mod work_stealing;
+#[cfg(feature = "threads")]
+pub mod worker_pool;
@@ -0,0 +1,167 @@
+use std::marker::PhantomData;
+
+use crate::typed_queue::{self, QueueError, TypedQueue};
+
+/// Which element a `LossyQueue` sacrifices when the underlying queue is full.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DropPolicy {
+    /// Evict the oldest queued element to make room for the new one.
+    Oldest,
+    /// Discard the element being pushed.
+    Newest,
+}
+
+// Wrapper turning any queue into one whose push never fails. When the underlying queue is full,
+// an element is dropped according to the policy, counted, and handed back to the caller, so call
+// sites that cannot handle errors (FFI callbacks, interrupt handlers) still get observability.
+// Works with shared queues too, e.g. `LossyQueue<T, &ThreadSafeTypedQueue<T, N>>`.
+pub struct LossyQueue<T: Copy, Q: TypedQueue<T>> {
+    queue: Q,
+    policy: DropPolicy,
+    pushed: u64,
+    dropped: u64,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy, Q: TypedQueue<T>> LossyQueue<T, Q> {
+    /// Wrap a queue, dropping elements according to `policy` when it is full.
+    pub fn new(queue: Q, policy: DropPolicy) -> Self {
+        LossyQueue {
+            queue,
+            policy,
+            pushed: 0,
+            dropped: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Push an element to the queue. If an element had to be dropped to do so, it is returned:
+    /// the evicted element with `DropPolicy::Oldest` (the last one, if concurrent producers forced
+    /// several evictions), or `input` itself with `DropPolicy::Newest`, if the underlying queue
+    /// failed for any reason other than being full, or if nothing could be evicted to make room,
+    /// e.g. because every slot of a shared queue is held by unpublished elements.
+    pub fn push(&mut self, input: T) -> Option<T> {
+        let mut sacrificed = None;
+        let result = match self.policy {
+            DropPolicy::Oldest => typed_queue::push_evicting(&mut self.queue, &input, |evicted| {
+                self.dropped += 1;
+                sacrificed = Some(evicted);
+                Ok(())
+            }),
+            DropPolicy::Newest => self.queue.push_ref(&input),
+        };
+
+        if result.is_err() {
+            self.dropped += 1;
+            return Some(input);
+        }
+        self.pushed += 1;
+        sacrificed
+    }
+
+    /// Pop an element from the queue by value. Fails if queue is empty.
+    pub fn pop(&mut self) -> Result<T, QueueError> {
+        self.queue.pop()
+    }
+
+    /// Get the policy used when the queue is full.
+    pub fn policy(&self) -> DropPolicy {
+        self.policy
+    }
+
+    /// Get the number of elements successfully pushed through this wrapper.
+    pub fn pushed(&self) -> u64 {
+        self.pushed
+    }
+
+    /// Get the number of elements dropped by this wrapper, whether evicted or rejected.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Get a reference to the underlying queue.
+    pub fn inner(&self) -> &Q {
+        &self.queue
+    }
+
+    /// Get a mutable reference to the underlying queue.
+    pub fn inner_mut(&mut self) -> &mut Q {
+        &mut self.queue
+    }
+
+    /// Unwrap the underlying queue.
+    pub fn into_inner(self) -> Q {
+        self.queue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DropPolicy, LossyQueue};
+    use crate::basic_typed_queue::BasicTypedQueue;
+    #[cfg(feature = "threads")]
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    #[cfg(feature = "threads")]
+    use crate::typed_queue::TypedQueue;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn drop_oldest() {
+        let mut queue =
+            LossyQueue::new(BasicTypedQueue::<u32, SIZE>::default(), DropPolicy::Oldest);
+        for n in 0..SIZE as u32 {
+            assert_eq!(queue.push(n), None);
+        }
+
+        assert_eq!(queue.push(100), Some(0));
+        assert_eq!(queue.push(101), Some(1));
+        assert_eq!(queue.pushed(), SIZE as u64 + 2);
+        assert_eq!(queue.dropped(), 2);
+
+        for n in 2..SIZE as u32 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert_eq!(queue.pop().unwrap(), 100);
+        assert_eq!(queue.pop().unwrap(), 101);
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn drop_newest() {
+        let shared = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        let mut queue = LossyQueue::new(&shared, DropPolicy::Newest);
+        for n in 0..SIZE as u32 {
+            assert_eq!(queue.push(n), None);
+        }
+
+        assert_eq!(queue.push(100), Some(100));
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(queue.pushed(), SIZE as u64);
+        assert!(queue.inner().is_full());
+
+        for n in 0..SIZE as u32 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert!(shared.is_empty());
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn nothing_to_evict() {
+        let shared = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 {
+            assert!(shared.push_unpublished(n).is_ok());
+        }
+
+        // Every slot is taken but none can be popped, so the new element is dropped instead
+        let mut queue = LossyQueue::new(&shared, DropPolicy::Oldest);
+        assert_eq!(queue.push(100), Some(100));
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(queue.pushed(), 0);
+
+        assert!(shared.publish_after().is_ok());
+        assert_eq!(queue.push(101), Some(0));
+    }
+}
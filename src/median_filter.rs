@@ -0,0 +1,139 @@
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::typed_queue::TypedQueue;
+
+// Median of the last `N` samples, for smoothing noisy sensor readings: unlike a moving average,
+// a single outlier does not shift the output. Next to the samples in arrival order, it keeps a
+// sorted copy of them, so each push costs O(N) to move the evicted and new samples in and out of
+// the sorted copy, and the median is read in O(1). Samples that are not comparable with each
+// other, such as NaN, are sorted after every other sample.
+pub struct MedianFilter<T: Copy, const N: usize> {
+    samples: BasicTypedQueue<T, N>,
+    sorted: BasicTypedQueue<T, N>,
+}
+
+impl<T: Copy + PartialOrd, const N: usize> MedianFilter<T, N> {
+    /// Create an empty filter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero.
+    pub fn new() -> Self {
+        assert!(N > 0, "median filter window size must be non-zero");
+
+        MedianFilter {
+            samples: BasicTypedQueue::new(),
+            sorted: BasicTypedQueue::new(),
+        }
+    }
+
+    /// Add a sample, evicting the oldest one if the window is full, and return the median of the
+    /// window including the new sample.
+    pub fn push(&mut self, sample: T) -> T {
+        // The window is never empty, so no error can occur
+        if let Ok(Some(evicted)) = self.samples.push_evict(sample) {
+            let index = self.sorted.position(|&value| {
+                value == evicted || (is_incomparable(value) && is_incomparable(evicted))
+            });
+            if let Some(index) = index {
+                self.sorted.remove(index);
+            }
+        }
+
+        let index = self
+            .sorted
+            .position(|&value| sorts_before(sample, value))
+            .unwrap_or(self.sorted.size());
+        let _ = self.sorted.insert(index, sample);
+
+        self.sorted[self.sorted.size() / 2]
+    }
+
+    /// Get the median of the samples in the window, or `None` if it is empty. For an even number
+    /// of samples, this is the upper of the two middle samples, so that the result is always one
+    /// of the samples.
+    pub fn median(&self) -> Option<T> {
+        self.sorted.get(self.sorted.size() / 2).copied()
+    }
+
+    /// Get the number of samples in the window.
+    pub fn len(&self) -> usize {
+        self.samples.size()
+    }
+
+    /// Check if the window holds no samples.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Get the samples in the window, oldest first.
+    pub fn samples(&self) -> &BasicTypedQueue<T, N> {
+        &self.samples
+    }
+
+    /// Remove every sample from the window.
+    pub fn clear(&mut self) {
+        let _ = self.samples.clear();
+        let _ = self.sorted.clear();
+    }
+}
+
+// Check if a value cannot be compared with itself, such as NaN.
+fn is_incomparable<T: PartialOrd>(value: T) -> bool {
+    value.partial_cmp(&value).is_none()
+}
+
+// Order of the sorted samples: incomparable values go after every other value.
+fn sorts_before<T: PartialOrd>(a: T, b: T) -> bool {
+    match a.partial_cmp(&b) {
+        Some(order) => order.is_lt(),
+        None => is_incomparable(b) && !is_incomparable(a),
+    }
+}
+
+impl<T: Copy + PartialOrd, const N: usize> Default for MedianFilter<T, N> {
+    fn default() -> Self {
+        MedianFilter::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MedianFilter;
+
+    // Arbitrary window size for tests
+    const SIZE: usize = 5;
+
+    #[test]
+    fn median() {
+        let mut filter = MedianFilter::<i32, SIZE>::default();
+        assert_eq!(filter.median(), None);
+
+        // Running median while the window fills up
+        assert_eq!(filter.push(10), 10);
+        assert_eq!(filter.push(30), 30);
+        assert_eq!(filter.push(20), 20);
+        assert_eq!(filter.push(20), 20);
+        assert_eq!(filter.push(-5), 20);
+        assert_eq!(filter.len(), SIZE);
+
+        // 10 and then 30 are evicted
+        assert_eq!(filter.push(0), 20);
+        assert_eq!(filter.push(1), 1);
+        assert_eq!(filter.median(), Some(1));
+
+        filter.clear();
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn outliers() {
+        let mut filter = MedianFilter::<f32, SIZE>::default();
+        let readings = [1.0, 1.1, 250.0, 0.9, 1.0, f32::NAN, 1.2, 1.1];
+        let smoothed: Vec<f32> = readings.iter().map(|&r| filter.push(r)).collect();
+
+        // Once three samples are in, single spikes never reach the output
+        assert!(smoothed[2..]
+            .iter()
+            .all(|value| (0.9..=1.2).contains(value)));
+    }
+}
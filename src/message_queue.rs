@@ -0,0 +1,212 @@
+use std::io;
+
+use crate::typed_queue::QueueError;
+
+// Size of the length prefix stored before each message.
+const HEADER: usize = 4;
+
+// Queue of variable-length byte messages, e.g. serialized packets, stored back to back in a fixed
+// ring of `BYTES` bytes. Each message is preceded by its length as a little-endian `u32`, and may
+// wrap around the end of the ring, so no space is wasted on padding: a message of `len` bytes takes
+// up `len + 4` bytes. Not thread-safe.
+pub struct MessageQueue<const BYTES: usize> {
+    buffer: [u8; BYTES],
+    head: usize, // index of the first byte of the oldest message
+    used: usize, // number of bytes taken up by messages and their prefixes
+    count: usize,
+}
+
+impl<const BYTES: usize> MessageQueue<BYTES> {
+    /// Create a new, empty message queue.
+    pub fn new() -> Self {
+        MessageQueue {
+            buffer: [0; BYTES],
+            head: 0,
+            used: 0,
+            count: 0,
+        }
+    }
+
+    /// Push a message. Fails if queue does not have room for the message and its 4-byte length
+    /// prefix, including when the message could never fit.
+    pub fn push_msg(&mut self, msg: &[u8]) -> Result<(), QueueError> {
+        let len = u32::try_from(msg.len()).map_err(|_| QueueError::QueueFull)?;
+        if HEADER + msg.len() > self.free_bytes() {
+            return Err(QueueError::QueueFull);
+        }
+
+        let tail = (self.head + self.used) % BYTES;
+        self.copy_in(tail, &len.to_le_bytes());
+        self.copy_in((tail + HEADER) % BYTES, msg);
+        self.used += HEADER + msg.len();
+        self.count += 1;
+
+        Ok(())
+    }
+
+    /// Pop the oldest message into `output`, returning its length. Fails if queue is empty, or
+    /// with `QueueError::Io(io::ErrorKind::InvalidInput)` if `output` is shorter than the message,
+    /// which then stays in the queue.
+    pub fn pop_msg(&mut self, output: &mut [u8]) -> Result<usize, QueueError> {
+        let len = self.peek_len().ok_or(QueueError::QueueEmpty)?;
+        let output = output
+            .get_mut(..len)
+            .ok_or(QueueError::Io(io::ErrorKind::InvalidInput))?;
+
+        self.copy_out((self.head + HEADER) % BYTES, output);
+        self.discard_front(len);
+
+        Ok(len)
+    }
+
+    /// Drop the oldest message without reading it, returning its length. Fails if queue is
+    /// empty.
+    pub fn skip_msg(&mut self) -> Result<usize, QueueError> {
+        let len = self.peek_len().ok_or(QueueError::QueueEmpty)?;
+        self.discard_front(len);
+        Ok(len)
+    }
+
+    /// Get the length of the oldest message, so that a buffer can be sized for it, or `None` if
+    /// the queue is empty.
+    pub fn peek_len(&self) -> Option<usize> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let mut header = [0; HEADER];
+        self.copy_out(self.head, &mut header);
+        Some(u32::from_le_bytes(header) as usize)
+    }
+
+    /// Remove every message.
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.used = 0;
+        self.count = 0;
+    }
+
+    /// Get the number of messages in the queue.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Check if the queue holds no messages.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Get the number of bytes taken up by the queued messages, including their length prefixes.
+    pub fn used_bytes(&self) -> usize {
+        self.used
+    }
+
+    /// Get the number of free bytes. A message fits if it is at most 4 bytes shorter.
+    pub fn free_bytes(&self) -> usize {
+        BYTES - self.used
+    }
+
+    /// Get the size of the byte ring.
+    pub fn capacity(&self) -> usize {
+        BYTES
+    }
+
+    fn discard_front(&mut self, len: usize) {
+        self.head = (self.head + HEADER + len) % BYTES;
+        self.used -= HEADER + len;
+        self.count -= 1;
+    }
+
+    // Copy bytes into the ring starting at `start`, wrapping around its end.
+    fn copy_in(&mut self, start: usize, bytes: &[u8]) {
+        let first = bytes.len().min(BYTES - start);
+        self.buffer[start..start + first].copy_from_slice(&bytes[..first]);
+        self.buffer[..bytes.len() - first].copy_from_slice(&bytes[first..]);
+    }
+
+    // Copy bytes out of the ring starting at `start`, wrapping around its end.
+    fn copy_out(&self, start: usize, output: &mut [u8]) {
+        let first = output.len().min(BYTES - start);
+        output[..first].copy_from_slice(&self.buffer[start..start + first]);
+        let rest = output.len() - first;
+        output[first..].copy_from_slice(&self.buffer[..rest]);
+    }
+}
+
+impl<const BYTES: usize> Default for MessageQueue<BYTES> {
+    fn default() -> Self {
+        MessageQueue::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageQueue;
+    use crate::typed_queue::QueueError;
+    use std::io;
+
+    // Arbitrary ring size for tests
+    const BYTES: usize = 32;
+
+    #[test]
+    fn variable_lengths() {
+        let mut queue = MessageQueue::<BYTES>::default();
+        assert!(queue.push_msg(b"hello").is_ok());
+        assert!(queue.push_msg(b"").is_ok());
+        assert!(queue.push_msg(b"world!").is_ok());
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.used_bytes(), 12 + 11);
+        assert_eq!(queue.peek_len(), Some(5));
+
+        let mut buffer = [0; BYTES];
+        assert_eq!(queue.pop_msg(&mut buffer), Ok(5));
+        assert_eq!(&buffer[..5], b"hello");
+        assert_eq!(queue.pop_msg(&mut buffer), Ok(0));
+        assert_eq!(queue.pop_msg(&mut buffer), Ok(6));
+        assert_eq!(&buffer[..6], b"world!");
+        assert_eq!(queue.pop_msg(&mut buffer), Err(QueueError::QueueEmpty));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn full_and_short_buffer() {
+        let mut queue = MessageQueue::<BYTES>::default();
+        assert_eq!(queue.push_msg(&[0; BYTES]), Err(QueueError::QueueFull));
+        assert!(queue.push_msg(&[1; BYTES - 4]).is_ok());
+        assert_eq!(queue.free_bytes(), 0);
+        assert_eq!(queue.push_msg(b""), Err(QueueError::QueueFull));
+
+        // A message that does not fit the output buffer stays queued
+        let mut buffer = [0; 8];
+        assert_eq!(
+            queue.pop_msg(&mut buffer),
+            Err(QueueError::Io(io::ErrorKind::InvalidInput))
+        );
+        assert_eq!(queue.skip_msg(), Ok(BYTES - 4));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn wrap_around() {
+        let mut queue = MessageQueue::<BYTES>::default();
+        let mut buffer = [0; BYTES];
+
+        // Messages of every length end up split across the end of the ring at some point
+        for n in 0..100u8 {
+            let len = n as usize % 11;
+            let msg: Vec<u8> = (0..len as u8).map(|i| n.wrapping_add(i)).collect();
+            assert!(queue.push_msg(&msg).is_ok());
+            assert_eq!(queue.pop_msg(&mut buffer), Ok(len));
+            assert_eq!(&buffer[..len], &msg[..]);
+        }
+
+        // The length prefix can wrap too
+        for len in [9, 10, 1] {
+            assert!(queue.push_msg(&vec![7; len]).is_ok());
+        }
+        for len in [9, 10, 1] {
+            assert_eq!(queue.pop_msg(&mut buffer), Ok(len));
+            assert!(buffer[..len].iter().all(|&byte| byte == 7));
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// FIFO queue answering `min()` and `max()` of its contents in O(1). Next to the elements, it
+// keeps two monotonic queues of the candidates for each extreme: a pushed element removes from
+// their back every candidate it beats, as those leave the queue before it and so can never be an
+// extreme again. Each element enters and leaves each candidate queue at most once, so pushes are
+// amortized O(1), and pops are O(1). Elements that do not compare equal to themselves, such as
+// NaN, could never be evicted from the candidates, so pushing one fails with
+// `QueueError::Unordered`.
+pub struct MonotonicQueue<T: Copy + PartialOrd, const N: usize> {
+    items: BasicTypedQueue<T, N>,
+    mins: BasicTypedQueue<T, N>, // non-decreasing from front to back
+    maxs: BasicTypedQueue<T, N>, // non-increasing from front to back
+}
+
+impl<T: Copy + PartialOrd, const N: usize> MonotonicQueue<T, N> {
+    /// Create an empty queue.
+    pub fn new() -> Self {
+        MonotonicQueue {
+            items: BasicTypedQueue::new(),
+            mins: BasicTypedQueue::new(),
+            maxs: BasicTypedQueue::new(),
+        }
+    }
+
+    /// Get the smallest element in the queue, or `None` if it is empty.
+    pub fn min(&self) -> Option<T> {
+        self.mins.front().ok().copied()
+    }
+
+    /// Get the largest element in the queue, or `None` if it is empty.
+    pub fn max(&self) -> Option<T> {
+        self.maxs.front().ok().copied()
+    }
+
+    /// Get a reference to the element at the front of the queue.
+    pub fn front(&self) -> Result<&T, QueueError> {
+        self.items.front()
+    }
+
+    /// Get a reference to the queued elements, oldest first.
+    pub fn items(&self) -> &BasicTypedQueue<T, N> {
+        &self.items
+    }
+
+    // Record a newly pushed element as a candidate for both extremes.
+    fn add_candidate(&mut self, input: T) {
+        while self.mins.back().is_ok_and(|&min| min > input) {
+            self.mins.truncate(self.mins.size() - 1);
+        }
+        while self.maxs.back().is_ok_and(|&max| max < input) {
+            self.maxs.truncate(self.maxs.size() - 1);
+        }
+
+        // The candidate queues never hold more elements than the queue itself
+        let _ = self.mins.push(input);
+        let _ = self.maxs.push(input);
+    }
+
+    // Drop an element that left the front of the queue from the candidates.
+    fn remove_candidate(&mut self, output: T) {
+        if self.mins.front().is_ok_and(|&min| min == output) {
+            let _ = self.mins.pop();
+        }
+        if self.maxs.front().is_ok_and(|&max| max == output) {
+            let _ = self.maxs.pop();
+        }
+    }
+}
+
+impl<T: Copy + PartialOrd, const N: usize> Default for MonotonicQueue<T, N> {
+    fn default() -> Self {
+        MonotonicQueue::new()
+    }
+}
+
+impl<T: Copy + PartialOrd, const N: usize> TypedQueue<T> for MonotonicQueue<T, N> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref(&input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref_overwrite(&input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        check_ordered(input)?;
+        self.items.push_ref(input)?;
+        self.add_candidate(*input);
+        Ok(())
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        check_ordered(input)?;
+        if let Some(evicted) = self.items.push_evict(*input)? {
+            self.remove_candidate(evicted);
+        }
+        self.add_candidate(*input);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        let output = self.items.pop()?;
+        self.remove_candidate(output);
+        Ok(output)
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        *output = self.pop()?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        self.items.clear()?;
+        self.mins.clear()?;
+        self.maxs.clear()
+    }
+
+    fn is_full(&self) -> bool {
+        self.items.is_full()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    fn size(&self) -> usize {
+        self.items.size()
+    }
+
+    fn capacity(&self) -> usize {
+        self.items.capacity()
+    }
+}
+
+fn check_ordered<T: PartialOrd>(input: &T) -> Result<(), QueueError> {
+    match input.partial_cmp(input) {
+        Some(_) => Ok(()),
+        None => Err(QueueError::Unordered),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MonotonicQueue;
+    use crate::typed_queue::{QueueError, TypedQueue};
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn fifo() {
+        let mut queue = MonotonicQueue::<i32, SIZE>::default();
+        assert_eq!(queue.min(), None);
+        assert_eq!(queue.max(), None);
+
+        for n in 0..SIZE as i32 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert_eq!(queue.push(0).unwrap_err(), QueueError::QueueFull);
+        for n in 0..SIZE as i32 {
+            assert_eq!(queue.min(), Some(n));
+            assert_eq!(queue.max(), Some(SIZE as i32 - 1));
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn extremes() {
+        let mut queue = MonotonicQueue::<i32, SIZE>::default();
+        let samples = [5, 3, 3, 8, -2, 7, 7, 1, 9, 0, 4, 4, -2, 6];
+        for &sample in &samples {
+            assert!(queue.push(sample).is_ok());
+        }
+
+        // Compare against a scan of the remaining elements while popping
+        for n in 0..samples.len() {
+            let window = &samples[n..];
+            assert_eq!(queue.min(), window.iter().copied().min());
+            assert_eq!(queue.max(), window.iter().copied().max());
+            assert_eq!(queue.pop().unwrap(), samples[n]);
+        }
+        assert_eq!(queue.min(), None);
+    }
+
+    #[test]
+    fn sliding_window() {
+        const WINDOW: usize = 3;
+
+        let mut queue = MonotonicQueue::<u32, WINDOW>::default();
+        let samples = [4, 2, 12, 3, 8, 1, 1, 10, 6, 5];
+        for (n, &sample) in samples.iter().enumerate() {
+            assert!(queue.push_overwrite(sample).is_ok());
+
+            let window = &samples[n.saturating_sub(WINDOW - 1)..=n];
+            assert_eq!(queue.size(), window.len());
+            assert_eq!(queue.min(), window.iter().copied().min());
+            assert_eq!(queue.max(), window.iter().copied().max());
+        }
+
+        assert!(queue.clear().is_ok());
+        assert_eq!(queue.max(), None);
+    }
+
+    #[test]
+    fn nan() {
+        let mut queue = MonotonicQueue::<f64, SIZE>::default();
+        assert!(queue.push(1.0).is_ok());
+        assert_eq!(queue.push(f64::NAN).unwrap_err(), QueueError::Unordered);
+        assert_eq!(
+            queue.push_overwrite(f64::NAN).unwrap_err(),
+            QueueError::Unordered
+        );
+        assert_eq!(queue.size(), 1);
+
+        assert!(queue.push(0.5).is_ok());
+        assert_eq!(queue.pop(), Ok(1.0));
+        assert_eq!(queue.max(), Some(0.5));
+    }
+}
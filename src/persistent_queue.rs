@@ -0,0 +1,629 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::typed_queue::QueueError;
+
+// Identifies a queue file, followed by the versions of the file format and of the element type.
+const MAGIC: [u8; 4] = *b"RQPQ";
+
+// Version of the file format written by this build. Files in newer formats are rejected.
+const FORMAT_VERSION: u32 = 1;
+
+// Size of the file header:
+//
+//     offset  0  magic number, b"RQPQ"
+//     offset  4  u32  format version
+//     offset  8  u32  element version, as given by the `Migration` the file was written with
+//     offset 12  u32  reserved, zero
+//     offset 16  u64  offset of the oldest record
+//
+// with little-endian integers.
+const HEADER: u64 = 24;
+
+// Offset of the head offset within the header.
+const HEAD_OFFSET: u64 = 16;
+
+// Size of the frame prefix stored before each record: its length and the CRC-32 of its payload,
+// both as little-endian `u32`s.
+const PREFIX: u64 = 8;
+
+// Table for the CRC-32 used by Ethernet, zlib and PNG, with reflected polynomial 0xedb88320.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut crc = n as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[n] = crc;
+        n += 1;
+    }
+    table
+};
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+// Append a record to `buffer`, framed by its length and checksum.
+fn frame(buffer: &mut Vec<u8>, payload: &[u8]) -> Result<(), QueueError> {
+    let len =
+        u32::try_from(payload.len()).map_err(|_| QueueError::Io(io::ErrorKind::InvalidInput))?;
+    buffer.extend_from_slice(&len.to_le_bytes());
+    buffer.extend_from_slice(&crc32(payload).to_le_bytes());
+    buffer.extend_from_slice(payload);
+    Ok(())
+}
+
+/// Hook for opening queue files written by older builds, whose elements were encoded from an
+/// earlier version of the element type. The version is stored in the file header, and the
+/// elements of files with an older version are migrated when the file is opened.
+pub trait Migration<T> {
+    /// Get the version of the element type in this build. Should be bumped whenever its encoding
+    /// changes.
+    fn version(&self) -> u32;
+
+    /// Decode an element encoded by an older version of the element type.
+    fn migrate(&self, version: u32, payload: &[u8]) -> Result<T, QueueError>;
+}
+
+/// Migration for element types that never changed, all stored as version 0. Fails to open files
+/// written with any other version.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct NoMigration;
+
+impl<T> Migration<T> for NoMigration {
+    fn version(&self) -> u32 {
+        0
+    }
+
+    fn migrate(&self, _version: u32, _payload: &[u8]) -> Result<T, QueueError> {
+        Err(QueueError::Io(io::ErrorKind::InvalidData))
+    }
+}
+
+// Unbounded queue of serializable elements kept in a file, so that elements buffered while e.g.
+// an uplink is down survive a restart of the process. Pushed elements are appended to the file as
+// postcard-encoded records, each framed by its length and checksum, and popping an element moves
+// the offset of the oldest record stored in the file header. Opening an existing file recovers the
+// queue from it, truncating the file at the first torn or corrupt record, e.g. one that was being
+// appended when the process crashed, so that the records before it remain usable. Files written
+// with an older version of the element type are migrated through a `Migration` when opened, and
+// files that do not start with the magic number are refused untouched. Consumed records are
+// reclaimed once the queue is empty, or on demand with `compact()`. Writes reach the operating
+// system immediately, but are only guaranteed to be on disk after `sync()`.
+pub struct PersistentQueue<T> {
+    file: File,
+    path: PathBuf,
+    version: u32, // version of the element type
+    head: u64,    // offset of the oldest record
+    tail: u64,    // end of the newest record
+    len: usize,
+    truncated: u64, // bytes discarded when recovering
+    _marker: PhantomData<T>,
+}
+
+impl<T: Serialize + DeserializeOwned> PersistentQueue<T> {
+    /// Open the queue stored at `path`, creating an empty one if the file does not exist. Any
+    /// torn or corrupt record is discarded along with everything after it. Fails with
+    /// `QueueError::Io` if the file cannot be accessed, or with
+    /// `QueueError::Io(io::ErrorKind::InvalidData)` if it is not a queue file or was written with
+    /// a newer format or an element version other than 0.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, QueueError> {
+        Self::open_with(path, &NoMigration)
+    }
+
+    /// Open the queue stored at `path` like `open()`, migrating the elements of a file written
+    /// with an older element version than `migration.version()`. The file is rewritten with the
+    /// migrated elements before the queue is returned. Fails with
+    /// `QueueError::Io(io::ErrorKind::InvalidData)` if the file was written with a newer format
+    /// or element version, or with the error of a failed migration, in which case the file keeps
+    /// its old element version.
+    pub fn open_with<P: AsRef<Path>, M: Migration<T>>(
+        path: P,
+        migration: &M,
+    ) -> Result<Self, QueueError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        let tail = file.seek(SeekFrom::End(0))?;
+        let mut header = [0; HEADER as usize];
+        let header_len = tail.min(HEADER) as usize;
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header[..header_len])?;
+
+        let mut queue = PersistentQueue {
+            file,
+            path,
+            version: migration.version(),
+            head: HEADER,
+            tail,
+            len: 0,
+            truncated: 0,
+            _marker: PhantomData,
+        };
+
+        let magic_len = header_len.min(MAGIC.len());
+        if header[..magic_len] != MAGIC[..magic_len] {
+            // Some other file, e.g. a wrong path: recovering would truncate it
+            return Err(QueueError::Io(io::ErrorKind::InvalidData));
+        }
+
+        if tail < HEADER {
+            // New file, or one that never got past its creation
+            queue.tail = HEADER;
+            queue.file.set_len(0)?;
+            queue.file.seek(SeekFrom::Start(0))?;
+            queue.file.write_all(&queue.header(HEADER))?;
+            return Ok(queue);
+        }
+
+        let field =
+            |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+        let (format, version) = (field(4), field(8));
+        if format > FORMAT_VERSION || version > migration.version() {
+            return Err(QueueError::Io(io::ErrorKind::InvalidData));
+        }
+
+        let head = u64::from_le_bytes(header[16..].try_into().unwrap());
+        queue.recover(HEADER, head)?;
+        if version == migration.version() {
+            if queue.len == 0 {
+                queue.clear()?;
+            }
+            return Ok(queue);
+        }
+
+        queue.rewrite(|payload| {
+            let value = migration.migrate(version, &payload)?;
+            Ok(postcard::to_extend(&value, Vec::new())?)
+        })?;
+        Ok(queue)
+    }
+
+    /// Append an element to the queue.
+    pub fn push(&mut self, input: &T) -> Result<(), QueueError> {
+        let mut record = Vec::new();
+        frame(&mut record, &postcard::to_extend(input, Vec::new())?)?;
+
+        self.file.seek(SeekFrom::Start(self.tail))?;
+        self.file.write_all(&record)?;
+        self.tail += record.len() as u64;
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Pop the oldest element. Fails if queue is empty.
+    pub fn pop(&mut self) -> Result<T, QueueError> {
+        let (value, next) = self.read_front()?;
+        self.len -= 1;
+
+        if self.len == 0 {
+            // Reclaim the consumed records. Truncating first means that a crash in between leaves
+            // a head past the end of the file, which recovers as an empty queue.
+            self.file.set_len(HEADER)?;
+            self.tail = HEADER;
+            self.set_head(HEADER)?;
+        } else {
+            self.set_head(next)?;
+        }
+
+        Ok(value)
+    }
+
+    /// Get a copy of the oldest element without removing it, e.g. to only pop it once it has
+    /// been delivered. Fails if queue is empty.
+    pub fn peek(&mut self) -> Result<T, QueueError> {
+        Ok(self.read_front()?.0)
+    }
+
+    /// Remove every element.
+    pub fn clear(&mut self) -> Result<(), QueueError> {
+        self.file.set_len(HEADER)?;
+        self.tail = HEADER;
+        self.len = 0;
+        self.set_head(HEADER)
+    }
+
+    /// Rewrite the file without the records already consumed, to reclaim their space while the
+    /// queue is not empty. The new file replaces the old one atomically.
+    pub fn compact(&mut self) -> Result<(), QueueError> {
+        if self.head == HEADER {
+            return Ok(());
+        }
+
+        self.rewrite(Ok)
+    }
+
+    /// Flush every write to disk.
+    pub fn sync(&self) -> Result<(), QueueError> {
+        Ok(self.file.sync_data()?)
+    }
+
+    /// Get the number of elements in the queue.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the size of the file, including consumed records not reclaimed yet.
+    pub fn file_size(&self) -> u64 {
+        self.tail
+    }
+
+    /// Get the number of bytes of torn or corrupt records discarded when the queue was opened.
+    pub fn truncated(&self) -> u64 {
+        self.truncated
+    }
+
+    /// Get the version of the element type the file is written with.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Get the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Count the intact records from `head` on, dropping the rest of the file from the first
+    // record that is not. Records start at `start`, the end of the header.
+    fn recover(&mut self, start: u64, head: u64) -> Result<(), QueueError> {
+        // A head past the end of the file is left over from reclaiming the consumed records
+        self.head = head.clamp(start, self.tail);
+
+        let mut offset = self.head;
+        while offset < self.tail {
+            match self.read_record(offset)? {
+                Some((_, next)) => offset = next,
+                None => break,
+            }
+            self.len += 1;
+        }
+
+        if offset < self.tail {
+            self.truncated = self.tail - offset;
+            self.file.set_len(offset)?;
+            self.tail = offset;
+        }
+
+        Ok(())
+    }
+
+    // Replace the file with one holding the current header and the remaining records, passed
+    // through `convert`. The new file is written in full before it replaces the old one
+    // atomically, so that a failure leaves the old one in place.
+    fn rewrite<F>(&mut self, mut convert: F) -> Result<(), QueueError>
+    where
+        F: FnMut(Vec<u8>) -> Result<Vec<u8>, QueueError>,
+    {
+        let mut contents = self.header(HEADER).to_vec();
+        let mut offset = self.head;
+        while offset < self.tail {
+            let (payload, next) = self
+                .read_record(offset)?
+                .ok_or(QueueError::Io(io::ErrorKind::InvalidData))?;
+            frame(&mut contents, &convert(payload)?)?;
+            offset = next;
+        }
+
+        let temp_path = self.path.with_extension("rewrite");
+        let mut temp = File::create(&temp_path)?;
+        temp.write_all(&contents)?;
+        temp.sync_all()?;
+        fs::rename(&temp_path, &self.path)?;
+
+        self.file = OpenOptions::new().read(true).write(true).open(&self.path)?;
+        self.head = HEADER;
+        self.tail = contents.len() as u64;
+        Ok(())
+    }
+
+    // Read the payload of the record at `offset`, returning it with the offset of the next
+    // record, or `None` if the record runs past the end of the file or fails its checksum.
+    fn read_record(&mut self, offset: u64) -> Result<Option<(Vec<u8>, u64)>, QueueError> {
+        if self.tail - offset < PREFIX {
+            return Ok(None);
+        }
+
+        let mut prefix = [0; PREFIX as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut prefix)?;
+        let [l0, l1, l2, l3, c0, c1, c2, c3] = prefix;
+        let len = u32::from_le_bytes([l0, l1, l2, l3]) as u64;
+        let crc = u32::from_le_bytes([c0, c1, c2, c3]);
+
+        let next = offset + PREFIX + len;
+        if next > self.tail {
+            return Ok(None);
+        }
+
+        let mut payload = vec![0; len as usize];
+        self.file.read_exact(&mut payload)?;
+        if crc32(&payload) == crc {
+            Ok(Some((payload, next)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Decode the oldest record, returning it with the offset of the next one.
+    fn read_front(&mut self) -> Result<(T, u64), QueueError> {
+        if self.len == 0 {
+            return Err(QueueError::QueueEmpty);
+        }
+
+        let (payload, next) = self
+            .read_record(self.head)?
+            .ok_or(QueueError::Io(io::ErrorKind::InvalidData))?;
+        Ok((postcard::from_bytes(&payload)?, next))
+    }
+
+    fn header(&self, head: u64) -> [u8; HEADER as usize] {
+        let mut header = [0; HEADER as usize];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4..8].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        header[8..12].copy_from_slice(&self.version.to_le_bytes());
+        header[16..].copy_from_slice(&head.to_le_bytes());
+        header
+    }
+
+    fn set_head(&mut self, head: u64) -> Result<(), QueueError> {
+        self.file.seek(SeekFrom::Start(HEAD_OFFSET))?;
+        self.file.write_all(&head.to_le_bytes())?;
+        self.head = head;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32, Migration, PersistentQueue};
+    use crate::typed_queue::QueueError;
+    use std::fs::{self, OpenOptions};
+    use std::io::{self, Seek, SeekFrom, Write};
+    use std::path::PathBuf;
+
+    // Variable-size elements, e.g. a sensor name and a batch of readings
+    type Sample = (String, Vec<i32>);
+
+    fn sample(n: i32) -> Sample {
+        (format!("sensor-{}", n), vec![n; n as usize % 4])
+    }
+
+    // Path of a file unique to the test, removed when dropped
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "rust-queue-{}-{}.queue",
+                std::process::id(),
+                name
+            ));
+            let _ = fs::remove_file(&path);
+            TempPath(path)
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn reopen() {
+        let path = TempPath::new("reopen");
+        {
+            let mut queue = PersistentQueue::open(&path.0).unwrap();
+            for n in 0..5 {
+                assert!(queue.push(&sample(n)).is_ok());
+            }
+            assert_eq!(queue.pop(), Ok(sample(0)));
+            assert!(queue.sync().is_ok());
+        }
+
+        // Both the pushed elements and the progress of the consumer survive
+        let mut queue = PersistentQueue::<Sample>::open(&path.0).unwrap();
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.peek(), Ok(sample(1)));
+        for n in 1..5 {
+            assert_eq!(queue.pop(), Ok(sample(n)));
+        }
+        assert_eq!(queue.pop(), Err(QueueError::QueueEmpty));
+
+        // The file shrinks back to its header once the queue is empty
+        assert_eq!(queue.file_size(), 24);
+        assert_eq!(fs::metadata(&path.0).unwrap().len(), 24);
+    }
+
+    #[test]
+    fn checksum() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn torn_write() {
+        let path = TempPath::new("torn_write");
+        let mut queue = PersistentQueue::open(&path.0).unwrap();
+        for n in 0..3 {
+            assert!(queue.push(&sample(n)).is_ok());
+        }
+        drop(queue);
+
+        // A crash in the middle of an append leaves part of a frame behind
+        let mut file = OpenOptions::new().append(true).open(&path.0).unwrap();
+        assert!(file.write_all(&[40, 0, 0, 0, 1, 2]).is_ok());
+        drop(file);
+
+        let mut queue = PersistentQueue::<Sample>::open(&path.0).unwrap();
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.truncated(), 6);
+        assert!(queue.push(&sample(3)).is_ok());
+        drop(queue);
+
+        let mut queue = PersistentQueue::<Sample>::open(&path.0).unwrap();
+        assert_eq!(queue.truncated(), 0);
+        for n in 0..4 {
+            assert_eq!(queue.pop(), Ok(sample(n)));
+        }
+    }
+
+    #[test]
+    fn corrupt_record() {
+        let path = TempPath::new("corrupt_record");
+        let mut queue = PersistentQueue::open(&path.0).unwrap();
+        assert!(queue.push(&sample(0)).is_ok());
+        let first = queue.file_size();
+        for n in 1..3 {
+            assert!(queue.push(&sample(n)).is_ok());
+        }
+        let size = queue.file_size();
+        drop(queue);
+
+        // Corrupt the payload of the second record: it and every later record are dropped
+        let mut file = OpenOptions::new().write(true).open(&path.0).unwrap();
+        assert!(file.seek(SeekFrom::Start(first + 8 + 2)).is_ok());
+        assert!(file.write_all(b"X").is_ok());
+        drop(file);
+
+        let mut queue = PersistentQueue::<Sample>::open(&path.0).unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.truncated(), size - first);
+        assert_eq!(queue.pop(), Ok(sample(0)));
+        assert!(queue.is_empty());
+    }
+
+    // Version 1 of `Sample` gained a flag, which elements stored by version 0 did not have
+    type SampleV1 = (String, Vec<i32>, bool);
+
+    struct AddFlag;
+
+    impl Migration<SampleV1> for AddFlag {
+        fn version(&self) -> u32 {
+            1
+        }
+
+        fn migrate(&self, version: u32, payload: &[u8]) -> Result<SampleV1, QueueError> {
+            assert_eq!(version, 0);
+            let (sensor, values): Sample = postcard::from_bytes(payload)?;
+            Ok((sensor, values, false))
+        }
+    }
+
+    #[test]
+    fn migration() {
+        let path = TempPath::new("migration");
+        let mut queue = PersistentQueue::open(&path.0).unwrap();
+        for n in 0..3 {
+            assert!(queue.push(&sample(n)).is_ok());
+        }
+        assert_eq!(queue.pop(), Ok(sample(0)));
+        drop(queue);
+
+        // The remaining elements are migrated, and the file is rewritten as version 1
+        let mut queue = PersistentQueue::open_with(&path.0, &AddFlag).unwrap();
+        assert_eq!(queue.version(), 1);
+        assert_eq!(queue.len(), 2);
+        assert!(queue.push(&("new".to_string(), vec![], true)).is_ok());
+        drop(queue);
+
+        // Older builds cannot open it anymore
+        assert_eq!(
+            PersistentQueue::<SampleV1>::open(&path.0).err(),
+            Some(QueueError::Io(io::ErrorKind::InvalidData))
+        );
+
+        let mut queue = PersistentQueue::open_with(&path.0, &AddFlag).unwrap();
+        let (sensor, values) = sample(1);
+        assert_eq!(queue.pop(), Ok((sensor, values, false)));
+        assert_eq!(queue.pop().unwrap().0, "sensor-2");
+        assert_eq!(queue.pop(), Ok(("new".to_string(), vec![], true)));
+    }
+
+    #[test]
+    fn foreign_file() {
+        let path = TempPath::new("foreign_file");
+
+        // Opening the wrong path must not truncate whatever is there
+        let contents = b"not a queue, but longer than a queue header".to_vec();
+        assert!(fs::write(&path.0, &contents).is_ok());
+        assert_eq!(
+            PersistentQueue::<Sample>::open(&path.0).err(),
+            Some(QueueError::Io(io::ErrorKind::InvalidData))
+        );
+        assert!(fs::write(&path.0, b"abc").is_ok());
+        assert_eq!(
+            PersistentQueue::<Sample>::open(&path.0).err(),
+            Some(QueueError::Io(io::ErrorKind::InvalidData))
+        );
+        assert_eq!(fs::read(&path.0).unwrap(), b"abc");
+
+        // A header torn while the file was being created is completed
+        assert!(fs::write(&path.0, b"RQ").is_ok());
+        let mut queue = PersistentQueue::<Sample>::open(&path.0).unwrap();
+        assert!(queue.is_empty());
+        assert!(queue.push(&sample(0)).is_ok());
+        drop(queue);
+
+        // Newer formats are rejected
+        let mut contents = fs::read(&path.0).unwrap();
+        assert_eq!(&contents[..4], b"RQPQ");
+        contents[4] = 2;
+        assert!(fs::write(&path.0, &contents).is_ok());
+        assert_eq!(
+            PersistentQueue::<Sample>::open(&path.0).err(),
+            Some(QueueError::Io(io::ErrorKind::InvalidData))
+        );
+    }
+
+    #[test]
+    fn compact() {
+        let path = TempPath::new("compact");
+        let mut queue = PersistentQueue::open(&path.0).unwrap();
+        for n in 0..10 {
+            assert!(queue.push(&sample(n)).is_ok());
+        }
+        for n in 0..8 {
+            assert_eq!(queue.pop(), Ok(sample(n)));
+        }
+
+        let size = queue.file_size();
+        assert!(queue.compact().is_ok());
+        assert!(queue.file_size() < size);
+        assert!(queue.push(&sample(10)).is_ok());
+        drop(queue);
+
+        let mut queue = PersistentQueue::<Sample>::open(&path.0).unwrap();
+        for n in 8..11 {
+            assert_eq!(queue.pop(), Ok(sample(n)));
+        }
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+#[cfg(feature = "threads")]
+use std::sync::{Condvar, Mutex};
+use std::time::Instant;
+
+use crate::basic_typed_queue::BasicTypedQueue;
+#[cfg(feature = "threads")]
+use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+use crate::typed_queue::OverflowPolicy;
+#[cfg(feature = "threads")]
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Curated aliases for common queue configurations, so that new users can pick a preset by use
+// case instead of assembling one from the individual queue types.
+
+/// Ring of timestamped samples that always retains the newest ones. Create it with
+/// `sensor_ring()`, so that `push()` overwrites the oldest sample once full. Not thread-safe.
+pub type SensorRing<T, const N: usize> = BasicTypedQueue<Timestamped<T>, N>;
+
+/// Create an empty `SensorRing` whose pushes overwrite the oldest sample once it is full.
+pub fn sensor_ring<T: Copy, const N: usize>() -> SensorRing<T, N> {
+    let mut ring = SensorRing::new();
+    ring.set_overflow_policy(OverflowPolicy::OverwriteOldest);
+    ring
+}
+
+/// Ring of bytes, usable as an in-memory pipe through `io::Read`, `io::BufRead` and
+/// `io::Write`. Not thread-safe; a `SharedQueue<u8, N>` is also readable and writable through a
+/// shared reference.
+pub type ByteQueue<const N: usize> = BasicTypedQueue<u8, N>;
+
+/// Queue shared between any number of producer and consumer threads, e.g. through an `Arc`.
+#[cfg(feature = "threads")]
+pub type SharedQueue<T, const N: usize> = ThreadSafeTypedQueue<T, N>;
+
+// Queue of work items shared between any number of producer and consumer threads, which wait for
+// room or for an item instead of handling `QueueFull` and `QueueEmpty` themselves. Closing the
+// queue lets the consumers finish the remaining items and then stop. Unlike
+// `job_queue::JobQueue`, which runs boxed closures, the items are plain values. Waiting threads
+// sleep on a condition variable until a push, pop or close lets them make progress.
+#[cfg(feature = "threads")]
+pub struct BlockingQueue<T: Copy, const N: usize> {
+    queue: ThreadSafeTypedQueue<T, N>,
+    lock: Mutex<()>, // held while checking the queue before waiting, so no wakeup is missed
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+#[cfg(feature = "threads")]
+impl<T: Copy, const N: usize> BlockingQueue<T, N> {
+    /// Create a new, open blocking queue.
+    pub fn new() -> Self {
+        BlockingQueue {
+            queue: ThreadSafeTypedQueue::new(),
+            lock: Mutex::new(()),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+
+    /// Push an item, waiting for room while the queue is full. Fails with `QueueError::Closed`
+    /// if the queue is closed.
+    pub fn push(&self, input: T) -> Result<(), QueueError> {
+        let mut guard = self.lock.lock()?;
+        loop {
+            match (&self.queue).push(input) {
+                Err(QueueError::QueueFull) => {
+                    self.queue.on_wait();
+                    guard = self.not_full.wait(guard)?;
+                    self.queue.on_wait_done();
+                }
+                result => {
+                    drop(guard);
+                    if result.is_ok() {
+                        self.not_empty.notify_one();
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+
+    /// Pop the oldest item, waiting for one while the queue is empty. Fails with
+    /// `QueueError::Closed` once the queue is closed and every item has been popped.
+    pub fn pop(&self) -> Result<T, QueueError> {
+        let mut guard = self.lock.lock()?;
+        loop {
+            match (&self.queue).pop() {
+                // Once closed, nothing more can be pushed, so an empty queue stays empty
+                Err(QueueError::QueueEmpty) if self.queue.is_closed() => {
+                    return Err(QueueError::Closed)
+                }
+                Err(QueueError::QueueEmpty) => {
+                    self.queue.on_wait();
+                    guard = self.not_empty.wait(guard)?;
+                    self.queue.on_wait_done();
+                }
+                result => {
+                    drop(guard);
+                    if result.is_ok() {
+                        self.not_full.notify_one();
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+
+    /// Close the queue: later pushes fail, and consumers stop once the remaining items are
+    /// popped. Every waiting producer and consumer is woken up.
+    pub fn close(&self) -> Result<(), QueueError> {
+        let guard = self.lock.lock()?;
+        self.queue.close()?;
+        drop(guard);
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+        Ok(())
+    }
+
+    /// Check if the queue has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.queue.is_closed()
+    }
+
+    /// Get the number of queued items.
+    pub fn size(&self) -> usize {
+        self.queue.size()
+    }
+
+    /// Get a reference to the underlying queue, e.g. to read its statistics. Pushes and pops made
+    /// through it do not wake waiting producers or consumers.
+    pub fn inner(&self) -> &ThreadSafeTypedQueue<T, N> {
+        &self.queue
+    }
+}
+
+#[cfg(feature = "threads")]
+impl<T: Copy, const N: usize> Default for BlockingQueue<T, N> {
+    fn default() -> Self {
+        BlockingQueue::new()
+    }
+}
+
+/// Element paired with the time at which it was recorded.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Timestamped<T: Copy> {
+    pub timestamp: Instant,
+    pub value: T,
+}
+
+impl<T: Copy> Timestamped<T> {
+    /// Pair a value with the current time.
+    pub fn now(value: T) -> Self {
+        Timestamped {
+            timestamp: Instant::now(),
+            value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sensor_ring, Timestamped};
+    #[cfg(feature = "threads")]
+    use super::{BlockingQueue, SharedQueue};
+    #[cfg(feature = "threads")]
+    use crate::typed_queue::QueueError;
+    use crate::typed_queue::TypedQueue;
+    #[cfg(feature = "threads")]
+    use std::thread;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 4;
+
+    #[test]
+    fn sensor_ring_overwrites() {
+        let mut ring = sensor_ring::<u32, SIZE>();
+        for n in 0..SIZE as u32 * 2 {
+            assert!(ring.push(Timestamped::now(n)).is_ok());
+        }
+        assert!(ring.is_full());
+
+        // Plain pushes kept the newest samples
+        let sample = ring.pop().unwrap();
+        assert_eq!(sample.value, SIZE as u32);
+        assert!(sample.timestamp.elapsed() < std::time::Duration::from_secs(60));
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn shared_queue() {
+        let queue = SharedQueue::<u32, SIZE>::default();
+        let mut producer = &queue;
+        let mut consumer = &queue;
+
+        assert!(producer.push(1).is_ok());
+        assert_eq!(consumer.pop().unwrap(), 1);
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn blocking_queue() {
+        const COUNT: u32 = 1000;
+        let queue = BlockingQueue::<u32, SIZE>::default();
+
+        let sums = thread::scope(|scope| {
+            let consumers: Vec<_> = (0..2)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut sum = 0;
+                        while let Ok(n) = queue.pop() {
+                            sum += n;
+                        }
+                        sum
+                    })
+                })
+                .collect();
+
+            // Pushes wait for the consumers to make room
+            for n in 1..=COUNT {
+                assert!(queue.push(n).is_ok());
+            }
+            assert!(queue.close().is_ok());
+            assert_eq!(queue.push(0), Err(QueueError::Closed));
+
+            consumers
+                .into_iter()
+                .map(|consumer| consumer.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(sums.iter().sum::<u32>(), COUNT * (COUNT + 1) / 2);
+        assert_eq!(queue.pop(), Err(QueueError::Closed));
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn blocking_queue_close_wakes_consumers() {
+        let queue = BlockingQueue::<u32, SIZE>::default();
+
+        thread::scope(|scope| {
+            let consumer = scope.spawn(|| queue.pop());
+
+            // The consumer waits on the empty queue until it is closed
+            thread::sleep(std::time::Duration::from_millis(20));
+            assert!(queue.close().is_ok());
+            assert_eq!(consumer.join().unwrap(), Err(QueueError::Closed));
+        });
+    }
+}
@@ -0,0 +1,214 @@
+use std::array;
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Queue multiplexing one ring of `N` elements per priority level. Pop serves the highest
+// non-empty level, in FIFO order within a level. Unlike `StablePriorityQueue`, each level has a
+// capacity of its own, so a flood of low-priority elements can never crowd out high-priority
+// ones. Optional aging prevents starvation: once a non-empty level has been passed over a given
+// number of times, its oldest element is served next regardless of priority. Not thread-safe.
+pub struct PriorityLevels<T: Copy, const N: usize, const LEVELS: usize> {
+    levels: [BasicTypedQueue<T, N>; LEVELS],
+    skipped: [usize; LEVELS], // pops served from another level while this one was waiting
+    aging_limit: usize,
+}
+
+impl<T: Copy, const N: usize, const LEVELS: usize> PriorityLevels<T, N, LEVELS> {
+    /// Create a new queue with `LEVELS` priority levels of `N` elements each, without aging.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `LEVELS` is zero.
+    pub fn new() -> Self {
+        assert!(LEVELS > 0, "priority queue must have at least one level");
+
+        PriorityLevels {
+            levels: array::from_fn(|_| BasicTypedQueue::new()),
+            skipped: [0; LEVELS],
+            aging_limit: 0,
+        }
+    }
+
+    /// Serve a waiting level once it has been passed over `limit` times in favour of higher
+    /// levels, so that low priorities cannot starve. Zero (the default) disables aging.
+    pub fn set_aging_limit(&mut self, limit: usize) {
+        self.aging_limit = limit;
+    }
+
+    /// Get the number of times a waiting level can be passed over before it is served.
+    pub fn aging_limit(&self) -> usize {
+        self.aging_limit
+    }
+
+    /// Push an element with the given priority. Higher values are popped first, and priorities
+    /// at or above `LEVELS` are treated as the highest level. Fails if that level is full.
+    pub fn push_with_priority(&mut self, input: T, priority: u8) -> Result<(), QueueError> {
+        let level = self.level(priority);
+        self.levels[level].push(input)
+    }
+
+    /// Pop the next element along with its priority: the oldest element of the highest non-empty
+    /// level, or of an aged level if aging is enabled. Fails if queue is empty.
+    pub fn pop_with_priority(&mut self) -> Result<(T, u8), QueueError> {
+        let highest = (0..LEVELS)
+            .rev()
+            .find(|&level| !self.levels[level].is_empty())
+            .ok_or(QueueError::QueueEmpty)?;
+
+        let aged = (0..highest).rev().find(|&level| {
+            self.aging_limit > 0
+                && !self.levels[level].is_empty()
+                && self.skipped[level] >= self.aging_limit
+        });
+        let served = aged.unwrap_or(highest);
+
+        for level in 0..LEVELS {
+            if level == served || self.levels[level].is_empty() {
+                self.skipped[level] = 0;
+            } else {
+                self.skipped[level] += 1;
+            }
+        }
+
+        Ok((self.levels[served].pop()?, served as u8))
+    }
+
+    /// Get the number of elements queued at the given priority.
+    pub fn size_at(&self, priority: u8) -> usize {
+        self.levels[self.level(priority)].size()
+    }
+
+    /// Get a reference to the ring of the given priority.
+    pub fn level_queue(&self, priority: u8) -> &BasicTypedQueue<T, N> {
+        &self.levels[self.level(priority)]
+    }
+
+    fn level(&self, priority: u8) -> usize {
+        (priority as usize).min(LEVELS - 1)
+    }
+}
+
+impl<T: Copy, const N: usize, const LEVELS: usize> Default for PriorityLevels<T, N, LEVELS> {
+    fn default() -> Self {
+        PriorityLevels::new()
+    }
+}
+
+// Elements pushed through the trait get the lowest priority, and overwriting pushes evict the
+// oldest element of the lowest level. The queue thus counts as full whenever the lowest level is,
+// as that is when pushes through the trait fail, even if higher levels still have room; use
+// `size_at()` to check those.
+impl<T: Copy, const N: usize, const LEVELS: usize> TypedQueue<T> for PriorityLevels<T, N, LEVELS> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_with_priority(input, 0)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.levels[0].push_overwrite(input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        self.push_with_priority(*input, 0)
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        self.push_overwrite(*input)
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        self.pop_with_priority().map(|(value, _)| value)
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        *output = self.pop()?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        for level in self.levels.iter_mut() {
+            level.clear()?;
+        }
+        self.skipped = [0; LEVELS];
+
+        Ok(())
+    }
+
+    fn is_full(&self) -> bool {
+        self.levels[0].is_full()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.levels.iter().all(|level| level.is_empty())
+    }
+
+    fn size(&self) -> usize {
+        self.levels.iter().map(|level| level.size()).sum()
+    }
+
+    fn capacity(&self) -> usize {
+        N * LEVELS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PriorityLevels;
+    use crate::typed_queue::{QueueError, TypedQueue};
+
+    // Arbitrary queue size and number of levels for tests
+    const SIZE: usize = 16;
+    const LEVELS: usize = 3;
+
+    #[test]
+    fn priority_order() {
+        let mut queue = PriorityLevels::<u32, SIZE, LEVELS>::default();
+        assert!(queue.push_with_priority(1, 0).is_ok());
+        assert!(queue.push_with_priority(2, 2).is_ok());
+        assert!(queue.push_with_priority(3, 1).is_ok());
+        assert!(queue.push_with_priority(4, u8::MAX).is_ok());
+        assert_eq!(queue.size_at(2), 2);
+
+        assert_eq!(queue.pop_with_priority().unwrap(), (2, 2));
+        assert_eq!(queue.pop_with_priority().unwrap(), (4, 2));
+        assert_eq!(queue.pop_with_priority().unwrap(), (3, 1));
+        assert_eq!(queue.pop_with_priority().unwrap(), (1, 0));
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+    }
+
+    #[test]
+    fn separate_capacity() {
+        let mut queue = PriorityLevels::<u32, SIZE, LEVELS>::default();
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert_eq!(queue.push(0).unwrap_err(), QueueError::QueueFull);
+        assert!(queue.is_full());
+
+        // A full low level does not block higher ones
+        assert!(queue.push_with_priority(100, 1).is_ok());
+        assert!(queue.push_overwrite(SIZE as u32).is_ok());
+        assert_eq!(queue.pop().unwrap(), 100);
+        assert_eq!(queue.pop().unwrap(), 1);
+        assert_eq!(queue.size(), SIZE - 1);
+
+        assert!(queue.clear().is_ok());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn aging() {
+        let mut queue = PriorityLevels::<u32, SIZE, LEVELS>::default();
+        queue.set_aging_limit(3);
+        assert_eq!(queue.aging_limit(), 3);
+
+        assert!(queue.push_with_priority(0, 0).is_ok());
+        for n in 1..=8 {
+            assert!(queue.push_with_priority(n, 2).is_ok());
+        }
+
+        // The low-priority element is served after being passed over three times
+        let order: Vec<u32> = (0..9).map(|_| queue.pop().unwrap()).collect();
+        assert_eq!(order, [1, 2, 3, 0, 4, 5, 6, 7, 8]);
+    }
+}
@@ -0,0 +1,162 @@
+use std::marker::PhantomData;
+
+use crate::basic_typed_queue::BasicTypedQueue;
+#[cfg(feature = "threads")]
+use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+use crate::typed_queue::OverflowPolicy;
+#[cfg(feature = "alloc")]
+use crate::typed_queue::TypedQueue;
+
+/// Synchronization of the queues built by a `QueueBuilder`.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum Backend {
+    /// `BasicTypedQueue`, owned by a single thread at a time.
+    #[default]
+    Local,
+    /// `ThreadSafeTypedQueue`, which can be shared between threads.
+    #[cfg(feature = "threads")]
+    Shared,
+}
+
+// Builder collecting the configuration of a queue of `CAPACITY` elements in one place, instead of
+// a constructor followed by a series of setters on each queue type. Settings not supported by the
+// selected backend are ignored: the latency probe only exists on shared queues.
+#[derive(Debug, Copy, Clone)]
+pub struct QueueBuilder<T: Copy, const CAPACITY: usize> {
+    backend: Backend,
+    overflow_policy: OverflowPolicy,
+    soft_capacity: usize,
+    latency_interval: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy, const CAPACITY: usize> QueueBuilder<T, CAPACITY> {
+    /// Start from the defaults: a local queue rejecting pushes when full, with the soft capacity
+    /// equal to the capacity and no instrumentation.
+    pub fn new() -> Self {
+        QueueBuilder {
+            backend: Backend::Local,
+            overflow_policy: OverflowPolicy::Reject,
+            soft_capacity: CAPACITY,
+            latency_interval: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Select the backend used by `build()`.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Set what `push()` does when the queue is full.
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Set the soft capacity, clamped to the capacity.
+    pub fn soft_capacity(mut self, soft_capacity: usize) -> Self {
+        self.soft_capacity = soft_capacity;
+        self
+    }
+
+    /// Measure push-to-pop handoff latency by marking every `interval`-th pushed element. Only
+    /// applies to shared queues; an interval of zero disables the probe.
+    pub fn latency_probe(mut self, interval: usize) -> Self {
+        self.latency_interval = interval;
+        self
+    }
+
+    /// Build a local queue, regardless of the selected backend.
+    pub fn build_local(&self) -> BasicTypedQueue<T, CAPACITY> {
+        let mut queue = BasicTypedQueue::new();
+        queue.set_overflow_policy(self.overflow_policy);
+        queue.set_soft_capacity(self.soft_capacity);
+        queue
+    }
+
+    /// Build a shared queue, regardless of the selected backend.
+    #[cfg(feature = "threads")]
+    pub fn build_shared(&self) -> ThreadSafeTypedQueue<T, CAPACITY> {
+        let queue = ThreadSafeTypedQueue::new();
+        queue.set_overflow_policy(self.overflow_policy);
+        queue.set_soft_capacity(self.soft_capacity);
+        if self.latency_interval > 0 {
+            // A freshly created queue cannot be poisoned
+            let _ = queue.enable_latency_probe(self.latency_interval);
+        }
+        queue
+    }
+
+    /// Build a queue with the selected backend, behind the `TypedQueue` trait. To share a queue
+    /// between threads, use `build_shared()` and wrap the result in an `Arc` instead.
+    #[cfg(feature = "alloc")]
+    pub fn build(&self) -> Box<dyn TypedQueue<T> + Send>
+    where
+        T: Send + 'static,
+    {
+        match self.backend {
+            Backend::Local => Box::new(self.build_local()),
+            #[cfg(feature = "threads")]
+            Backend::Shared => Box::new(self.build_shared()),
+        }
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize> Default for QueueBuilder<T, CAPACITY> {
+    fn default() -> Self {
+        QueueBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueueBuilder;
+    use crate::typed_queue::OverflowPolicy;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn build_concrete() {
+        let builder = QueueBuilder::<u32, SIZE>::new()
+            .overflow_policy(OverflowPolicy::OverwriteOldest)
+            .soft_capacity(SIZE / 2)
+            .latency_probe(4);
+
+        let local = builder.build_local();
+        assert_eq!(local.overflow_policy(), OverflowPolicy::OverwriteOldest);
+        assert_eq!(local.soft_capacity(), SIZE / 2);
+
+        #[cfg(feature = "threads")]
+        {
+            let shared = builder.build_shared();
+            assert_eq!(shared.overflow_policy(), OverflowPolicy::OverwriteOldest);
+            assert_eq!(shared.soft_capacity(), SIZE / 2);
+            assert_eq!(shared.latency_probe().unwrap().interval(), 4);
+        }
+    }
+
+    #[cfg(all(feature = "alloc", feature = "threads"))]
+    #[test]
+    fn build_dyn() {
+        use super::Backend;
+
+        for backend in [Backend::Local, Backend::Shared] {
+            let mut queue = QueueBuilder::<u32, SIZE>::default()
+                .backend(backend)
+                .overflow_policy(OverflowPolicy::DropNewest)
+                .build();
+            assert_eq!(queue.capacity(), SIZE);
+
+            for n in 0..SIZE as u32 + 1 {
+                assert!(queue.push(n).is_ok());
+            }
+            for n in 0..SIZE as u32 {
+                assert_eq!(queue.pop().unwrap(), n);
+            }
+            assert!(queue.is_empty());
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use metrics::{counter, gauge, Counter, Gauge};
+
+// Handles of the metrics reported for one queue through the `metrics` facade, all labelled with
+// the queue's name: its depth as a gauge, and the element counts as counters from which
+// dashboards derive throughput and drop rates.
+pub(crate) struct QueueMetrics {
+    depth: Gauge,
+    pushed: Counter,
+    popped: Counter,
+    discarded: Counter,
+}
+
+impl QueueMetrics {
+    // Register the metrics of the queue called `name` with the current recorder.
+    pub(crate) fn new(name: &str) -> Self {
+        let name = name.to_owned();
+        QueueMetrics {
+            depth: gauge!("queue_depth", "queue" => name.clone()),
+            pushed: counter!("queue_pushed_total", "queue" => name.clone()),
+            popped: counter!("queue_popped_total", "queue" => name.clone()),
+            discarded: counter!("queue_discarded_total", "queue" => name),
+        }
+    }
+
+    // Record elements entering, leaving or being dropped from the queue.
+    pub(crate) fn on_push(&self, count: usize) {
+        self.pushed.increment(count as u64);
+    }
+
+    pub(crate) fn on_pop(&self, count: usize) {
+        self.popped.increment(count as u64);
+    }
+
+    pub(crate) fn on_discard(&self, count: usize) {
+        self.discarded.increment(count as u64);
+    }
+
+    // Record the number of elements visible to consumers, i.e. excluding unpublished ones.
+    pub(crate) fn on_resize(&self, depth: usize) {
+        self.depth.set(depth as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::{OverflowPolicy, TypedQueue};
+    use metrics::{
+        Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+    };
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    // Recorder keeping the current value of every metric, keyed by name and labels.
+    #[derive(Default)]
+    struct TestRecorder {
+        values: Mutex<HashMap<String, Arc<AtomicU64>>>,
+    }
+
+    impl TestRecorder {
+        fn register(&self, key: &Key) -> Arc<AtomicU64> {
+            let labels: Vec<_> = key
+                .labels()
+                .map(|label| format!("{}={}", label.key(), label.value()))
+                .collect();
+            let id = format!("{}{{{}}}", key.name(), labels.join(","));
+            Arc::clone(self.values.lock().unwrap().entry(id).or_default())
+        }
+
+        fn value(&self, id: &str) -> u64 {
+            self.values.lock().unwrap()[id].load(Ordering::Relaxed)
+        }
+    }
+
+    impl Recorder for TestRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(self.register(key))
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::from_arc(self.register(key))
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn report() {
+        let recorder = TestRecorder::default();
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert!(queue.push(0).is_ok());
+        metrics::with_local_recorder(&recorder, || queue.enable_metrics("ingest").unwrap());
+
+        for n in 1..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(queue.push_overwrite(100).is_ok());
+        queue.set_overflow_policy(OverflowPolicy::DropNewest);
+        assert!(queue.push(101).is_ok());
+        assert!(queue.pop().is_ok());
+
+        let depth = f64::from_bits(recorder.value("queue_depth{queue=ingest}"));
+        assert_eq!(depth, (SIZE - 1) as f64);
+        assert_eq!(
+            recorder.value("queue_pushed_total{queue=ingest}"),
+            SIZE as u64 + 1
+        );
+        assert_eq!(recorder.value("queue_popped_total{queue=ingest}"), 1);
+        assert_eq!(recorder.value("queue_discarded_total{queue=ingest}"), 2);
+    }
+
+    #[test]
+    fn depth_excludes_unpublished() {
+        let recorder = TestRecorder::default();
+        let queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        metrics::with_local_recorder(&recorder, || queue.enable_metrics("batch").unwrap());
+        let depth = || f64::from_bits(recorder.value("queue_depth{queue=batch}"));
+
+        assert!((&queue).push(0).is_ok());
+        assert!(queue.push_unpublished(1).is_ok());
+        assert!((&queue).push(2).is_ok());
+        assert_eq!(depth(), 1.0);
+
+        assert!(queue.publish_after().is_ok());
+        assert_eq!(depth(), 3.0);
+        assert!((&queue).pop().is_ok());
+        assert_eq!(depth(), 2.0);
+    }
+}
@@ -0,0 +1,225 @@
+use std::array;
+use std::marker::PhantomData;
+
+use crate::typed_queue::{self, QueueError, TypedQueue};
+
+// Multiplexer owning one queue per topic, for up to `TOPICS` topics. Pushes are routed to the
+// queue of their topic, and `pop_any()` serves the topics round-robin, so that a single consumer
+// can handle every topic without one of them starving the others. Topics are looked up with a
+// linear scan, which beats hashing for the handful of topics a service typically has.
+pub struct QueueRouter<K: Copy + Eq, T: Copy, Q: TypedQueue<T>, const TOPICS: usize> {
+    routes: [Option<(K, Q)>; TOPICS],
+    next: usize, // route served first by the next `pop_any()`
+    _marker: PhantomData<T>,
+}
+
+impl<K: Copy + Eq, T: Copy, Q: TypedQueue<T>, const TOPICS: usize> QueueRouter<K, T, Q, TOPICS> {
+    /// Create a router without topics.
+    pub fn new() -> Self {
+        QueueRouter {
+            routes: array::from_fn(|_| None),
+            next: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Route `topic` to `queue`, returning the queue it was previously routed to, if any. Fails
+    /// with `QueueError::QueueFull` if the topic is new and `TOPICS` topics are already routed.
+    pub fn add_topic(&mut self, topic: K, queue: Q) -> Result<Option<Q>, QueueError> {
+        if let Some(index) = self.index_of(&topic) {
+            return Ok(self.routes[index]
+                .replace((topic, queue))
+                .map(|(_, old)| old));
+        }
+
+        let free = self.routes.iter_mut().find(|route| route.is_none());
+        *free.ok_or(QueueError::QueueFull)? = Some((topic, queue));
+
+        Ok(None)
+    }
+
+    /// Stop routing `topic`, returning its queue so that any remaining elements can be drained.
+    pub fn remove_topic(&mut self, topic: &K) -> Option<Q> {
+        let index = self.index_of(topic)?;
+        self.routes[index].take().map(|(_, queue)| queue)
+    }
+
+    /// Push an element to the queue of `topic`. Fails with `QueueError::NoRoute` if the topic is
+    /// not routed, or with the error of its queue.
+    pub fn push(&mut self, topic: &K, input: T) -> Result<(), QueueError> {
+        self.queue_mut(topic)
+            .ok_or(QueueError::NoRoute)?
+            .push(input)
+    }
+
+    /// Pop an element from the queue of `topic`. Fails with `QueueError::NoRoute` if the topic is
+    /// not routed, or with the error of its queue.
+    pub fn pop(&mut self, topic: &K) -> Result<T, QueueError> {
+        self.queue_mut(topic).ok_or(QueueError::NoRoute)?.pop()
+    }
+
+    /// Pop an element from the next non-empty topic, round-robin, along with its topic. Fails if
+    /// every queue is empty, or with the error of the first queue failing for another reason,
+    /// e.g. a poisoned shared queue, which is skipped by the next call.
+    pub fn pop_any(&mut self) -> Result<(K, T), QueueError> {
+        let routes = &mut self.routes;
+        typed_queue::pop_round_robin(&mut self.next, TOPICS, |index| {
+            let (topic, queue) = routes[index].as_mut()?;
+            Some(queue.pop().map(|value| (*topic, value)))
+        })
+        .map(|(_, popped)| popped)
+    }
+
+    /// Get a reference to the queue of `topic`, if it is routed.
+    pub fn queue(&self, topic: &K) -> Option<&Q> {
+        let index = self.index_of(topic)?;
+        self.routes[index].as_ref().map(|(_, queue)| queue)
+    }
+
+    /// Get a mutable reference to the queue of `topic`, if it is routed.
+    pub fn queue_mut(&mut self, topic: &K) -> Option<&mut Q> {
+        let index = self.index_of(topic)?;
+        self.routes[index].as_mut().map(|(_, queue)| queue)
+    }
+
+    /// Iterate over the routed topics.
+    pub fn topics(&self) -> impl Iterator<Item = &K> {
+        self.routes.iter().flatten().map(|(topic, _)| topic)
+    }
+
+    /// Get the total number of elements queued over every topic.
+    pub fn size(&self) -> usize {
+        self.routes
+            .iter()
+            .flatten()
+            .map(|(_, queue)| queue.size())
+            .sum()
+    }
+
+    /// Check if every queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.routes
+            .iter()
+            .flatten()
+            .all(|(_, queue)| queue.is_empty())
+    }
+
+    fn index_of(&self, topic: &K) -> Option<usize> {
+        self.routes
+            .iter()
+            .position(|route| route.as_ref().is_some_and(|(other, _)| other == topic))
+    }
+}
+
+impl<K: Copy + Eq, T: Copy, Q: TypedQueue<T>, const TOPICS: usize> Default
+    for QueueRouter<K, T, Q, TOPICS>
+{
+    fn default() -> Self {
+        QueueRouter::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueueRouter;
+    use crate::basic_typed_queue::BasicTypedQueue;
+    #[cfg(feature = "threads")]
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::{QueueError, TypedQueue};
+
+    // Arbitrary queue size and number of topics for tests
+    const SIZE: usize = 16;
+    const TOPICS: usize = 3;
+
+    type Queue = BasicTypedQueue<u32, SIZE>;
+
+    #[test]
+    fn routing() {
+        let mut router = QueueRouter::<&str, u32, Queue, TOPICS>::default();
+        assert!(router.add_topic("status", Queue::new()).unwrap().is_none());
+        assert!(router.add_topic("command", Queue::new()).unwrap().is_none());
+        assert_eq!(router.push(&"log", 0), Err(QueueError::NoRoute));
+        assert_eq!(router.pop(&"log"), Err(QueueError::NoRoute));
+
+        assert!(router.push(&"status", 1).is_ok());
+        assert!(router.push(&"command", 2).is_ok());
+        assert!(router.push(&"status", 3).is_ok());
+        assert_eq!(router.size(), 3);
+        assert_eq!(router.queue(&"status").unwrap().size(), 2);
+
+        assert_eq!(router.pop(&"status").unwrap(), 1);
+        assert_eq!(router.pop(&"command").unwrap(), 2);
+        assert_eq!(router.pop(&"command"), Err(QueueError::QueueEmpty));
+
+        // Removing a topic hands its queue back
+        let status = router.remove_topic(&"status").unwrap();
+        assert_eq!(status.front().unwrap(), &3);
+        assert_eq!(router.topics().collect::<Vec<_>>(), [&"command"]);
+        assert!(router.is_empty());
+    }
+
+    #[test]
+    fn topic_limit() {
+        let mut router = QueueRouter::<u8, u32, Queue, TOPICS>::default();
+        for topic in 0..TOPICS as u8 {
+            assert!(router.add_topic(topic, Queue::new()).is_ok());
+        }
+        assert!(router.add_topic(TOPICS as u8, Queue::new()).is_err());
+
+        // An existing topic can be rerouted
+        assert!(router.push(&0, 1).is_ok());
+        let old = router.add_topic(0, Queue::new()).unwrap().unwrap();
+        assert_eq!(old.size(), 1);
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn pop_any_round_robin() {
+        let shared = [
+            ThreadSafeTypedQueue::<u32, SIZE>::new(),
+            ThreadSafeTypedQueue::<u32, SIZE>::new(),
+        ];
+        let mut router = QueueRouter::<char, u32, _, TOPICS>::default();
+        assert!(router.add_topic('a', &shared[0]).is_ok());
+        assert!(router.add_topic('b', &shared[1]).is_ok());
+
+        for n in 0..3 {
+            assert!(router.push(&'a', n).is_ok());
+        }
+        assert!(router.push(&'b', 10).is_ok());
+
+        // Topics take turns while both have elements
+        assert_eq!(router.pop_any().unwrap(), ('a', 0));
+        assert_eq!(router.pop_any().unwrap(), ('b', 10));
+        assert_eq!(router.pop_any().unwrap(), ('a', 1));
+        assert_eq!(router.pop_any().unwrap(), ('a', 2));
+        assert_eq!(router.pop_any().unwrap_err(), QueueError::QueueEmpty);
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn pop_any_errors() {
+        let shared = [
+            ThreadSafeTypedQueue::<u32, SIZE>::new(),
+            ThreadSafeTypedQueue::<u32, SIZE>::new(),
+        ];
+        let mut router = QueueRouter::<char, u32, _, TOPICS>::default();
+        assert!(router.add_topic('a', &shared[0]).is_ok());
+        assert!(router.add_topic('b', &shared[1]).is_ok());
+        assert!(router.push(&'b', 1).is_ok());
+
+        // Poison the first queue by panicking while it is locked
+        let _ = std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _guard = shared[0].iter().unwrap();
+                    panic!("poison the queue");
+                })
+                .join()
+        });
+
+        // The failure is reported rather than mistaken for an empty topic, then skipped
+        assert_eq!(router.pop_any().unwrap_err(), QueueError::MutexPoisoned);
+        assert_eq!(router.pop_any().unwrap(), ('b', 1));
+    }
+}
@@ -0,0 +1,157 @@
+use tracing::{debug, trace};
+
+// Source of `tracing` events for one queue, each carrying the queue's name and its depth after
+// the operation. Routine pushes and pops are emitted at trace level, while elements lost to
+// overwrites or rejected by a full queue, and waits in a blocking wrapper, are emitted at debug
+// level.
+pub(crate) struct QueueTracer {
+    name: String,
+}
+
+impl QueueTracer {
+    pub(crate) fn new(name: &str) -> Self {
+        QueueTracer {
+            name: name.to_owned(),
+        }
+    }
+
+    pub(crate) fn on_push(&self, count: usize, depth: usize) {
+        trace!(queue = %self.name, count, depth, "push");
+    }
+
+    pub(crate) fn on_pop(&self, count: usize, depth: usize) {
+        trace!(queue = %self.name, count, depth, "pop");
+    }
+
+    pub(crate) fn on_discard(&self, count: usize, depth: usize) {
+        trace!(queue = %self.name, count, depth, "discard");
+    }
+
+    pub(crate) fn on_overwrite(&self, depth: usize) {
+        debug!(queue = %self.name, depth, "overwrite");
+    }
+
+    pub(crate) fn on_full(&self, depth: usize) {
+        debug!(queue = %self.name, depth, "full");
+    }
+
+    pub(crate) fn on_wait(&self, depth: usize) {
+        debug!(queue = %self.name, depth, "wait");
+    }
+
+    pub(crate) fn on_wait_done(&self, depth: usize) {
+        debug!(queue = %self.name, depth, "wait_done");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::presets::BlockingQueue;
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::TypedQueue;
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 4;
+
+    // Subscriber logging every event as "message queue=.. depth=..".
+    #[derive(Default)]
+    struct TestSubscriber {
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct EventVisitor(String);
+
+    impl Visit for EventVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            match field.name() {
+                "message" => self.0.insert_str(0, &format!("{:?}", value)),
+                "queue" | "depth" => self.0 += &format!(" {}={:?}", field.name(), value),
+                _ => {}
+            }
+        }
+    }
+
+    impl Subscriber for TestSubscriber {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut visitor = EventVisitor(String::new());
+            event.record(&mut visitor);
+            self.events.lock().unwrap().push(visitor.0);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn events() {
+        let subscriber = TestSubscriber::default();
+        let events = Arc::clone(&subscriber.events);
+
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        queue.enable_tracing("ingest").unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            for n in 0..SIZE as u32 {
+                assert!(queue.push(n).is_ok());
+            }
+            assert!(queue.push(100).is_err());
+            assert!(queue.push_overwrite(100).is_ok());
+            assert!(queue.pop().is_ok());
+        });
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), SIZE + 5);
+        assert_eq!(events[0], "push queue=ingest depth=1");
+        assert_eq!(events[SIZE], "full queue=ingest depth=4");
+        assert_eq!(events[SIZE + 2], "overwrite queue=ingest depth=4");
+        assert_eq!(events[SIZE + 4], "pop queue=ingest depth=3");
+    }
+
+    #[test]
+    fn wait_events() {
+        let subscriber = TestSubscriber::default();
+        let events = Arc::clone(&subscriber.events);
+
+        let queue = BlockingQueue::<u32, SIZE>::new();
+        queue.inner().enable_tracing("jobs").unwrap();
+        thread::scope(|scope| {
+            // Subscribers are per thread, so only the waiting producer's events are recorded
+            tracing::subscriber::with_default(subscriber, || {
+                for n in 0..SIZE as u32 {
+                    assert!(queue.push(n).is_ok());
+                }
+                scope.spawn(|| {
+                    thread::sleep(Duration::from_millis(20));
+                    assert_eq!(queue.pop(), Ok(0));
+                });
+                assert!(queue.push(100).is_ok());
+            });
+        });
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), SIZE + 4);
+        assert_eq!(events[SIZE], "full queue=jobs depth=4");
+        assert_eq!(events[SIZE + 1], "wait queue=jobs depth=4");
+        assert_eq!(events[SIZE + 2], "wait_done queue=jobs depth=3");
+        assert_eq!(events[SIZE + 3], "push queue=jobs depth=4");
+    }
+}
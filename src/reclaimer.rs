@@ -0,0 +1,181 @@
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Background thread releasing retired elements, keeping expensive releases (freeing the buffer
+// behind a handle, closing a file descriptor) off the caller's hot path. Retired elements wait in
+// a bounded side queue of `N` elements, so retiring never allocates; the thread sleeps while the
+// side queue is empty. A reclaimer plugs into the queue wrappers as the destination of evicted
+// elements, e.g. `DeadLetterQueue<T, BasicTypedQueue<T, M>, &Reclaimer<T, N>>`, and popped
+// elements can be handed to `retire()` once used instead of being released in place.
+pub struct Reclaimer<T: Copy + Send + 'static, const N: usize> {
+    queue: Arc<ThreadSafeTypedQueue<T, N>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T: Copy + Send + 'static, const N: usize> Reclaimer<T, N> {
+    /// Spawn the reclamation thread, which passes every retired element to `reclaim`.
+    pub fn new<F: FnMut(T) + Send + 'static>(mut reclaim: F) -> Self {
+        let queue = Arc::new(ThreadSafeTypedQueue::<T, N>::new());
+        let side_queue = Arc::clone(&queue);
+        let thread = thread::spawn(move || loop {
+            // Once closed, nothing more can be retired, so the side queue is drained one last time
+            let closed = side_queue.is_closed();
+            while let Ok(value) = (&*side_queue).pop() {
+                reclaim(value);
+            }
+            if closed {
+                break;
+            }
+            thread::park();
+        });
+
+        Reclaimer {
+            queue,
+            thread: Some(thread),
+        }
+    }
+
+    /// Hand an element over to the reclamation thread to be released there. Fails with
+    /// `QueueError::QueueFull` if the thread has fallen `N` elements behind, in which case the
+    /// caller keeps the element.
+    pub fn retire(&self, value: T) -> Result<(), QueueError> {
+        (&*self.queue).push(value)?;
+        if let Some(thread) = &self.thread {
+            thread.thread().unpark();
+        }
+        Ok(())
+    }
+
+    /// Get the number of retired elements not released yet.
+    pub fn pending(&self) -> usize {
+        self.queue.size()
+    }
+}
+
+impl<T: Copy + Send + 'static, const N: usize> Drop for Reclaimer<T, N> {
+    // Wait for every retired element to be released before returning.
+    fn drop(&mut self) {
+        let _ = self.queue.close();
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+// Retire elements pushed through `TypedQueue`, so that wrappers evicting elements into another
+// queue can hand them to the reclamation thread. Pending elements are never overwritten, as they
+// would then never be released: overwriting pushes fail like plain ones once the side queue is
+// full. Only the reclamation thread consumes the side queue, so pops always find it empty, and
+// `clear()` leaves the pending elements to be released.
+impl<T: Copy + Send + 'static, const N: usize> TypedQueue<T> for &Reclaimer<T, N> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.retire(input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.retire(input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        self.retire(*input)
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        self.retire(*input)
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        Err(QueueError::QueueEmpty)
+    }
+
+    fn pop_ref(&mut self, _output: &mut T) -> Result<(), QueueError> {
+        Err(QueueError::QueueEmpty)
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        Ok(())
+    }
+
+    fn is_full(&self) -> bool {
+        self.queue.is_full()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    fn size(&self) -> usize {
+        self.queue.size()
+    }
+
+    fn capacity(&self) -> usize {
+        N
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Reclaimer;
+    use crate::basic_typed_queue::BasicTypedQueue;
+    use crate::dead_letter_queue::DeadLetterQueue;
+    use crate::typed_queue::{QueueError, TypedQueue};
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, ThreadId};
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    // Elements released so far, along with the thread releasing them
+    type Released = Arc<Mutex<Vec<(u32, ThreadId)>>>;
+
+    // Reclaimer recording the elements it releases
+    fn recording() -> (Reclaimer<u32, SIZE>, Released) {
+        let released = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&released);
+        let reclaimer = Reclaimer::new(move |value| {
+            log.lock().unwrap().push((value, thread::current().id()));
+        });
+        (reclaimer, released)
+    }
+
+    #[test]
+    fn retire() {
+        let (reclaimer, released) = recording();
+        for n in 0..SIZE as u32 {
+            while reclaimer.retire(n) == Err(QueueError::QueueFull) {
+                thread::yield_now();
+            }
+        }
+        drop(reclaimer);
+
+        // Every element is released in order, off the retiring thread
+        let released = released.lock().unwrap();
+        assert_eq!(released.len(), SIZE);
+        for (n, &(value, owner)) in released.iter().enumerate() {
+            assert_eq!(value, n as u32);
+            assert_ne!(owner, thread::current().id());
+        }
+    }
+
+    #[test]
+    fn evictions() {
+        let (reclaimer, released) = recording();
+        {
+            let mut queue = DeadLetterQueue::new(BasicTypedQueue::<u32, 2>::default(), &reclaimer);
+            for n in 0..5 {
+                assert!(queue.push_overwrite(n).is_ok());
+            }
+            assert_eq!(queue.dead_lettered(), 3);
+            assert_eq!(queue.dead_letters_mut().pop(), Err(QueueError::QueueEmpty));
+        }
+        drop(reclaimer);
+
+        let released = released.lock().unwrap();
+        let values: Vec<_> = released.iter().map(|&(value, _)| value).collect();
+        assert_eq!(values, [0, 1, 2]);
+    }
+}
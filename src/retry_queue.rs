@@ -0,0 +1,228 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use crate::delay_queue::DelayQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+/// Element handed out by a `RetryQueue`, along with the number of times it has been attempted,
+/// including this attempt.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Attempt<T> {
+    /// The element to process.
+    pub value: T,
+    /// Attempt number, starting at 1 for the first attempt.
+    pub attempt: u32,
+}
+
+/// Retry limits and exponential backoff parameters for a `RetryQueue`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// Number of attempts after which a failed element is given up on.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every later retry.
+    pub initial_backoff: Duration,
+    /// Upper limit on the delay before a retry.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Get the delay before retrying an element whose attempt number `attempt` just failed.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+// Decorator adding retries with exponential backoff to a queue feeding a flaky downstream. Each
+// element is handed out as an `Attempt` counting how often it has been tried. When processing
+// fails, the consumer passes the attempt back to `retry()`, which holds the element in a delay
+// queue of `N` elements until its backoff has elapsed, or gives up on it once it has been tried
+// `max_attempts` times. Due retries are popped before fresh elements. Not thread-safe, although
+// the underlying queue may be a shared one.
+pub struct RetryQueue<T: Copy, Q: TypedQueue<T>, const N: usize> {
+    queue: Q,
+    retries: DelayQueue<Attempt<T>, N>,
+    policy: RetryPolicy,
+    given_up: u64,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy, Q: TypedQueue<T>, const N: usize> RetryQueue<T, Q, N> {
+    /// Wrap a queue, retrying failed elements according to `policy`.
+    pub fn new(queue: Q, policy: RetryPolicy) -> Self {
+        RetryQueue {
+            queue,
+            retries: DelayQueue::new(),
+            policy,
+            given_up: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Push a fresh element to the underlying queue. Fails if queue is full.
+    pub fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.queue.push(input)
+    }
+
+    /// Pop the next element to attempt: a retry whose backoff has elapsed if there is one, or
+    /// else a fresh element. Fails with `QueueError::WouldBlock` if only retries that are not
+    /// due yet remain, or `QueueError::QueueEmpty` if there is nothing left at all.
+    pub fn pop(&mut self) -> Result<Attempt<T>, QueueError> {
+        if let Ok(attempt) = self.retries.pop_due() {
+            return Ok(attempt);
+        }
+
+        match self.queue.pop() {
+            Ok(value) => Ok(Attempt { value, attempt: 1 }),
+            Err(QueueError::QueueEmpty) if !self.retries.is_empty() => Err(QueueError::WouldBlock),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Report that an attempt failed, scheduling the element to be retried after its backoff.
+    /// Returns the element if it is given up on instead, having been tried `max_attempts`
+    /// times. Fails if `N` retries are already scheduled.
+    pub fn retry(&mut self, failed: Attempt<T>) -> Result<Option<T>, QueueError> {
+        if failed.attempt >= self.policy.max_attempts {
+            self.given_up += 1;
+            return Ok(Some(failed.value));
+        }
+
+        let next = Attempt {
+            value: failed.value,
+            attempt: failed.attempt + 1,
+        };
+        self.retries
+            .push_after(next, self.policy.backoff(failed.attempt))?;
+
+        Ok(None)
+    }
+
+    /// Get the time left until the next scheduled retry is due, zero if one is due already, or
+    /// `None` if no retry is scheduled.
+    pub fn time_to_next_retry(&self) -> Option<Duration> {
+        self.retries.time_to_next()
+    }
+
+    /// Get the number of scheduled retries.
+    pub fn pending_retries(&self) -> usize {
+        self.retries.size()
+    }
+
+    /// Get the number of elements given up on after `max_attempts` attempts.
+    pub fn given_up(&self) -> u64 {
+        self.given_up
+    }
+
+    /// Get the retry policy.
+    pub fn policy(&self) -> RetryPolicy {
+        self.policy
+    }
+
+    /// Get a reference to the underlying queue.
+    pub fn inner(&self) -> &Q {
+        &self.queue
+    }
+
+    /// Get a mutable reference to the underlying queue.
+    pub fn inner_mut(&mut self) -> &mut Q {
+        &mut self.queue
+    }
+
+    /// Unwrap the underlying queue, dropping any scheduled retries.
+    pub fn into_inner(self) -> Q {
+        self.queue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RetryPolicy, RetryQueue};
+    use crate::basic_typed_queue::BasicTypedQueue;
+    use crate::typed_queue::QueueError;
+    use std::thread;
+    use std::time::Duration;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(50),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(10));
+        assert_eq!(policy.backoff(2), Duration::from_millis(20));
+        assert_eq!(policy.backoff(3), Duration::from_millis(40));
+        assert_eq!(policy.backoff(4), Duration::from_millis(50));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn retries() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        };
+        let mut queue =
+            RetryQueue::<u32, _, SIZE>::new(BasicTypedQueue::<u32, SIZE>::new(), policy);
+        assert!(queue.push(1).is_ok());
+        assert!(queue.push(2).is_ok());
+
+        let first = queue.pop().unwrap();
+        assert_eq!((first.value, first.attempt), (1, 1));
+        assert_eq!(queue.retry(first), Ok(None));
+        assert_eq!(queue.pending_retries(), 1);
+
+        // Due retries come before fresh elements
+        let second = queue.pop().unwrap();
+        assert_eq!((second.value, second.attempt), (1, 2));
+        let attempt = queue.pop().unwrap();
+        assert_eq!((attempt.value, attempt.attempt), (2, 1));
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+
+        assert_eq!(queue.retry(second), Ok(None));
+        let third = queue.pop().unwrap();
+        assert_eq!(third.attempt, 3);
+        assert_eq!(queue.retry(third), Ok(Some(1)));
+        assert_eq!(queue.given_up(), 1);
+        assert_eq!(queue.pending_retries(), 0);
+    }
+
+    #[test]
+    fn waits_for_backoff() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(20),
+            ..RetryPolicy::default()
+        };
+        let mut queue =
+            RetryQueue::<u32, _, SIZE>::new(BasicTypedQueue::<u32, SIZE>::new(), policy);
+        assert!(queue.push(1).is_ok());
+
+        let attempt = queue.pop().unwrap();
+        assert_eq!(queue.retry(attempt), Ok(None));
+        assert_eq!(queue.pop().unwrap_err(), QueueError::WouldBlock);
+
+        thread::sleep(queue.time_to_next_retry().unwrap());
+        let attempt = queue.pop().unwrap();
+        assert_eq!((attempt.value, attempt.attempt), (1, 2));
+        assert_eq!(queue.time_to_next_retry(), None);
+    }
+}
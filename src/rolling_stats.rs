@@ -0,0 +1,189 @@
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::typed_queue::TypedQueue;
+
+// Statistics over the last `N` samples, kept in a ring that overwrites the oldest sample once
+// full. The mean and variance are updated incrementally (Welford's method, extended to replace
+// an evicted sample), so each push costs O(1); the minimum and maximum are only recomputed, in
+// O(N), when the sample holding them is evicted.
+pub struct RollingStats<T: Copy, const N: usize> {
+    samples: BasicTypedQueue<T, N>,
+    mean: f64,
+    m2: f64, // sum of squared differences from the mean
+    min: Option<T>,
+    max: Option<T>,
+}
+
+impl<T: Copy + PartialOrd + Into<f64>, const N: usize> RollingStats<T, N> {
+    /// Create an empty window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero.
+    pub fn new() -> Self {
+        assert!(N > 0, "rolling window size must be non-zero");
+
+        RollingStats {
+            samples: BasicTypedQueue::new(),
+            mean: 0.0,
+            m2: 0.0,
+            min: None,
+            max: None,
+        }
+    }
+
+    /// Add a sample, evicting and returning the oldest one if the window is full.
+    pub fn push(&mut self, sample: T) -> Option<T> {
+        let evicted = self.samples.push_evict(sample).ok().flatten();
+        let new = sample.into();
+
+        match evicted {
+            Some(old) => {
+                let old_value = old.into();
+                let old_mean = self.mean;
+                self.mean += (new - old_value) / N as f64;
+                self.m2 += (new - old_value) * (new - self.mean + old_value - old_mean);
+                // Rounding may push the sum slightly below zero for constant samples
+                self.m2 = self.m2.max(0.0);
+            }
+            None => {
+                let delta = new - self.mean;
+                self.mean += delta / self.samples.size() as f64;
+                self.m2 += delta * (new - self.mean);
+            }
+        }
+
+        if evicted.is_some_and(|old| Some(old) == self.min || Some(old) == self.max) {
+            self.rescan();
+        } else {
+            if self.min.is_none_or(|min| sample < min) {
+                self.min = Some(sample);
+            }
+            if self.max.is_none_or(|max| sample > max) {
+                self.max = Some(sample);
+            }
+        }
+
+        evicted
+    }
+
+    /// Get the mean of the samples in the window, or `None` if it is empty.
+    pub fn mean(&self) -> Option<f64> {
+        (!self.is_empty()).then_some(self.mean)
+    }
+
+    /// Get the population variance of the samples in the window, or `None` if it is empty.
+    pub fn variance(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| self.m2 / self.len() as f64)
+    }
+
+    /// Get the population standard deviation of the samples in the window, or `None` if it is
+    /// empty.
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    /// Get the smallest sample in the window, or `None` if it is empty.
+    pub fn min(&self) -> Option<T> {
+        self.min
+    }
+
+    /// Get the largest sample in the window, or `None` if it is empty.
+    pub fn max(&self) -> Option<T> {
+        self.max
+    }
+
+    /// Get the number of samples in the window.
+    pub fn len(&self) -> usize {
+        self.samples.size()
+    }
+
+    /// Check if the window holds no samples.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Get the samples in the window, oldest first.
+    pub fn samples(&self) -> &BasicTypedQueue<T, N> {
+        &self.samples
+    }
+
+    /// Remove every sample from the window.
+    pub fn clear(&mut self) {
+        *self = RollingStats::new();
+    }
+
+    // Recompute the minimum and maximum from scratch.
+    fn rescan(&mut self) {
+        self.min = None;
+        self.max = None;
+        for &sample in self.samples.iter() {
+            if self.min.is_none_or(|min| sample < min) {
+                self.min = Some(sample);
+            }
+            if self.max.is_none_or(|max| sample > max) {
+                self.max = Some(sample);
+            }
+        }
+    }
+}
+
+impl<T: Copy + PartialOrd + Into<f64>, const N: usize> Default for RollingStats<T, N> {
+    fn default() -> Self {
+        RollingStats::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RollingStats;
+
+    // Arbitrary window size for tests
+    const SIZE: usize = 4;
+
+    #[test]
+    fn empty() {
+        let stats = RollingStats::<u32, SIZE>::default();
+        assert!(stats.is_empty());
+        assert_eq!(stats.mean(), None);
+        assert_eq!(stats.variance(), None);
+        assert_eq!(stats.min(), None);
+        assert_eq!(stats.max(), None);
+    }
+
+    #[test]
+    fn filling() {
+        let mut stats = RollingStats::<u32, SIZE>::default();
+        for sample in [2, 4, 4, 6] {
+            assert_eq!(stats.push(sample), None);
+        }
+
+        assert_eq!(stats.mean(), Some(4.0));
+        assert_eq!(stats.variance(), Some(2.0));
+        assert_eq!(stats.min(), Some(2));
+        assert_eq!(stats.max(), Some(6));
+    }
+
+    #[test]
+    fn overwriting() {
+        let mut stats = RollingStats::<f32, SIZE>::default();
+        for sample in [9.0, 1.0, 5.0, 5.0] {
+            assert_eq!(stats.push(sample), None);
+        }
+
+        // Evicting the maximum and then the minimum
+        assert_eq!(stats.push(3.0), Some(9.0));
+        assert_eq!(stats.max(), Some(5.0));
+        assert_eq!(stats.push(7.0), Some(1.0));
+        assert_eq!(stats.min(), Some(3.0));
+        assert_eq!(stats.max(), Some(7.0));
+
+        // Window is now [5, 5, 3, 7]
+        assert_eq!(stats.len(), SIZE);
+        assert!((stats.mean().unwrap() - 5.0).abs() < 1e-9);
+        assert!((stats.variance().unwrap() - 2.0).abs() < 1e-9);
+        assert!((stats.std_dev().unwrap() - 2.0f64.sqrt()).abs() < 1e-9);
+
+        stats.clear();
+        assert!(stats.is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+use std::marker::PhantomData;
+
+use crate::typed_queue::{QueueError, TypedQueue};
+
+/// Element stamped with its position in the stream of elements pushed by a `SequencedQueue`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Sequenced<T: Copy> {
+    /// Number of elements pushed before this one.
+    pub sequence: u64,
+    /// The element itself.
+    pub value: T,
+}
+
+/// Range of consecutive sequence numbers that never reached the consumer.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Gap {
+    /// First missing sequence number.
+    pub first: u64,
+    /// Number of missing elements.
+    pub count: u64,
+}
+
+// Consumer-side tracker of the sequence numbers seen so far. Each sequence number past the next
+// expected one reveals a gap, while older ones (duplicates or reordered elements) are ignored.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct GapDetector {
+    expected: Option<u64>, // next sequence number, unknown until the first element is seen
+    lost: u64,
+    gaps: u64,
+}
+
+impl GapDetector {
+    /// Create a detector that accepts any sequence number as the first one.
+    pub fn new() -> Self {
+        GapDetector::default()
+    }
+
+    /// Create a detector expecting the stream to start at `sequence`, so that elements lost
+    /// before the first one received are reported too.
+    pub fn starting_at(sequence: u64) -> Self {
+        GapDetector {
+            expected: Some(sequence),
+            ..GapDetector::default()
+        }
+    }
+
+    /// Record a received sequence number, returning the gap it reveals, if any.
+    pub fn observe(&mut self, sequence: u64) -> Option<Gap> {
+        let expected = self.expected.unwrap_or(sequence);
+        if sequence < expected {
+            return None;
+        }
+
+        self.expected = Some(sequence + 1);
+        if sequence == expected {
+            return None;
+        }
+
+        let gap = Gap {
+            first: expected,
+            count: sequence - expected,
+        };
+        self.lost += gap.count;
+        self.gaps += 1;
+        Some(gap)
+    }
+
+    /// Get the next sequence number expected, if any element has been seen.
+    pub fn expected(&self) -> Option<u64> {
+        self.expected
+    }
+
+    /// Get the total number of missing elements so far.
+    pub fn lost(&self) -> u64 {
+        self.lost
+    }
+
+    /// Get the number of gaps detected so far.
+    pub fn gaps(&self) -> u64 {
+        self.gaps
+    }
+}
+
+// Wrapper stamping each pushed element with a monotonically increasing sequence number and
+// checking the sequence numbers of popped elements, so that a consumer can quantify how many
+// elements were lost, e.g. to overwrites, instead of losing them silently. With a shared queue,
+// the producer and the consumer each wrap their own reference to it, e.g.
+// `SequencedQueue<T, &ThreadSafeTypedQueue<Sequenced<T>, N>>`: the producer's wrapper numbers
+// the elements and the consumer's detects the gaps. Several producers would each need their own
+// queue, as their sequence numbers would interleave.
+pub struct SequencedQueue<T: Copy, Q: TypedQueue<Sequenced<T>>> {
+    queue: Q,
+    next: u64, // sequence number of the next element pushed
+    detector: GapDetector,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy, Q: TypedQueue<Sequenced<T>>> SequencedQueue<T, Q> {
+    /// Wrap a queue, numbering elements from zero.
+    pub fn new(queue: Q) -> Self {
+        SequencedQueue {
+            queue,
+            next: 0,
+            detector: GapDetector::starting_at(0),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Push an element stamped with the next sequence number, which is returned. A rejected
+    /// element does not use up a sequence number. Fails if queue is full.
+    pub fn push(&mut self, input: T) -> Result<u64, QueueError> {
+        self.queue.push(self.stamp(input))?;
+        Ok(self.advance())
+    }
+
+    /// Push an element stamped with the next sequence number, which is returned, overwriting the
+    /// oldest element if the queue is full. The consumer sees the overwritten element as a gap.
+    pub fn push_overwrite(&mut self, input: T) -> Result<u64, QueueError> {
+        self.queue.push_overwrite(self.stamp(input))?;
+        Ok(self.advance())
+    }
+
+    /// Pop an element along with the gap preceding it, if elements before it were lost. Fails if
+    /// queue is empty.
+    pub fn pop(&mut self) -> Result<(T, Option<Gap>), QueueError> {
+        let element = self.queue.pop()?;
+        Ok((element.value, self.detector.observe(element.sequence)))
+    }
+
+    /// Get the sequence number the next pushed element will be stamped with.
+    pub fn next_sequence(&self) -> u64 {
+        self.next
+    }
+
+    /// Get the gap detector of the consumer side.
+    pub fn detector(&self) -> &GapDetector {
+        &self.detector
+    }
+
+    /// Get the total number of elements found missing by the consumer side.
+    pub fn lost(&self) -> u64 {
+        self.detector.lost()
+    }
+
+    /// Get a reference to the underlying queue.
+    pub fn inner(&self) -> &Q {
+        &self.queue
+    }
+
+    /// Unwrap the underlying queue.
+    pub fn into_inner(self) -> Q {
+        self.queue
+    }
+
+    fn stamp(&self, value: T) -> Sequenced<T> {
+        Sequenced {
+            sequence: self.next,
+            value,
+        }
+    }
+
+    fn advance(&mut self) -> u64 {
+        self.next += 1;
+        self.next - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gap, GapDetector, SequencedQueue};
+    use crate::basic_typed_queue::BasicTypedQueue;
+    #[cfg(feature = "threads")]
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::QueueError;
+    #[cfg(feature = "threads")]
+    use std::thread;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 4;
+
+    #[test]
+    fn detector() {
+        let mut detector = GapDetector::new();
+        assert_eq!(detector.observe(10), None);
+        assert_eq!(detector.observe(11), None);
+        assert_eq!(
+            detector.observe(14),
+            Some(Gap {
+                first: 12,
+                count: 2
+            })
+        );
+
+        // Duplicates and late elements are not counted
+        assert_eq!(detector.observe(13), None);
+        assert_eq!(detector.observe(15), None);
+        assert_eq!(detector.expected(), Some(16));
+        assert_eq!(detector.lost(), 2);
+        assert_eq!(detector.gaps(), 1);
+    }
+
+    #[test]
+    fn overwrites() {
+        let mut queue = SequencedQueue::new(BasicTypedQueue::<_, SIZE>::new());
+        for n in 0..SIZE as u32 + 2 {
+            assert_eq!(queue.push_overwrite(n), Ok(n as u64));
+        }
+        assert_eq!(queue.push(0), Err(QueueError::QueueFull));
+        assert_eq!(queue.next_sequence(), SIZE as u64 + 2);
+
+        // The two overwritten elements are reported before the first one popped
+        assert_eq!(queue.pop().unwrap(), (2, Some(Gap { first: 0, count: 2 })));
+        assert_eq!(queue.pop().unwrap(), (3, None));
+        assert_eq!(queue.lost(), 2);
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn shared_queue() {
+        const COUNT: u32 = 10_000;
+        let shared = ThreadSafeTypedQueue::<_, SIZE>::new();
+
+        let (received, lost) = thread::scope(|scope| {
+            let mut producer = SequencedQueue::new(&shared);
+            scope.spawn(move || {
+                for n in 0..COUNT {
+                    assert!(producer.push_overwrite(n).is_ok());
+                }
+            });
+
+            // Every element is either received or counted as lost
+            let mut consumer = SequencedQueue::new(&shared);
+            let mut received = 0;
+            let mut last = None;
+            while last != Some(COUNT - 1) {
+                if let Ok((value, _)) = consumer.pop() {
+                    received += 1;
+                    last = Some(value);
+                }
+            }
+            (received, consumer.lost())
+        });
+
+        assert_eq!(received + lost, COUNT as u64);
+    }
+}
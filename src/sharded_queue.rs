@@ -0,0 +1,260 @@
+use std::array;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Thread-safe queue striped over `SHARDS` independently locked queues of `N` elements each, so
+// that concurrent producers and consumers mostly contend on different locks. Pushes and pops
+// start at a different shard each time, round-robin, and move on to the next shard when one is
+// full or empty. Elements are FIFO within a shard, but there is no global order between shards:
+// this trades strict ordering for throughput under heavy MPMC load.
+pub struct ShardedQueue<T: Copy, const N: usize, const SHARDS: usize> {
+    shards: [ThreadSafeTypedQueue<T, N>; SHARDS],
+    push_cursor: AtomicUsize,
+    pop_cursor: AtomicUsize,
+}
+
+impl<T: Copy, const N: usize, const SHARDS: usize> ShardedQueue<T, N, SHARDS> {
+    /// Create a new sharded queue.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `SHARDS` is zero.
+    pub fn new() -> Self {
+        assert!(SHARDS > 0, "sharded queue must have at least one shard");
+
+        ShardedQueue {
+            shards: array::from_fn(|_| ThreadSafeTypedQueue::new()),
+            push_cursor: AtomicUsize::new(0),
+            pop_cursor: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get a reference to the shard at `index`, e.g. to read its statistics.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than `SHARDS`.
+    pub fn shard(&self, index: usize) -> &ThreadSafeTypedQueue<T, N> {
+        &self.shards[index]
+    }
+
+    // Shards in the order an operation should try them, starting at the next one of `cursor`.
+    fn shards_from(
+        &self,
+        cursor: &AtomicUsize,
+    ) -> impl Iterator<Item = &ThreadSafeTypedQueue<T, N>> {
+        let start = cursor.fetch_add(1, Ordering::Relaxed);
+        (0..SHARDS).map(move |offset| &self.shards[(start + offset) % SHARDS])
+    }
+}
+
+impl<T: Copy, const N: usize, const SHARDS: usize> Default for ShardedQueue<T, N, SHARDS> {
+    fn default() -> Self {
+        ShardedQueue::new()
+    }
+}
+
+impl<T: Copy, const N: usize, const SHARDS: usize> TypedQueue<T> for ShardedQueue<T, N, SHARDS> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        (&*self).push(input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        (&*self).push_overwrite(input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        (&*self).push_ref(input)
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        (&*self).push_ref_overwrite(input)
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        (&*self).pop()
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        (&*self).pop_ref(output)
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        (&*self).clear()
+    }
+
+    fn is_full(&self) -> bool {
+        (&self).is_full()
+    }
+
+    fn is_empty(&self) -> bool {
+        (&self).is_empty()
+    }
+
+    fn size(&self) -> usize {
+        (&self).size()
+    }
+
+    fn capacity(&self) -> usize {
+        N * SHARDS
+    }
+}
+
+// Implemented for shared references too, so that threads can push and pop concurrently, as with
+// `ThreadSafeTypedQueue`.
+impl<T: Copy, const N: usize, const SHARDS: usize> TypedQueue<T> for &ShardedQueue<T, N, SHARDS> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref(&input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref_overwrite(&input)
+    }
+
+    // Fails with `QueueError::QueueFull` only if every shard is full.
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        for mut shard in self.shards_from(&self.push_cursor) {
+            match shard.push_ref(input) {
+                Err(QueueError::QueueFull) => continue,
+                result => return result,
+            }
+        }
+
+        Err(QueueError::QueueFull)
+    }
+
+    // Pushes to a free shard if there is one, and otherwise overwrites the oldest element of the
+    // next shard.
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        match self.push_ref(input) {
+            Err(QueueError::QueueFull) => {
+                let mut shards = self.shards_from(&self.push_cursor);
+                let mut shard = shards.next().ok_or(QueueError::QueueFull)?;
+                shard.push_ref_overwrite(input)
+            }
+            result => result,
+        }
+    }
+
+    // Fails with `QueueError::QueueEmpty` only if every shard is empty.
+    fn pop(&mut self) -> Result<T, QueueError> {
+        for mut shard in self.shards_from(&self.pop_cursor) {
+            match shard.pop() {
+                Err(QueueError::QueueEmpty) => continue,
+                result => return result,
+            }
+        }
+
+        Err(QueueError::QueueEmpty)
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        *output = self.pop()?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        for mut shard in &self.shards {
+            shard.clear()?;
+        }
+        Ok(())
+    }
+
+    fn is_full(&self) -> bool {
+        self.shards.iter().all(|shard| (&shard).is_full())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| (&shard).is_empty())
+    }
+
+    fn size(&self) -> usize {
+        self.shards.iter().map(|shard| (&shard).size()).sum()
+    }
+
+    fn capacity(&self) -> usize {
+        N * SHARDS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ShardedQueue;
+    use crate::typed_queue::{QueueError, TypedQueue};
+    use std::collections::HashSet;
+    use std::thread;
+
+    // Arbitrary queue size and number of shards for tests
+    const SIZE: usize = 4;
+    const SHARDS: usize = 4;
+
+    #[test]
+    fn striping() {
+        let mut queue = ShardedQueue::<u32, SIZE, SHARDS>::default();
+        assert_eq!(queue.capacity(), SIZE * SHARDS);
+        for n in 0..SHARDS as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+
+        // Consecutive pushes go to consecutive shards
+        for n in 0..SHARDS {
+            assert_eq!(queue.shard(n).size(), 1);
+        }
+
+        for n in 0..SHARDS as u32 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+    }
+
+    #[test]
+    fn full_shards() {
+        let mut queue = ShardedQueue::<u32, SIZE, SHARDS>::default();
+
+        // Pushes skip full shards
+        let mut shard = queue.shard(1);
+        for _ in 0..SIZE {
+            assert!(shard.push(0).is_ok());
+        }
+        for n in 0..(SIZE * (SHARDS - 1)) as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(queue.is_full());
+        assert_eq!(queue.push(0).unwrap_err(), QueueError::QueueFull);
+        assert!(queue.push_overwrite(100).is_ok());
+        assert_eq!(queue.size(), SIZE * SHARDS);
+
+        assert!(queue.clear().is_ok());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn concurrent() {
+        const COUNT: u32 = 10_000;
+        let queue = ShardedQueue::<u32, SIZE, SHARDS>::default();
+
+        let received = thread::scope(|scope| {
+            for producer in 0..2 {
+                let mut queue = &queue;
+                scope.spawn(move || {
+                    for n in 0..COUNT {
+                        while queue.push(producer * COUNT + n).is_err() {}
+                    }
+                });
+            }
+
+            let mut queue = &queue;
+            let mut received = HashSet::new();
+            while received.len() < 2 * COUNT as usize {
+                if let Ok(value) = queue.pop() {
+                    assert!(received.insert(value));
+                }
+            }
+            received
+        });
+
+        assert_eq!(received.len(), 2 * COUNT as usize);
+    }
+}
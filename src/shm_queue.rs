@@ -0,0 +1,393 @@
+use std::io;
+use std::mem::{align_of, size_of, MaybeUninit};
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::typed_queue::QueueError;
+
+// Single-producer, single-consumer queue of fixed-size records whose ring and indices live in a
+// caller-provided memory region, e.g. a shared `mmap` of a file or of `shm_open()` memory, so
+// that two processes can exchange records without sockets. One side formats the region with
+// `init()`, after which each process attaches its end with `ShmProducer::attach()` or
+// `ShmConsumer::attach()`.
+//
+// Layout of the region, which must be aligned to 64 bytes, with native-endian integers:
+//
+//     offset   0  u64  magic number, b"RQSHMQ01", written last by `init()`
+//     offset   8  u64  size of a record in bytes
+//     offset  16  u64  capacity in records
+//     offset  64  u64  tail: number of records pushed so far, only written by the producer
+//     offset 128  u64  head: number of records popped so far, only written by the consumer
+//     offset 192       `capacity` records, record `n` being stored in slot `n % capacity`
+//
+// The head and the tail sit on separate cache lines so that the two sides do not contend. Records
+// are copied bit for bit, so they must be plain data that means the same in both processes:
+// no pointers, references or handles.
+
+// Marks a formatted region, and changes with any incompatible change to the layout.
+const MAGIC: u64 = u64::from_ne_bytes(*b"RQSHMQ01");
+
+// Alignment required of the region, that of the header.
+const ALIGN: usize = align_of::<Header>();
+
+#[repr(C, align(64))]
+struct CacheLine<T>(T);
+
+#[repr(C)]
+struct Header {
+    magic: AtomicU64,
+    record_size: u64,
+    capacity: u64,
+    tail: CacheLine<AtomicU64>,
+    head: CacheLine<AtomicU64>,
+}
+
+/// Get the number of bytes a region needs to hold `capacity` records of type `T`.
+pub fn region_size<T: Copy>(capacity: usize) -> usize {
+    size_of::<Header>() + capacity * size_of::<T>()
+}
+
+/// Format a region of `len` bytes as an empty queue of as many records of type `T` as fit,
+/// returning that capacity. Fails with `QueueError::Io(io::ErrorKind::InvalidInput)` if the region
+/// is not aligned to 64 bytes or too small to hold a single record.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads and writes of `len` bytes for as long as any end of the queue is
+/// attached, and no end may be attached while the region is being formatted.
+pub unsafe fn init<T: Copy>(ptr: *mut u8, len: usize) -> Result<usize, QueueError> {
+    assert!(size_of::<T>() > 0, "zero-sized records cannot be shared");
+    assert!(
+        align_of::<T>() <= ALIGN,
+        "records must be aligned to at most 64 bytes"
+    );
+
+    let capacity = len.saturating_sub(size_of::<Header>()) / size_of::<T>();
+    if ptr.align_offset(ALIGN) != 0 || capacity == 0 {
+        return Err(QueueError::Io(io::ErrorKind::InvalidInput));
+    }
+
+    let header = ptr as *mut Header;
+    ptr::write(
+        header,
+        Header {
+            magic: AtomicU64::new(0),
+            record_size: size_of::<T>() as u64,
+            capacity: capacity as u64,
+            tail: CacheLine(AtomicU64::new(0)),
+            head: CacheLine(AtomicU64::new(0)),
+        },
+    );
+    (*header).magic.store(MAGIC, Ordering::Release);
+
+    Ok(capacity)
+}
+
+// View of a formatted region, shared by both ends.
+struct Region<T> {
+    header: *const Header,
+    slots: *mut MaybeUninit<T>,
+    capacity: u64,
+}
+
+impl<T: Copy> Region<T> {
+    // Check that the region was formatted for records of type `T`. The header may have been
+    // written by another process, so its capacity is checked against the length of the region
+    // before any slot is accessed.
+    unsafe fn attach(ptr: *mut u8, len: usize) -> Result<Self, QueueError> {
+        if ptr.align_offset(ALIGN) != 0 || len < size_of::<Header>() {
+            return Err(QueueError::Io(io::ErrorKind::InvalidInput));
+        }
+
+        let header = ptr as *const Header;
+        if (*header).magic.load(Ordering::Acquire) != MAGIC {
+            return Err(QueueError::Io(io::ErrorKind::InvalidData));
+        }
+        if (*header).record_size != size_of::<T>() as u64 {
+            return Err(QueueError::TypeMismatch);
+        }
+        let capacity = (*header).capacity;
+        let fits = usize::try_from(capacity)
+            .ok()
+            .and_then(|capacity| capacity.checked_mul(size_of::<T>()))
+            .and_then(|slots| slots.checked_add(size_of::<Header>()))
+            .is_some_and(|size| size <= len);
+        if capacity == 0 || !fits {
+            return Err(QueueError::Io(io::ErrorKind::InvalidData));
+        }
+
+        Ok(Region {
+            header,
+            slots: ptr.add(size_of::<Header>()) as *mut MaybeUninit<T>,
+            capacity,
+        })
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { &*self.header }
+    }
+
+    fn size(&self) -> usize {
+        let tail = self.header().tail.0.load(Ordering::Acquire);
+        let head = self.header().head.0.load(Ordering::Acquire);
+        tail.wrapping_sub(head) as usize
+    }
+
+    fn slot(&self, index: u64) -> *mut MaybeUninit<T> {
+        unsafe { self.slots.add((index % self.capacity) as usize) }
+    }
+}
+
+// Producing end of a shared-memory queue. Only one producer may be attached to a region at a
+// time, across all processes.
+pub struct ShmProducer<T: Copy> {
+    region: Region<T>,
+}
+
+// The region is only accessed through atomics and slots the producer exclusively owns.
+unsafe impl<T: Copy + Send> Send for ShmProducer<T> {}
+
+impl<T: Copy> ShmProducer<T> {
+    /// Attach the producing end of the queue in a region formatted by `init()`. Fails with
+    /// `QueueError::Io(io::ErrorKind::InvalidData)` if the region is not formatted or its header
+    /// claims no records or more than fit in `len` bytes, or with `QueueError::TypeMismatch` if
+    /// it was formatted for records of another size.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to the start of the region of `len` bytes, valid for as long as the
+    /// producer is attached, and no other producer may be attached to the same region.
+    pub unsafe fn attach(ptr: *mut u8, len: usize) -> Result<Self, QueueError> {
+        Ok(ShmProducer {
+            region: Region::attach(ptr, len)?,
+        })
+    }
+
+    /// Push a record. Fails if queue is full.
+    pub fn push(&mut self, input: T) -> Result<(), QueueError> {
+        let header = self.region.header();
+        let tail = header.tail.0.load(Ordering::Relaxed);
+        if tail.wrapping_sub(header.head.0.load(Ordering::Acquire)) == self.region.capacity {
+            return Err(QueueError::QueueFull);
+        }
+
+        unsafe { (*self.region.slot(tail)).write(input) };
+        header.tail.0.store(tail.wrapping_add(1), Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Check if the queue is full.
+    pub fn is_full(&self) -> bool {
+        self.region.size() as u64 == self.region.capacity
+    }
+
+    /// Get the number of records in the queue.
+    pub fn size(&self) -> usize {
+        self.region.size()
+    }
+
+    /// Get the maximum number of records the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.region.capacity as usize
+    }
+}
+
+// Consuming end of a shared-memory queue. Only one consumer may be attached to a region at a
+// time, across all processes.
+pub struct ShmConsumer<T: Copy> {
+    region: Region<T>,
+}
+
+// The region is only accessed through atomics and slots the consumer exclusively owns.
+unsafe impl<T: Copy + Send> Send for ShmConsumer<T> {}
+
+impl<T: Copy> ShmConsumer<T> {
+    /// Attach the consuming end of the queue in a region formatted by `init()`. Fails with
+    /// `QueueError::Io(io::ErrorKind::InvalidData)` if the region is not formatted or its header
+    /// claims no records or more than fit in `len` bytes, or with `QueueError::TypeMismatch` if
+    /// it was formatted for records of another size.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to the start of the region of `len` bytes, valid for as long as the
+    /// consumer is attached, and no other consumer may be attached to the same region. Records
+    /// pushed by the producer must be valid values of type `T`.
+    pub unsafe fn attach(ptr: *mut u8, len: usize) -> Result<Self, QueueError> {
+        Ok(ShmConsumer {
+            region: Region::attach(ptr, len)?,
+        })
+    }
+
+    /// Pop the oldest record. Fails if queue is empty.
+    pub fn pop(&mut self) -> Result<T, QueueError> {
+        let header = self.region.header();
+        let head = header.head.0.load(Ordering::Relaxed);
+        if head == header.tail.0.load(Ordering::Acquire) {
+            return Err(QueueError::QueueEmpty);
+        }
+
+        let value = unsafe { (*self.region.slot(head)).assume_init() };
+        header.head.0.store(head.wrapping_add(1), Ordering::Release);
+
+        Ok(value)
+    }
+
+    /// Check if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.region.size() == 0
+    }
+
+    /// Get the number of records in the queue.
+    pub fn size(&self) -> usize {
+        self.region.size()
+    }
+
+    /// Get the maximum number of records the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.region.capacity as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{init, region_size, ShmConsumer, ShmProducer};
+    use crate::typed_queue::QueueError;
+    use std::io;
+    use std::thread;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    // Stand-in for a shared mapping, which is page-aligned
+    #[repr(C, align(64))]
+    struct Region([u8; 1024]);
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    #[repr(C)]
+    struct Record {
+        id: u32,
+        value: f32,
+    }
+
+    #[test]
+    fn layout() {
+        assert_eq!(region_size::<u64>(SIZE), 192 + SIZE * 8);
+
+        let mut region = Box::new(Region([0; 1024]));
+        let ptr = region.0.as_mut_ptr();
+        unsafe {
+            assert_eq!(
+                ShmConsumer::<u64>::attach(ptr, 1024).err(),
+                Some(QueueError::Io(io::ErrorKind::InvalidData))
+            );
+            assert_eq!(
+                init::<u64>(ptr.add(8), 1016).err(),
+                Some(QueueError::Io(io::ErrorKind::InvalidInput))
+            );
+            assert_eq!(
+                init::<u64>(ptr, 192).err(),
+                Some(QueueError::Io(io::ErrorKind::InvalidInput))
+            );
+            assert_eq!(init::<u64>(ptr, region_size::<u64>(SIZE)), Ok(SIZE));
+            assert_eq!(
+                ShmConsumer::<u32>::attach(ptr, 1024).err(),
+                Some(QueueError::TypeMismatch)
+            );
+        }
+
+        // The header is readable by other languages or tools at the documented offsets
+        assert_eq!(&region.0[..8], b"RQSHMQ01");
+        assert_eq!(region.0[8..16], 8u64.to_ne_bytes());
+        assert_eq!(region.0[16..24], (SIZE as u64).to_ne_bytes());
+    }
+
+    #[test]
+    fn corrupt_capacity() {
+        let mut region = Box::new(Region([0; 1024]));
+        let ptr = region.0.as_mut_ptr();
+        let len = region_size::<u64>(SIZE);
+        assert_eq!(unsafe { init::<u64>(ptr, len) }, Ok(SIZE));
+
+        // A capacity overrunning the region, or of zero, as another process might have written
+        for capacity in [SIZE as u64 + 1, u64::MAX, 0] {
+            region.0[16..24].copy_from_slice(&capacity.to_ne_bytes());
+            unsafe {
+                assert_eq!(
+                    ShmProducer::<u64>::attach(ptr, len).err(),
+                    Some(QueueError::Io(io::ErrorKind::InvalidData))
+                );
+                assert_eq!(
+                    ShmConsumer::<u64>::attach(ptr, len).err(),
+                    Some(QueueError::Io(io::ErrorKind::InvalidData))
+                );
+            }
+        }
+
+        // A region too short for the header is rejected before it is read
+        assert_eq!(
+            unsafe { ShmConsumer::<u64>::attach(ptr, 64) }.err(),
+            Some(QueueError::Io(io::ErrorKind::InvalidInput))
+        );
+    }
+
+    #[test]
+    fn push_pop() {
+        let mut region = Box::new(Region([0; 1024]));
+        let ptr = region.0.as_mut_ptr();
+        let len = region_size::<Record>(SIZE);
+        let (mut producer, mut consumer) = unsafe {
+            assert_eq!(init::<Record>(ptr, len), Ok(SIZE));
+            (
+                ShmProducer::<Record>::attach(ptr, len).unwrap(),
+                ShmConsumer::<Record>::attach(ptr, len).unwrap(),
+            )
+        };
+
+        assert_eq!(consumer.pop(), Err(QueueError::QueueEmpty));
+        for id in 0..SIZE as u32 {
+            assert!(producer.push(Record { id, value: 0.5 }).is_ok());
+        }
+        assert!(producer.is_full());
+        assert_eq!(
+            producer.push(Record { id: 0, value: 0.0 }),
+            Err(QueueError::QueueFull)
+        );
+        assert_eq!(consumer.size(), SIZE);
+        assert_eq!(consumer.pop(), Ok(Record { id: 0, value: 0.5 }));
+        assert_eq!(producer.size(), SIZE - 1);
+    }
+
+    #[test]
+    fn separate_attachments() {
+        const COUNT: u32 = 10_000;
+        let mut region = Box::new(Region([0; 1024]));
+        let len = region_size::<u32>(SIZE);
+        // Raw pointers are not `Send`, so each side gets the address, as another process would
+        let address = region.0.as_mut_ptr() as usize;
+        assert_eq!(unsafe { init::<u32>(address as *mut u8, len) }, Ok(SIZE));
+
+        thread::scope(|scope| {
+            scope.spawn(move || {
+                let mut producer =
+                    unsafe { ShmProducer::<u32>::attach(address as *mut u8, len) }.unwrap();
+                for n in 0..COUNT {
+                    while producer.push(n).is_err() {
+                        thread::yield_now();
+                    }
+                }
+            });
+
+            let mut consumer =
+                unsafe { ShmConsumer::<u32>::attach(address as *mut u8, len) }.unwrap();
+            for n in 0..COUNT {
+                loop {
+                    match consumer.pop() {
+                        Ok(value) => break assert_eq!(value, n),
+                        Err(_) => thread::yield_now(),
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,267 @@
+use std::array;
+use std::mem::MaybeUninit;
+
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Marks the end of a slot list.
+const NONE: usize = usize::MAX;
+
+// Queue where each element carries a small priority. Pop returns the highest-priority element,
+// breaking ties in FIFO order. All elements share a single buffer, and each priority level only
+// keeps the first and last slot of a linked list threaded through the buffer, so memory does not
+// grow with the number of levels beyond two indices per level. Not thread-safe.
+pub struct StablePriorityQueue<T: Copy, const CAPACITY: usize, const LEVELS: usize> {
+    size: usize,
+    buffer: [MaybeUninit<T>; CAPACITY],
+    next: [usize; CAPACITY], // next slot in the same level list, or in the free list
+    heads: [usize; LEVELS],
+    tails: [usize; LEVELS],
+    free: usize,
+}
+
+impl<T: Copy, const CAPACITY: usize, const LEVELS: usize> StablePriorityQueue<T, CAPACITY, LEVELS> {
+    // Evaluated by `new()`, so that a queue without priority levels fails to build.
+    const HAS_LEVELS: () = assert!(LEVELS > 0, "priority queue must have at least one level");
+
+    /// Create a new queue for the specified type, capacity and number of priority levels. Fails
+    /// to build if `LEVELS` is zero.
+    pub fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::HAS_LEVELS;
+
+        StablePriorityQueue {
+            size: 0,
+            buffer: [MaybeUninit::uninit(); CAPACITY],
+            next: array::from_fn(|n| if n + 1 < CAPACITY { n + 1 } else { NONE }),
+            heads: [NONE; LEVELS],
+            tails: [NONE; LEVELS],
+            free: if CAPACITY > 0 { 0 } else { NONE },
+        }
+    }
+
+    /// Push an element with the given priority. Higher values are popped first, and priorities
+    /// at or above `LEVELS` are treated as the highest level. Fails if queue is full.
+    pub fn push_with_priority(&mut self, input: T, priority: u8) -> Result<(), QueueError> {
+        if self.is_full() {
+            return Err(QueueError::QueueFull);
+        }
+
+        let level = self.level(priority);
+        let slot = self.free;
+        self.free = self.next[slot];
+
+        self.buffer[slot].write(input);
+        self.next[slot] = NONE;
+        match self.tails[level] {
+            NONE => self.heads[level] = slot,
+            tail => self.next[tail] = slot,
+        }
+        self.tails[level] = slot;
+        self.size += 1;
+
+        Ok(())
+    }
+
+    /// Pop the highest-priority element along with its priority. Fails if queue is empty.
+    pub fn pop_with_priority(&mut self) -> Result<(T, u8), QueueError> {
+        let level = self.highest_level().ok_or(QueueError::QueueEmpty)?;
+        let slot = self.unlink_front(level);
+        Ok((unsafe { self.buffer[slot].assume_init() }, level as u8))
+    }
+
+    /// Try to get an immutable reference to the element that would be popped next.
+    pub fn front(&self) -> Result<&T, QueueError> {
+        let level = self.highest_level().ok_or(QueueError::QueueEmpty)?;
+        Ok(unsafe { self.buffer[self.heads[level]].assume_init_ref() })
+    }
+
+    /// Get the number of elements queued at the given priority.
+    pub fn size_at(&self, priority: u8) -> usize {
+        let mut count = 0;
+        let mut slot = self.heads[self.level(priority)];
+        while slot != NONE {
+            count += 1;
+            slot = self.next[slot];
+        }
+        count
+    }
+
+    fn level(&self, priority: u8) -> usize {
+        (priority as usize).min(LEVELS - 1)
+    }
+
+    fn highest_level(&self) -> Option<usize> {
+        (0..LEVELS).rev().find(|&level| self.heads[level] != NONE)
+    }
+
+    fn lowest_level(&self) -> Option<usize> {
+        (0..LEVELS).find(|&level| self.heads[level] != NONE)
+    }
+
+    // Remove the oldest slot of a non-empty level list and return it to the free list.
+    fn unlink_front(&mut self, level: usize) -> usize {
+        let slot = self.heads[level];
+        self.heads[level] = self.next[slot];
+        if self.heads[level] == NONE {
+            self.tails[level] = NONE;
+        }
+
+        self.next[slot] = self.free;
+        self.free = slot;
+        self.size -= 1;
+        slot
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, const LEVELS: usize> Default
+    for StablePriorityQueue<T, CAPACITY, LEVELS>
+{
+    fn default() -> Self {
+        StablePriorityQueue::new()
+    }
+}
+
+// Elements pushed through the trait get the lowest priority. Overwriting pushes evict the oldest
+// element of the lowest non-empty priority.
+impl<T: Copy, const CAPACITY: usize, const LEVELS: usize> TypedQueue<T>
+    for StablePriorityQueue<T, CAPACITY, LEVELS>
+{
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_with_priority(input, 0)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref_overwrite(&input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        self.push_with_priority(*input, 0)
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        if self.is_full() {
+            if let Some(level) = self.lowest_level() {
+                self.unlink_front(level);
+            }
+        }
+
+        self.push_with_priority(*input, 0)
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        self.pop_with_priority().map(|(value, _)| value)
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        *output = self.pop()?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        *self = StablePriorityQueue::new();
+        Ok(())
+    }
+
+    fn is_full(&self) -> bool {
+        self.size() == CAPACITY
+    }
+
+    fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn capacity(&self) -> usize {
+        CAPACITY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StablePriorityQueue;
+    use crate::typed_queue::{QueueError, TypedQueue};
+
+    // Arbitrary queue size and number of levels for tests
+    const SIZE: usize = 16;
+    const LEVELS: usize = 4;
+
+    #[test]
+    fn priority_order() {
+        let mut queue = StablePriorityQueue::<u32, SIZE, LEVELS>::default();
+        assert!(queue.push_with_priority(1, 1).is_ok());
+        assert!(queue.push_with_priority(2, 3).is_ok());
+        assert!(queue.push_with_priority(3, 0).is_ok());
+        assert!(queue.push_with_priority(4, 2).is_ok());
+
+        assert_eq!(*queue.front().unwrap(), 2);
+        assert_eq!(queue.pop_with_priority().unwrap(), (2, 3));
+        assert_eq!(queue.pop_with_priority().unwrap(), (4, 2));
+        assert_eq!(queue.pop_with_priority().unwrap(), (1, 1));
+        assert_eq!(queue.pop_with_priority().unwrap(), (3, 0));
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+    }
+
+    #[test]
+    fn fifo_within_priority() {
+        let mut queue = StablePriorityQueue::<u32, SIZE, LEVELS>::default();
+        for n in 0..SIZE as u32 {
+            assert!(queue.push_with_priority(n, (n % 2) as u8).is_ok());
+        }
+        assert_eq!(queue.size_at(1), SIZE / 2);
+
+        for n in (1..SIZE as u32).step_by(2) {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        for n in (0..SIZE as u32).step_by(2) {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+    }
+
+    #[test]
+    fn clamped_priority() {
+        let mut queue = StablePriorityQueue::<u32, SIZE, LEVELS>::default();
+        assert!(queue.push_with_priority(1, LEVELS as u8 - 1).is_ok());
+        assert!(queue.push_with_priority(2, u8::MAX).is_ok());
+        assert_eq!(queue.size_at(u8::MAX), 2);
+        assert_eq!(queue.pop_with_priority().unwrap(), (1, LEVELS as u8 - 1));
+    }
+
+    #[test]
+    fn empty_full() {
+        let mut queue = StablePriorityQueue::<u32, SIZE, LEVELS>::default();
+        assert!(queue.is_empty());
+
+        for n in 0..SIZE {
+            assert!(!queue.is_full());
+            assert!(queue
+                .push_with_priority(n as u32, (n % LEVELS) as u8)
+                .is_ok());
+        }
+        assert!(queue.is_full());
+        assert_eq!(queue.push(0).unwrap_err(), QueueError::QueueFull);
+
+        // Slots are recycled through the free list
+        for _ in 0..SIZE * 2 {
+            assert!(queue.pop().is_ok());
+            assert!(queue.push_with_priority(0, 1).is_ok());
+        }
+        assert!(queue.clear().is_ok());
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+    }
+
+    #[test]
+    fn push_overwrite() {
+        let mut queue = StablePriorityQueue::<u32, 2, LEVELS>::default();
+        assert!(queue.push_with_priority(1, 2).is_ok());
+        assert!(queue.push_with_priority(2, 1).is_ok());
+
+        // Evicts the oldest element of the lowest priority
+        assert!(queue.push_overwrite(3).is_ok());
+        assert_eq!(queue.pop().unwrap(), 1);
+        assert_eq!(queue.pop().unwrap(), 3);
+    }
+}
@@ -0,0 +1,202 @@
+use std::mem::{self, MaybeUninit};
+use std::ptr;
+
+/// Backing store for the elements of a queue. Abstracting the buffer lets one queue implementation
+/// serve inline arrays, heap allocations, and caller-provided memory alike.
+///
+/// # Safety
+///
+/// Implementations must return the same memory, with the same length, from every call to
+/// `as_slice()` and `as_mut_slice()` for as long as the storage is not moved, since queues rely on
+/// previously written slots staying initialized.
+pub unsafe trait Storage<T> {
+    /// Get the slots of the storage.
+    fn as_slice(&self) -> &[MaybeUninit<T>];
+
+    /// Get the slots of the storage mutably.
+    fn as_mut_slice(&mut self) -> &mut [MaybeUninit<T>];
+
+    /// Get the number of elements the storage can hold.
+    fn capacity(&self) -> usize {
+        self.as_slice().len()
+    }
+}
+
+// Inline array, sized at compile time. The default storage for queues.
+unsafe impl<T, const CAPACITY: usize> Storage<T> for [MaybeUninit<T>; CAPACITY] {
+    fn as_slice(&self) -> &[MaybeUninit<T>] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [MaybeUninit<T>] {
+        self
+    }
+
+    fn capacity(&self) -> usize {
+        CAPACITY
+    }
+}
+
+// Heap-allocated slice, sized at runtime.
+#[cfg(feature = "alloc")]
+unsafe impl<T> Storage<T> for Box<[MaybeUninit<T>]> {
+    fn as_slice(&self) -> &[MaybeUninit<T>] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [MaybeUninit<T>] {
+        self
+    }
+}
+
+// Borrowed slice owned by the caller, such as a static buffer or a stack array.
+unsafe impl<T> Storage<T> for &mut [MaybeUninit<T>] {
+    fn as_slice(&self) -> &[MaybeUninit<T>] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [MaybeUninit<T>] {
+        self
+    }
+}
+
+/// Allocate heap storage for `capacity` elements.
+#[cfg(feature = "alloc")]
+pub fn boxed<T>(capacity: usize) -> Box<[MaybeUninit<T>]> {
+    (0..capacity).map(|_| MaybeUninit::uninit()).collect()
+}
+
+// Smallest page size in common use. Touching memory more often than once per page is harmless.
+const PAGE_SIZE: usize = 4096;
+
+/// Write to every page spanned by `slots`, so that page faults for lazily allocated memory (heap,
+/// anonymous or file-backed mappings) are taken now rather than on first use. The contents of
+/// the slots are clobbered, so they must not hold live elements.
+pub fn prefault<T>(slots: &mut [MaybeUninit<T>]) {
+    let len = mem::size_of_val(slots);
+    let bytes = slots.as_mut_ptr().cast::<u8>();
+    for offset in (0..len).step_by(PAGE_SIZE).chain(len.checked_sub(1)) {
+        // Volatile, so that writes to otherwise unused memory are not optimized out
+        unsafe { ptr::write_volatile(bytes.add(offset), 0) };
+    }
+}
+
+#[cfg(feature = "mmap")]
+pub use self::mmap::MmapStorage;
+
+#[cfg(feature = "mmap")]
+mod mmap {
+    use std::io;
+    use std::marker::PhantomData;
+    use std::mem::{size_of, MaybeUninit};
+    use std::slice;
+
+    use memmap2::{MmapMut, MmapOptions};
+
+    use super::Storage;
+
+    // Memory-mapped region, either anonymous or backed by a file.
+    pub struct MmapStorage<T> {
+        map: MmapMut,
+        capacity: usize,
+        _marker: PhantomData<T>,
+    }
+
+    impl<T> MmapStorage<T> {
+        /// Map anonymous memory large enough for `capacity` elements.
+        pub fn anonymous(capacity: usize) -> io::Result<Self> {
+            let map = MmapOptions::new()
+                .len(capacity * size_of::<T>())
+                .map_anon()?;
+            Self::from_mmap(map)
+        }
+
+        /// Use an existing mapping, holding as many elements as fit in it. Fails if the mapping
+        /// is not suitably aligned for `T`.
+        pub fn from_mmap(map: MmapMut) -> io::Result<Self> {
+            assert!(
+                size_of::<T>() > 0,
+                "zero-sized types cannot be memory-mapped"
+            );
+
+            if !map.as_ptr().cast::<T>().is_aligned() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "mapping is not aligned for the element type",
+                ));
+            }
+
+            let capacity = map.len() / size_of::<T>();
+            Ok(MmapStorage {
+                map,
+                capacity,
+                _marker: PhantomData,
+            })
+        }
+
+        /// Get the underlying mapping, e.g. to flush a file-backed region.
+        pub fn mmap(&self) -> &MmapMut {
+            &self.map
+        }
+    }
+
+    unsafe impl<T> Storage<T> for MmapStorage<T> {
+        fn as_slice(&self) -> &[MaybeUninit<T>] {
+            unsafe { slice::from_raw_parts(self.map.as_ptr().cast(), self.capacity) }
+        }
+
+        fn as_mut_slice(&mut self) -> &mut [MaybeUninit<T>] {
+            unsafe { slice::from_raw_parts_mut(self.map.as_mut_ptr().cast(), self.capacity) }
+        }
+
+        fn capacity(&self) -> usize {
+            self.capacity
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Storage;
+    use std::mem::MaybeUninit;
+
+    #[test]
+    fn array() {
+        let storage = [MaybeUninit::<u32>::uninit(); 8];
+        assert_eq!(storage.capacity(), 8);
+        assert_eq!(storage.as_slice().len(), 8);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn boxed() {
+        let mut storage = super::boxed::<u32>(5);
+        assert_eq!(storage.capacity(), 5);
+        storage.as_mut_slice()[4].write(1);
+        assert_eq!(unsafe { storage.as_slice()[4].assume_init() }, 1);
+    }
+
+    #[test]
+    fn slice() {
+        let mut buffer = [MaybeUninit::<u32>::uninit(); 3];
+        let storage: &mut [MaybeUninit<u32>] = &mut buffer;
+        assert_eq!(storage.capacity(), 3);
+    }
+
+    #[test]
+    fn prefault() {
+        let mut buffer = [MaybeUninit::<u64>::uninit(); 2048];
+        super::prefault(&mut buffer);
+        super::prefault(&mut buffer[..0]);
+        super::prefault(&mut [MaybeUninit::<()>::uninit(); 4]);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap() {
+        let mut storage = super::MmapStorage::<u64>::anonymous(512).unwrap();
+        assert_eq!(storage.capacity(), 512);
+        storage.as_mut_slice()[511].write(7);
+        assert_eq!(unsafe { storage.as_slice()[511].assume_init() }, 7);
+    }
+}
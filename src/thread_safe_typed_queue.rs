@@ -1,26 +1,140 @@
 use std::cmp::min;
 use std::fmt;
-use std::mem::MaybeUninit;
+use std::mem::{self, MaybeUninit};
+use std::ops::{Deref, Range};
+use std::ptr;
 use std::sync::{
-    atomic::{AtomicUsize, Ordering},
+    atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
     Mutex, MutexGuard,
 };
 
-use crate::typed_queue::{QueueError, TypedQueue};
+use crate::basic_typed_queue::{self, BasicTypedQueue};
+use crate::latency_probe::LatencyProbe;
+#[cfg(feature = "metrics")]
+use crate::queue_metrics::QueueMetrics;
+#[cfg(feature = "tracing")]
+use crate::queue_tracing::QueueTracer;
+pub use crate::typed_queue::QueueStats;
+use crate::typed_queue::{OverflowPolicy, QueueError, TypedQueue};
 
 // Queue data to be protected via mutex
 struct QueueData<T: Copy, const CAPACITY: usize> {
-    head: usize,
-    tail: usize,
-    buffer: [MaybeUninit<T>; CAPACITY],
+    queue: BasicTypedQueue<T, CAPACITY>,
+    probe: LatencyProbe,
+    stats: QueueStats,
+    #[cfg(feature = "metrics")]
+    metrics: Option<QueueMetrics>,
+    #[cfg(feature = "tracing")]
+    tracer: Option<QueueTracer>,
+}
+
+impl<T: Copy, const CAPACITY: usize> QueueData<T, CAPACITY> {
+    // Record elements entering and leaving the queue in both the statistics and the probe.
+    fn on_push(&mut self, count: usize) {
+        self.stats.pushed += count as u64;
+        self.probe.on_push(count);
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.on_push(count);
+        }
+        #[cfg(feature = "tracing")]
+        if let Some(tracer) = &self.tracer {
+            tracer.on_push(count, self.queue.size());
+        }
+    }
+
+    fn on_pop(&mut self, count: usize) {
+        self.stats.popped += count as u64;
+        self.probe.on_pop(count);
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.on_pop(count);
+        }
+        #[cfg(feature = "tracing")]
+        if let Some(tracer) = &self.tracer {
+            tracer.on_pop(count, self.queue.size());
+        }
+    }
+
+    fn on_discard(&mut self, count: usize) {
+        self.stats.discarded += count as u64;
+        self.probe.on_discard(count);
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.on_discard(count);
+        }
+        #[cfg(feature = "tracing")]
+        if let Some(tracer) = &self.tracer {
+            tracer.on_discard(count, self.queue.size());
+        }
+    }
+
+    // Record the oldest element being evicted to make room for a new one.
+    fn on_overwrite(&mut self) {
+        self.on_discard(1);
+        #[cfg(feature = "tracing")]
+        if let Some(tracer) = &self.tracer {
+            tracer.on_overwrite(self.queue.size());
+        }
+    }
+
+    // Record a queued element being replaced in place. The replacement counts as both pushed and
+    // discarded, but the queue length is unchanged, so the probe is not told about it.
+    fn on_replace(&mut self) {
+        self.stats.pushed += 1;
+        self.stats.discarded += 1;
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.on_push(1);
+            metrics.on_discard(1);
+        }
+        #[cfg(feature = "tracing")]
+        if let Some(tracer) = &self.tracer {
+            tracer.on_discard(1, self.queue.size());
+            tracer.on_push(1, self.queue.size());
+        }
+    }
+
+    // Record a push finding the queue full.
+    fn on_full(&self) {
+        #[cfg(feature = "tracing")]
+        if let Some(tracer) = &self.tracer {
+            tracer.on_full(self.queue.size());
+        }
+    }
+
+    // Record an element dropped on arrival, which never entered the queue.
+    fn on_reject(&mut self) {
+        self.on_full();
+        self.stats.pushed += 1;
+        self.stats.discarded += 1;
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.on_push(1);
+            metrics.on_discard(1);
+        }
+    }
+
+    // Record the new number of elements visible to consumers.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn on_resize(&self, size: usize) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.on_resize(size);
+        }
+    }
 }
 
 impl<T: Copy, const CAPACITY: usize> Default for QueueData<T, CAPACITY> {
     fn default() -> Self {
         QueueData {
-            head: 0,
-            tail: 0,
-            buffer: [MaybeUninit::uninit(); CAPACITY],
+            queue: BasicTypedQueue::new(),
+            probe: LatencyProbe::default(),
+            stats: QueueStats::default(),
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            #[cfg(feature = "tracing")]
+            tracer: None,
         }
     }
 }
@@ -28,7 +142,7 @@ impl<T: Copy, const CAPACITY: usize> Default for QueueData<T, CAPACITY> {
 // Wrapper providing immutable reference to element in container. Holds a lock until dropped.
 pub struct RefGuard<'a, T: Copy, const CAPACITY: usize> {
     guard: MutexGuard<'a, QueueData<T, CAPACITY>>,
-    index: usize,
+    index: usize, // logical index, where 0 is the front of the queue
 }
 
 impl<'a, T: Copy, const CAPACITY: usize> RefGuard<'a, T, CAPACITY> {
@@ -37,168 +151,228 @@ impl<'a, T: Copy, const CAPACITY: usize> RefGuard<'a, T, CAPACITY> {
     }
 }
 
-impl<'a, T: Copy, const CAPACITY: usize> std::ops::Deref for RefGuard<'a, T, CAPACITY> {
+impl<'a, T: Copy, const CAPACITY: usize> Deref for RefGuard<'a, T, CAPACITY> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
-        unsafe { self.guard.buffer[self.index].assume_init_ref() }
+        &self.guard.queue[self.index]
     }
 }
 
 impl<'a, T: Copy + fmt::Debug, const CAPACITY: usize> fmt::Debug for RefGuard<'a, T, CAPACITY> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Debug::fmt(&self.guard.buffer[self.index], f)
+        fmt::Debug::fmt(self.deref(), f)
     }
 }
 
-// Thread-safe typed queue struct with generic capacity.
-pub struct ThreadSafeTypedQueue<T: Copy, const CAPACITY: usize> {
-    // Size is stored as an atomic separately from protected_data so that it can be read without
-    // needing to acquire a lock. This speeds up functions like size() and related.
-    size: AtomicUsize,
-    protected_data: Mutex<QueueData<T, CAPACITY>>,
+// Reservation of space for a batch of elements. Holds a lock until dropped. Written elements only
+// become visible to consumers once the batch is published; dropping without publishing discards
+// them.
+pub struct BatchGuard<'a, T: Copy, const CAPACITY: usize> {
+    guard: MutexGuard<'a, QueueData<T, CAPACITY>>,
+    queue: &'a ThreadSafeTypedQueue<T, CAPACITY>,
+    reserved: usize,
+    written: usize,
 }
 
-impl<T: Copy, const CAPACITY: usize> ThreadSafeTypedQueue<T, CAPACITY> {
-    pub fn new() -> Self {
-        ThreadSafeTypedQueue {
-            size: AtomicUsize::default(),
-            protected_data: Mutex::new(QueueData::default()),
+impl<'a, T: Copy, const CAPACITY: usize> BatchGuard<'a, T, CAPACITY> {
+    fn new(
+        guard: MutexGuard<'a, QueueData<T, CAPACITY>>,
+        queue: &'a ThreadSafeTypedQueue<T, CAPACITY>,
+        reserved: usize,
+    ) -> Self {
+        Self {
+            guard,
+            queue,
+            reserved,
+            written: 0,
         }
     }
 
-    pub fn front(&self) -> Result<RefGuard<T, CAPACITY>, QueueError> {
-        let res = self.protected_data.lock();
-        if res.is_err() {
-            return Err(QueueError::MutexPoisoned);
-        }
+    /// Write an element into the batch by value. Fails if the reservation is exhausted.
+    pub fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref(&input)
+    }
 
-        if self.is_empty() {
-            return Err(QueueError::QueueEmpty);
+    /// Write an element into the batch by reference. Fails if the reservation is exhausted.
+    pub fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        if self.written == self.reserved {
+            return Err(QueueError::QueueFull);
         }
 
-        let guard = res.unwrap();
-        let index = guard.head;
-        Ok(RefGuard::new(guard, index))
+        self.guard.queue.write_spare(self.written, *input);
+        self.written += 1;
+
+        Ok(())
     }
 
-    pub fn back(&self) -> Result<RefGuard<T, CAPACITY>, QueueError> {
-        let res = self.protected_data.lock();
-        if res.is_err() {
-            return Err(QueueError::MutexPoisoned);
-        }
+    /// Write an element into the batch by value without checking the reservation.
+    ///
+    /// # Safety
+    ///
+    /// Fewer than the reserved number of elements must have been written. With debug assertions
+    /// enabled this is checked and a violation panics; release builds perform no check.
+    pub unsafe fn push_unchecked(&mut self, input: T) {
+        debug_assert!(
+            self.written < self.reserved,
+            "push_unchecked() called on an exhausted batch reservation"
+        );
+
+        self.guard.queue.write_spare(self.written, input);
+        self.written += 1;
+    }
 
-        if self.is_empty() {
-            return Err(QueueError::QueueEmpty);
-        }
+    /// Get the number of elements written into the batch so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Get the number of elements that can still be written into the batch.
+    pub fn remaining(&self) -> usize {
+        self.reserved - self.written
+    }
 
-        let guard = res.unwrap();
-        let index = (guard.tail + CAPACITY - 1) % CAPACITY;
-        Ok(RefGuard::new(guard, index))
+    /// Make all written elements visible to consumers at once and release the lock. If a group
+    /// is still open (see `push_unpublished()`), the elements join it instead.
+    pub fn publish(mut self) {
+        self.guard.queue.commit_spare(self.written);
+        self.guard.on_push(self.written);
+        self.queue.commit(&self.guard, self.written);
     }
 }
 
-impl<T: Copy, const CAPACITY: usize> Default for ThreadSafeTypedQueue<T, CAPACITY> {
-    fn default() -> Self {
-        ThreadSafeTypedQueue::new()
+impl<'a, T: Copy, const CAPACITY: usize> fmt::Debug for BatchGuard<'a, T, CAPACITY> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchGuard")
+            .field("reserved", &self.reserved)
+            .field("written", &self.written)
+            .finish()
     }
 }
 
-impl<T: Copy, const CAPACITY: usize> TypedQueue<T> for ThreadSafeTypedQueue<T, CAPACITY> {
-    fn push(&mut self, input: T) -> Result<(), QueueError> {
-        self.push_ref(&input)
+// Tail slot reserved by `claim()`, to be filled in place. Holds a lock until published or dropped;
+// dropping the guard without publishing leaves the queue unchanged.
+pub struct SlotGuard<'a, T: Copy, const CAPACITY: usize> {
+    guard: MutexGuard<'a, QueueData<T, CAPACITY>>,
+    queue: &'a ThreadSafeTypedQueue<T, CAPACITY>,
+}
+
+impl<'a, T: Copy, const CAPACITY: usize> SlotGuard<'a, T, CAPACITY> {
+    /// Get the reserved slot, to be initialized in place.
+    pub fn as_uninit_mut(&mut self) -> &mut MaybeUninit<T> {
+        self.guard.queue.spare_mut(0)
     }
 
-    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
-        self.push_ref_overwrite(&input)
+    /// Get a raw pointer to the reserved slot.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.as_uninit_mut().as_mut_ptr()
     }
 
-    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
-        match self.protected_data.lock() {
-            Ok(mut guard) => {
-                if self.is_full() {
-                    return Err(QueueError::QueueFull);
-                }
+    /// Write an element into the slot, make it visible to consumers and release the lock.
+    pub fn write(mut self, input: T) {
+        self.as_uninit_mut().write(input);
+        unsafe { self.publish() }
+    }
+
+    /// Make the element in the slot visible to consumers and release the lock. If a group is
+    /// still open (see `push_unpublished()`), the element joins it instead.
+    ///
+    /// # Safety
+    ///
+    /// The slot must have been fully initialized.
+    pub unsafe fn publish(mut self) {
+        self.guard.queue.commit_spare(1);
+        self.guard.on_push(1);
+        self.queue.commit(&self.guard, 1);
+    }
+}
+
+impl<'a, T: Copy, const CAPACITY: usize> fmt::Debug for SlotGuard<'a, T, CAPACITY> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlotGuard").finish_non_exhaustive()
+    }
+}
 
-                let tail = guard.tail;
+// View of a locked queue passed to `transaction()`, so that several operations can be combined
+// atomically. Implements `TypedQueue` with the same semantics as the queue itself, except that
+// nothing blocks on the lock, which is already held.
+pub struct BasicView<'a, T: Copy, const CAPACITY: usize> {
+    data: &'a mut QueueData<T, CAPACITY>,
+    queue: &'a ThreadSafeTypedQueue<T, CAPACITY>,
+}
 
-                unsafe {
-                    *(guard.buffer[tail].as_mut_ptr()) = *input;
-                }
+impl<'a, T: Copy, const CAPACITY: usize> BasicView<'a, T, CAPACITY> {
+    /// Try to get a reference to the oldest element in the queue.
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
 
-                guard.tail = (guard.tail + 1) % CAPACITY;
-                self.size.fetch_add(1, Ordering::Relaxed);
+    /// Try to get a reference to the newest element visible to consumers.
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.size().checked_sub(1)?)
+    }
 
-                Ok(())
-            }
-            Err(..) => Err(QueueError::MutexPoisoned),
+    /// Try to get a reference to the element at logical index `index`, where index 0 is the
+    /// front of the queue. Returns `None` if out of bounds.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.size() {
+            self.data.queue.get(index)
+        } else {
+            None
         }
     }
+}
 
-    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
-        match self.protected_data.lock() {
-            Ok(mut guard) => {
-                let tail = guard.tail;
-                unsafe {
-                    *(guard.buffer[tail].as_mut_ptr()) = *input;
-                }
+impl<'a, T: Copy, const CAPACITY: usize> TypedQueue<T> for BasicView<'a, T, CAPACITY> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref(&input)
+    }
 
-                guard.tail = (guard.tail + 1) % CAPACITY;
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref_overwrite(&input)
+    }
 
-                // This size update is done in multiple steps, but is safe due to being in the
-                // scope of where we're holding the mutex on the other protected data.
-                let new_size = min(self.size.load(Ordering::Relaxed) + 1, CAPACITY);
-                self.size.store(new_size, Ordering::Relaxed);
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        self.queue.push_locked(self.data, input)
+    }
 
-                Ok(())
-            }
-            Err(..) => Err(QueueError::MutexPoisoned),
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        if self.queue.is_closed() {
+            return Err(QueueError::Closed);
         }
+
+        self.queue.push_overwrite_locked(self.data, input)
     }
 
     fn pop(&mut self) -> Result<T, QueueError> {
         let mut value = MaybeUninit::<T>::uninit();
-        // We can safely pass the uninit value into `pop_ref()` because we know `pop_ref()` will
-        // not read the value, only copy into it. If `pop_ref()` fails, we never access `value`.
-        unsafe {
-            match self.pop_ref(value.assume_init_mut()) {
-                Ok(()) => Ok(value.assume_init()),
-                Err(e) => Err(e),
-            }
-        }
+        self.pop_into_uninit(&mut value)?;
+        // `pop_into_uninit()` only succeeds after writing the value
+        Ok(unsafe { value.assume_init() })
     }
 
     fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
-        match self.protected_data.lock() {
-            Ok(mut guard) => {
-                if self.is_empty() {
-                    return Err(QueueError::QueueEmpty);
-                }
-
-                let head = guard.head;
-                *output = unsafe { *(guard.buffer[head].as_mut_ptr()) };
-                guard.head = (guard.head + 1) % CAPACITY;
-                self.size.fetch_sub(1, Ordering::Relaxed);
+        *output = self.pop()?;
+        Ok(())
+    }
 
-                Ok(())
-            }
-            Err(..) => Err(QueueError::MutexPoisoned),
-        }
+    fn pop_into_uninit(&mut self, output: &mut MaybeUninit<T>) -> Result<(), QueueError> {
+        self.queue.pop_locked(self.data, output)
     }
 
-    // There's no value in protecting the functions below, as the calling thread could be
-    // pre-empted by another thread that changes the state of the queue immediately after exiting
-    // any of these functions and dropping the would-be lock.
+    fn clear(&mut self) -> Result<(), QueueError> {
+        self.queue.clear_locked(self.data)
+    }
 
     fn is_full(&self) -> bool {
-        self.size() == CAPACITY
+        self.queue.is_full()
     }
 
     fn is_empty(&self) -> bool {
-        self.size() == 0
+        self.queue.is_empty()
     }
 
     fn size(&self) -> usize {
-        self.size.load(Ordering::Relaxed)
+        self.queue.size()
     }
 
     fn capacity(&self) -> usize {
@@ -206,122 +380,2012 @@ impl<T: Copy, const CAPACITY: usize> TypedQueue<T> for ThreadSafeTypedQueue<T, C
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::ThreadSafeTypedQueue;
-    use crate::typed_queue::{QueueError, TypedQueue};
+// Lock held while iterating over the elements visible to consumers, so that monitoring code sees
+// a consistent view of the queue. The queue cannot change until the guard is dropped.
+pub struct IterGuard<'a, T: Copy, const CAPACITY: usize> {
+    guard: MutexGuard<'a, QueueData<T, CAPACITY>>,
+    size: usize,
+}
 
-    // Arbitrary queue size for tests
-    const SIZE: usize = 16;
+impl<'a, T: Copy, const CAPACITY: usize> IterGuard<'a, T, CAPACITY> {
+    /// Iterate over the visible elements in FIFO order.
+    pub fn iter(&self) -> basic_typed_queue::Iter<'_, T> {
+        self.guard.queue.iter_first(self.size)
+    }
 
-    #[test]
-    fn push_pop() {
-        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+    /// Get the number of visible elements.
+    pub fn len(&self) -> usize {
+        self.size
+    }
 
-        for n in 0..SIZE {
-            assert!(queue.push(n as u32).is_ok())
-        }
+    /// Check if there are no visible elements.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
 
-        for n in 0..SIZE {
-            let output = queue.pop();
-            assert!(output.is_ok());
-            assert_eq!(output.unwrap(), n as u32);
-        }
+impl<'g, 'a, T: Copy, const CAPACITY: usize> IntoIterator for &'g IterGuard<'a, T, CAPACITY> {
+    type Item = &'g T;
+    type IntoIter = basic_typed_queue::Iter<'g, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
+}
 
-    #[test]
-    fn push_overwrite_pop() {
-        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+impl<'a, T: Copy + fmt::Debug, const CAPACITY: usize> fmt::Debug for IterGuard<'a, T, CAPACITY> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
 
-        for n in 0..SIZE {
-            assert!(queue.push(n as u32).is_ok())
+// Thread-safe typed queue struct with generic capacity.
+pub struct ThreadSafeTypedQueue<T: Copy, const CAPACITY: usize> {
+    // Size is stored as an atomic separately from protected_data so that it can be read without
+    // needing to acquire a lock. This speeds up functions like size() and related.
+    size: AtomicUsize,
+    // Number of elements pushed after the last group boundary, which are not yet visible to
+    // consumers. Only modified while holding the lock, like size.
+    unpublished: AtomicUsize,
+    // Maximum number of elements moved by a bulk operation before the lock is released and
+    // reacquired, giving other threads a chance to run. Zero holds the lock for the whole batch.
+    yield_interval: AtomicUsize,
+    // Advisory limit below the hard capacity, for backpressure.
+    soft_capacity: AtomicUsize,
+    // `OverflowPolicy` applied by push() and push_ref(), stored as its discriminant.
+    overflow_policy: AtomicU8,
+    // Set once the queue is closed to producers. Only modified while holding the lock.
+    closed: AtomicBool,
+    protected_data: Mutex<QueueData<T, CAPACITY>>,
+}
+
+impl<T: Copy, const CAPACITY: usize> ThreadSafeTypedQueue<T, CAPACITY> {
+    pub fn new() -> Self {
+        ThreadSafeTypedQueue {
+            size: AtomicUsize::default(),
+            unpublished: AtomicUsize::default(),
+            yield_interval: AtomicUsize::default(),
+            soft_capacity: AtomicUsize::new(CAPACITY),
+            overflow_policy: AtomicU8::new(OverflowPolicy::Reject as u8),
+            closed: AtomicBool::new(false),
+            protected_data: Mutex::new(QueueData::default()),
         }
+    }
 
-        // Overwrite oldest element (0) with SIZE
-        assert!(queue.push_overwrite(SIZE as u32).is_ok());
+    pub fn front(&self) -> Result<RefGuard<'_, T, CAPACITY>, QueueError> {
+        let res = self.protected_data.lock();
+        if res.is_err() {
+            return Err(QueueError::MutexPoisoned);
+        }
 
-        let output = queue.pop();
-        assert!(output.is_ok());
-        assert_eq!(output.unwrap(), SIZE as u32);
-    }
+        if self.is_empty() {
+            return Err(QueueError::QueueEmpty);
+        }
 
-    #[test]
-    fn push_ref_pop_ref() {
-        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        Ok(RefGuard::new(res.unwrap(), 0))
+    }
 
-        for n in 0..SIZE {
-            assert!(queue.push_ref(&(n as u32)).is_ok())
+    pub fn back(&self) -> Result<RefGuard<'_, T, CAPACITY>, QueueError> {
+        let res = self.protected_data.lock();
+        if res.is_err() {
+            return Err(QueueError::MutexPoisoned);
         }
 
-        for n in 0..SIZE {
-            let mut output: u32 = 0;
-            let res = queue.pop_ref(&mut output);
-            assert!(res.is_ok());
-            assert_eq!(output, n as u32);
+        if self.is_empty() {
+            return Err(QueueError::QueueEmpty);
         }
+
+        Ok(RefGuard::new(res.unwrap(), self.size() - 1))
     }
 
-    #[test]
-    fn push_ref_overwrite_pop() {
-        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+    /// Try to get a guarded reference to the `n`-th element from the front of the queue, where
+    /// `n` = 0 is the oldest element. Fails if the queue holds `n` or fewer elements.
+    pub fn peek_at(&self, n: usize) -> Result<RefGuard<'_, T, CAPACITY>, QueueError> {
+        let res = self.protected_data.lock();
+        if res.is_err() {
+            return Err(QueueError::MutexPoisoned);
+        }
 
-        for n in 0..SIZE {
-            assert!(queue.push_ref(&(n as u32)).is_ok())
+        if n >= self.size() {
+            return Err(QueueError::QueueEmpty);
         }
 
-        // Overwrite oldest element (0) with SIZE
-        assert!(queue.push_ref_overwrite(&(SIZE as u32)).is_ok());
+        Ok(RefGuard::new(res.unwrap(), n))
+    }
 
-        let mut output: u32 = 0;
-        let res = queue.pop_ref(&mut output);
-        assert!(res.is_ok());
-        assert_eq!(output, SIZE as u32);
+    /// Lock the queue and return a guard for iterating over the elements visible to consumers in
+    /// FIFO order. Other threads block on the queue until the guard is dropped.
+    pub fn iter(&self) -> Result<IterGuard<'_, T, CAPACITY>, QueueError> {
+        let guard = self.protected_data.lock()?;
+        Ok(IterGuard {
+            guard,
+            size: self.size(),
+        })
     }
 
-    #[test]
-    fn wrap() {
-        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+    /// Visit every element visible to consumers in FIFO order during a single critical section,
+    /// without copying them out. `f` should be quick, as other threads block on the queue until
+    /// it has seen every element.
+    pub fn for_each<F: FnMut(&T)>(&self, f: F) -> Result<(), QueueError> {
+        self.iter()?.iter().for_each(f);
+        Ok(())
+    }
 
-        // push/pop half capacity to move head/tail to SIZE/2
-        for n in 0..SIZE / 2 {
-            assert!(queue.push(n as u32).is_ok());
-        }
+    /// Copy the elements visible to consumers into a new `Vec` in FIFO order, under a single lock
+    /// acquisition. The lock is released before returning, so the copy can be formatted or
+    /// inspected at leisure.
+    #[cfg(feature = "alloc")]
+    pub fn snapshot(&self) -> Result<Vec<T>, QueueError> {
+        Ok(self.iter()?.iter().copied().collect())
+    }
 
-        for _ in 0..SIZE / 2 {
-            assert!(queue.pop().is_ok());
-        }
+    /// Copy the oldest elements visible to consumers into `output` in FIFO order, under a single
+    /// lock acquisition, until `output` is filled. Returns the number of elements copied.
+    pub fn snapshot_into(&self, output: &mut [T]) -> Result<usize, QueueError> {
+        let guard = self.protected_data.lock()?;
+        let count = min(output.len(), self.size());
+        let (first, second) = guard.queue.as_slices();
+        let head = min(count, first.len());
+        output[..head].copy_from_slice(&first[..head]);
+        output[head..count].copy_from_slice(&second[..count - head]);
+
+        Ok(count)
+    }
 
-        // Now perform full push/pop check to verify wrap logic
-        for n in 0..SIZE {
-            assert!(queue.push(n as u32).is_ok())
+    /// Run `f` on the oldest element under the lock, releasing it as soon as `f` returns, and
+    /// return its result. Fails if queue is empty.
+    pub fn with_front<R, F: FnOnce(&T) -> R>(&self, f: F) -> Result<R, QueueError> {
+        let guard = self.protected_data.lock()?;
+        if self.is_empty() {
+            return Err(QueueError::QueueEmpty);
         }
 
-        for n in 0..SIZE {
-            let output = queue.pop();
-            assert!(output.is_ok());
-            assert_eq!(output.unwrap(), n as u32);
+        Ok(f(&guard.queue[0]))
+    }
+
+    /// Run `f` on the newest element visible to consumers under the lock, releasing it as soon
+    /// as `f` returns, and return its result. Fails if queue is empty.
+    pub fn with_back<R, F: FnOnce(&T) -> R>(&self, f: F) -> Result<R, QueueError> {
+        let guard = self.protected_data.lock()?;
+        if self.is_empty() {
+            return Err(QueueError::QueueEmpty);
         }
+
+        Ok(f(&guard.queue[self.size() - 1]))
     }
 
-    #[test]
-    fn front_back() {
-        // TODO: Test for thread-safe front/back impl
-        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
-        assert_eq!(queue.front().unwrap_err(), QueueError::QueueEmpty);
-        assert_eq!(queue.back().unwrap_err(), QueueError::QueueEmpty);
+    /// Run `f` with exclusive access to the queue through a view, acquiring the lock once for
+    /// all the operations `f` performs, and return its result. Compound operations such as
+    /// popping two elements and pushing their sum are thus atomic with respect to other threads.
+    pub fn transaction<R, F>(&self, f: F) -> Result<R, QueueError>
+    where
+        F: FnOnce(&mut BasicView<'_, T, CAPACITY>) -> R,
+    {
+        let mut guard = self.protected_data.lock()?;
+        let mut view = BasicView {
+            data: &mut guard,
+            queue: self,
+        };
+
+        Ok(f(&mut view))
+    }
 
-        const VALUE: u32 = 123;
-        assert!(queue.push(VALUE).is_ok());
+    /// Find the logical index of the first element, from the front, matching `predicate`.
+    pub fn position<P: FnMut(&T) -> bool>(
+        &self,
+        predicate: P,
+    ) -> Result<Option<usize>, QueueError> {
+        let guard = self.protected_data.lock()?;
+
+        // Only consider elements visible to consumers
+        Ok(guard
+            .queue
+            .position(predicate)
+            .filter(|&index| index < self.size()))
+    }
 
-        assert_eq!(*queue.front().unwrap(), VALUE);
-        assert_eq!(*queue.back().unwrap(), VALUE);
+    /// Push an element unless an element with the same key, as computed by `key_fn`, is already
+    /// queued (published or not), e.g. so that retried work items are not queued twice. Returns
+    /// whether the element was pushed. Fails if queue is full and the element would have been
+    /// pushed.
+    pub fn push_unique_by<K: PartialEq, F: Fn(&T) -> K>(
+        &self,
+        key_fn: F,
+        input: T,
+    ) -> Result<bool, QueueError> {
+        let mut guard = self.protected_data.lock()?;
+        if self.is_closed() {
+            return Err(QueueError::Closed);
+        }
+
+        let key = key_fn(&input);
+        if guard
+            .queue
+            .position(|element| key_fn(element) == key)
+            .is_some()
+        {
+            return Ok(false);
+        }
+
+        guard.queue.push_ref(&input)?;
+        guard.on_push(1);
+        self.commit(&guard, 1);
+
+        Ok(true)
+    }
+
+    /// Push an element, or replace in place the queued element (published or not) with the same
+    /// key, as computed by `key_fn`. Returns the replaced element, if any; replacements count as
+    /// both pushed and discarded. Fails if queue is full and no element was replaced.
+    pub fn push_or_replace_by<K: PartialEq, F: Fn(&T) -> K>(
+        &self,
+        key_fn: F,
+        input: T,
+    ) -> Result<Option<T>, QueueError> {
+        let mut guard = self.protected_data.lock()?;
+        if self.is_closed() {
+            return Err(QueueError::Closed);
+        }
+
+        let key = key_fn(&input);
+        if let Some(index) = guard.queue.position(|element| key_fn(element) == key) {
+            if let Some(element) = guard.queue.get_mut(index) {
+                let replaced = mem::replace(element, input);
+                guard.on_replace();
+                return Ok(Some(replaced));
+            }
+        }
+
+        guard.queue.push_ref(&input)?;
+        guard.on_push(1);
+        self.commit(&guard, 1);
+
+        Ok(None)
+    }
+
+    /// Reserve space for `n` elements under a single lock. The elements written through the
+    /// returned guard are pushed atomically when it is published. Fails if there is not enough
+    /// free space for the whole batch.
+    pub fn reserve(&self, n: usize) -> Result<BatchGuard<'_, T, CAPACITY>, QueueError> {
+        let res = self.protected_data.lock();
+        if res.is_err() {
+            return Err(QueueError::MutexPoisoned);
+        }
+
+        if self.is_closed() {
+            return Err(QueueError::Closed);
+        }
+
+        if n > CAPACITY - self.occupied() {
+            return Err(QueueError::QueueFull);
+        }
+
+        Ok(BatchGuard::new(res.unwrap(), self, n))
+    }
+
+    /// Reserve the tail slot under the lock so that a producer can fill it in place, e.g. by
+    /// deserializing into it. The element becomes visible once the returned guard is published.
+    /// Fails if queue is full.
+    pub fn claim(&self) -> Result<SlotGuard<'_, T, CAPACITY>, QueueError> {
+        let guard = self.protected_data.lock()?;
+        if self.is_closed() {
+            return Err(QueueError::Closed);
+        }
+
+        if self.occupied() == CAPACITY {
+            return Err(QueueError::QueueFull);
+        }
+
+        Ok(SlotGuard { guard, queue: self })
+    }
+
+    /// Push an element by value without making it visible to consumers, opening a group if one
+    /// is not already open. Elements pushed by any producer while a group is open stay hidden
+    /// until `publish_after()` is called. Fails if queue is full.
+    pub fn push_unpublished(&self, input: T) -> Result<(), QueueError> {
+        match self.protected_data.lock() {
+            Ok(mut guard) => {
+                if self.is_closed() {
+                    return Err(QueueError::Closed);
+                }
+
+                guard.queue.push(input)?;
+                guard.on_push(1);
+                self.unpublished.fetch_add(1, Ordering::Relaxed);
+
+                Ok(())
+            }
+            Err(..) => Err(QueueError::MutexPoisoned),
+        }
+    }
+
+    /// Mark a group boundary after the newest element, making every element pushed so far
+    /// visible to consumers at once. Consumers never pop past the last boundary.
+    pub fn publish_after(&self) -> Result<(), QueueError> {
+        match self.protected_data.lock() {
+            Ok(guard) => {
+                let unpublished = self.unpublished.swap(0, Ordering::Relaxed);
+                self.size.fetch_add(unpublished, Ordering::Relaxed);
+                guard.on_resize(self.size());
+
+                Ok(())
+            }
+            Err(..) => Err(QueueError::MutexPoisoned),
+        }
+    }
+
+    /// Set how many elements bulk operations (`push_slice()`, `pop_slice()` and `drain()`) move
+    /// before briefly releasing the lock, so that latency-sensitive peers are not blocked for the
+    /// whole transfer. Zero (the default) holds the lock for the whole operation.
+    pub fn set_yield_interval(&self, interval: usize) {
+        self.yield_interval.store(interval, Ordering::Relaxed);
+    }
+
+    /// Get the number of elements bulk operations move before briefly releasing the lock.
+    pub fn yield_interval(&self) -> usize {
+        self.yield_interval.load(Ordering::Relaxed)
+    }
+
+    /// Push as many elements from `input` as fit, in order. Returns the number of elements
+    /// pushed.
+    pub fn push_slice(&self, input: &[T]) -> Result<usize, QueueError> {
+        let chunk = self.chunk_len();
+        let mut pushed = 0;
+
+        while pushed < input.len() {
+            let mut guard = self.protected_data.lock()?;
+            if self.is_closed() {
+                return Err(QueueError::Closed);
+            }
+
+            let count = min(min(chunk, input.len() - pushed), CAPACITY - self.occupied());
+            if count == 0 {
+                break;
+            }
+
+            guard.queue.push_slice(&input[pushed..pushed + count]);
+
+            guard.on_push(count);
+            self.commit(&guard, count);
+            pushed += count;
+        }
+
+        Ok(pushed)
+    }
+
+    /// Pop elements into `output` until it is filled or the queue is empty. Returns the number of
+    /// elements popped.
+    pub fn pop_slice(&self, output: &mut [T]) -> Result<usize, QueueError> {
+        self.pop_chunks(output.len(), |queue, range| {
+            queue.pop_slice(&mut output[range]);
+        })
+    }
+
+    /// Pop up to `max` elements into `output`, e.g. a `Vec`, taking the lock once per chunk of
+    /// `yield_interval()` elements rather than once per element. Returns the number of elements
+    /// popped.
+    pub fn pop_into<E: Extend<T>>(&self, output: &mut E, max: usize) -> Result<usize, QueueError> {
+        self.pop_chunks(max, |queue, range| {
+            output.extend(range.map(|_| unsafe { queue.pop_unchecked() }))
+        })
+    }
+
+    /// Pop every element, passing each to `f` in FIFO order, until the queue is observed empty.
+    /// Returns the number of elements drained.
+    pub fn drain<F: FnMut(T)>(&self, mut f: F) -> Result<usize, QueueError> {
+        self.pop_chunks(usize::MAX, |queue, range| {
+            for _ in range {
+                f(unsafe { queue.pop_unchecked() });
+            }
+        })
+    }
+
+    // Pop up to `max` elements, releasing the lock every `yield_interval` elements. Under each
+    // lock, `pop` must pop exactly as many elements as the range it is given holds; the range
+    // counts the elements popped so far, so that they can be block-copied into place.
+    fn pop_chunks<F>(&self, max: usize, mut pop: F) -> Result<usize, QueueError>
+    where
+        F: FnMut(&mut BasicTypedQueue<T, CAPACITY>, Range<usize>),
+    {
+        let chunk = self.chunk_len();
+        let mut popped = 0;
+
+        while popped < max {
+            let mut guard = self.protected_data.lock()?;
+
+            let count = min(min(chunk, max - popped), self.size());
+            if count == 0 {
+                break;
+            }
+
+            pop(&mut guard.queue, popped..popped + count);
+
+            guard.on_pop(count);
+            self.size.fetch_sub(count, Ordering::Relaxed);
+            guard.on_resize(self.size());
+            popped += count;
+        }
+
+        Ok(popped)
+    }
+
+    // Number of elements a bulk operation may move under a single lock.
+    fn chunk_len(&self) -> usize {
+        match self.yield_interval() {
+            0 => usize::MAX,
+            interval => interval,
+        }
+    }
+
+    /// Start measuring push-to-pop handoff latency by marking every `interval`-th pushed element.
+    /// An interval of zero stops measuring. Any previously recorded samples are discarded, and
+    /// elements already queued are never sampled, as their push times are unknown.
+    pub fn enable_latency_probe(&self, interval: usize) -> Result<(), QueueError> {
+        let mut guard = self.protected_data.lock()?;
+        guard.probe = LatencyProbe::new(interval);
+        let queued = guard.queue.size();
+        guard.probe.restart(queued);
+        Ok(())
+    }
+
+    /// Start reporting the queue's depth and its pushed, popped and discarded element counters
+    /// through the `metrics` facade, labelled with `queue` = `name`. The metrics are registered
+    /// with the recorder installed at the time of the call.
+    #[cfg(feature = "metrics")]
+    pub fn enable_metrics(&self, name: &str) -> Result<(), QueueError> {
+        let mut guard = self.protected_data.lock()?;
+        let metrics = QueueMetrics::new(name);
+        metrics.on_resize(self.size());
+        guard.metrics = Some(metrics);
+        Ok(())
+    }
+
+    /// Start emitting `tracing` events for elements entering and leaving the queue, with fields
+    /// for the queue's `name` and its depth. Pushes, pops and discards are traced at trace level;
+    /// overwrites, pushes finding the queue full, and callers of a `BlockingQueue` starting and
+    /// ending a wait at debug level.
+    #[cfg(feature = "tracing")]
+    pub fn enable_tracing(&self, name: &str) -> Result<(), QueueError> {
+        let mut guard = self.protected_data.lock()?;
+        guard.tracer = Some(QueueTracer::new(name));
+        Ok(())
+    }
+
+    /// Get a copy of the latency probe, including its rolling window of samples.
+    pub fn latency_probe(&self) -> Result<LatencyProbe, QueueError> {
+        let guard = self.protected_data.lock()?;
+        Ok(guard.probe)
+    }
+
+    /// Set the soft capacity: the normal limit on the number of elements, which pushes may exceed
+    /// in short bursts up to the hard capacity. Producers can check `is_above_soft_limit()` to
+    /// apply backpressure. Clamped to the hard capacity, which is also the default.
+    pub fn set_soft_capacity(&self, soft_capacity: usize) {
+        self.soft_capacity
+            .store(min(soft_capacity, CAPACITY), Ordering::Relaxed);
+    }
+
+    /// Get the soft capacity of the queue.
+    pub fn soft_capacity(&self) -> usize {
+        self.soft_capacity.load(Ordering::Relaxed)
+    }
+
+    /// Set what `push()` and `push_ref()` do when the queue is full. Defaults to
+    /// `OverflowPolicy::Reject`; the overwriting pushes always evict regardless of the policy.
+    /// Elements dropped by `OverflowPolicy::DropNewest` count as both pushed and discarded.
+    /// `OverflowPolicy::Decimate` only thins out elements visible to consumers, and rejects the
+    /// push if that frees no slot.
+    pub fn set_overflow_policy(&self, policy: OverflowPolicy) {
+        self.overflow_policy.store(policy as u8, Ordering::Relaxed);
+    }
+
+    /// Get the policy applied by `push()` when the queue is full.
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        match self.overflow_policy.load(Ordering::Relaxed) {
+            p if p == OverflowPolicy::OverwriteOldest as u8 => OverflowPolicy::OverwriteOldest,
+            p if p == OverflowPolicy::DropNewest as u8 => OverflowPolicy::DropNewest,
+            p if p == OverflowPolicy::Decimate as u8 => OverflowPolicy::Decimate,
+            _ => OverflowPolicy::Reject,
+        }
+    }
+
+    /// Check if the queue holds more elements than its soft capacity, counting unpublished ones.
+    pub fn is_above_soft_limit(&self) -> bool {
+        self.occupied() > self.soft_capacity()
+    }
+
+    /// Exchange the contents of two queues, including unpublished elements, while holding both
+    /// locks, so that a consumer can grab everything queued so far in one shot. Each queue keeps
+    /// its own configuration and statistics; latency probes only sample elements pushed after the
+    /// swap.
+    pub fn swap_contents(&self, other: &Self) -> Result<(), QueueError> {
+        if ptr::eq(self, other) {
+            return Ok(());
+        }
+
+        // Lock in address order, so that concurrent swaps in opposite directions cannot deadlock
+        let (first, second) = if (self as *const Self) < (other as *const Self) {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut first_guard = first.protected_data.lock()?;
+        let mut second_guard = second.protected_data.lock()?;
+
+        first_guard.queue.swap_contents(&mut second_guard.queue);
+        let (size, unpublished) = (first.size(), first.unpublished());
+        first.size.store(second.size(), Ordering::Relaxed);
+        first
+            .unpublished
+            .store(second.unpublished(), Ordering::Relaxed);
+        second.size.store(size, Ordering::Relaxed);
+        second.unpublished.store(unpublished, Ordering::Relaxed);
+        first_guard.on_resize(first.size());
+        second_guard.on_resize(second.size());
+
+        // Markers in flight refer to elements that moved to the other queue
+        let queued = first_guard.queue.size();
+        first_guard.probe.restart(queued);
+        let queued = second_guard.queue.size();
+        second_guard.probe.restart(queued);
+
+        Ok(())
+    }
+
+    /// Move every element visible to consumers into a new queue under a single lock acquisition,
+    /// so that they can be processed offline while producers carry on. Unpublished elements stay
+    /// behind. The moved elements count as popped.
+    pub fn take(&self) -> Result<BasicTypedQueue<T, CAPACITY>, QueueError> {
+        let mut guard = self.protected_data.lock()?;
+        let count = self.size();
+
+        let mut taken = BasicTypedQueue::new();
+        for _ in 0..count {
+            unsafe { taken.push_unchecked(guard.queue.pop_unchecked()) };
+        }
+        guard.on_pop(count);
+        self.size.store(0, Ordering::Relaxed);
+        guard.on_resize(0);
+
+        Ok(taken)
+    }
+
+    /// Touch every page of the free part of the buffer, so that first-use page faults happen now
+    /// instead of in the middle of real-time operation. Queued elements are left untouched.
+    pub fn prefault(&self) -> Result<(), QueueError> {
+        let mut guard = self.protected_data.lock()?;
+        guard.queue.prefault();
+        Ok(())
+    }
+
+    /// Get a value implementing `Display` that formats the elements visible to consumers in FIFO
+    /// order, separated by `separator`. The lock is taken while formatting.
+    pub fn display_with<'a>(&'a self, separator: &'a str) -> DisplayWith<'a, T, CAPACITY> {
+        DisplayWith {
+            queue: self,
+            separator,
+        }
+    }
+
+    /// Get a snapshot of the lifetime counters of the queue.
+    pub fn stats(&self) -> Result<QueueStats, QueueError> {
+        let guard = self.protected_data.lock()?;
+        Ok(guard.stats)
+    }
+
+    /// Close the queue to producers: every later push fails with `QueueError::Closed`, while
+    /// consumers can still pop the remaining elements. Closing an already closed queue has no
+    /// effect.
+    pub fn close(&self) -> Result<(), QueueError> {
+        let _guard = self.protected_data.lock()?;
+        self.closed.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Check if the queue has been closed to producers.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    /// Push an element to the queue, overwriting the oldest element (published or not) if the
+    /// queue is full. Returns the evicted element, if any, so that it can be counted or persisted
+    /// instead of being lost silently.
+    pub fn push_evict(&self, input: T) -> Result<Option<T>, QueueError> {
+        let mut guard = self.protected_data.lock()?;
+        if self.is_closed() {
+            return Err(QueueError::Closed);
+        }
+
+        let evicted = if guard.queue.is_full() {
+            guard.queue.front().ok().copied()
+        } else {
+            None
+        };
+        self.push_overwrite_locked(&mut guard, &input)?;
+
+        Ok(evicted)
+    }
+
+    /// Push an element by letting `init` write it directly into the tail slot while holding the
+    /// lock, so that large elements need not be built on the caller's stack first. The element is
+    /// committed if `init` succeeds, and the queue is left unchanged if it fails. Fails if queue
+    /// is full.
+    ///
+    /// # Safety
+    ///
+    /// If `init` returns `Ok`, it must have initialized the slot.
+    pub unsafe fn push_with<E, F>(&self, init: F) -> Result<(), E>
+    where
+        E: From<QueueError>,
+        F: FnOnce(&mut MaybeUninit<T>) -> Result<(), E>,
+    {
+        let mut guard = self.protected_data.lock().map_err(QueueError::from)?;
+        if self.is_closed() {
+            return Err(QueueError::Closed.into());
+        }
+
+        guard.queue.push_with(init)?;
+        guard.on_push(1);
+        self.commit(&guard, 1);
+
+        Ok(())
+    }
+
+    /// Shut the queue down in one call: close it, take every element still enqueued (including
+    /// unpublished ones) in FIFO order, and return them with the final statistics. Consumers
+    /// never block on the queue, so there are no waiters to wake; they observe an empty, closed
+    /// queue afterwards.
+    #[cfg(feature = "alloc")]
+    pub fn finish(&self) -> Result<(Vec<T>, QueueStats), QueueError> {
+        let mut guard = self.protected_data.lock()?;
+        self.closed.store(true, Ordering::Relaxed);
+
+        let remaining = guard.queue.to_vec();
+        guard.queue.clear()?;
+        guard.on_pop(remaining.len());
+        self.size.store(0, Ordering::Relaxed);
+        self.unpublished.store(0, Ordering::Relaxed);
+        guard.on_resize(0);
+
+        Ok((remaining, guard.stats))
+    }
+
+    /// Get the number of elements pushed after the last group boundary.
+    pub fn unpublished(&self) -> usize {
+        self.unpublished.load(Ordering::Relaxed)
+    }
+
+    // Total number of occupied slots, including unpublished elements.
+    fn occupied(&self) -> usize {
+        self.size() + self.unpublished()
+    }
+
+    // Push an element, applying the overflow policy if the queue is full. Must be called while
+    // holding the lock, whose guard is passed in.
+    fn push_locked(&self, guard: &mut QueueData<T, CAPACITY>, input: &T) -> Result<(), QueueError> {
+        if self.is_closed() {
+            return Err(QueueError::Closed);
+        }
+
+        if guard.queue.is_full() {
+            match self.overflow_policy() {
+                OverflowPolicy::Reject => {
+                    guard.on_full();
+                    return Err(QueueError::QueueFull);
+                }
+                OverflowPolicy::OverwriteOldest => return self.push_overwrite_locked(guard, input),
+                OverflowPolicy::DropNewest => {
+                    guard.on_reject();
+                    return Ok(());
+                }
+                OverflowPolicy::Decimate => {
+                    let discarded = guard.queue.decimate_first(self.size());
+                    if discarded == 0 {
+                        guard.on_full();
+                        return Err(QueueError::QueueFull);
+                    }
+                    guard.on_discard(discarded);
+                    // The discarded elements are spread over the queue rather than taken from its
+                    // front, so the position of a marker in flight is no longer known
+                    let queued = guard.queue.size();
+                    guard.probe.restart(queued);
+                    self.size.fetch_sub(discarded, Ordering::Relaxed);
+                    guard.on_resize(self.size());
+                }
+            }
+        }
+
+        guard.queue.push_ref(input)?;
+        guard.on_push(1);
+        self.commit(guard, 1);
+
+        Ok(())
+    }
+
+    // Pop the oldest element visible to consumers. Must be called while holding the lock.
+    fn pop_locked(
+        &self,
+        guard: &mut QueueData<T, CAPACITY>,
+        output: &mut MaybeUninit<T>,
+    ) -> Result<(), QueueError> {
+        if self.is_empty() {
+            return Err(QueueError::QueueEmpty);
+        }
+
+        output.write(unsafe { guard.queue.pop_unchecked() });
+        guard.on_pop(1);
+        self.size.fetch_sub(1, Ordering::Relaxed);
+        guard.on_resize(self.size());
+
+        Ok(())
+    }
+
+    // Discard every element, including unpublished ones. Must be called while holding the lock.
+    fn clear_locked(&self, guard: &mut QueueData<T, CAPACITY>) -> Result<(), QueueError> {
+        let count = guard.queue.size();
+        guard.queue.clear()?;
+        guard.on_discard(count);
+        self.size.store(0, Ordering::Relaxed);
+        self.unpublished.store(0, Ordering::Relaxed);
+        guard.on_resize(0);
+
+        Ok(())
+    }
+
+    // Push an element, evicting the oldest one if the queue is full. Must be called while holding
+    // the lock, whose guard is passed in.
+    fn push_overwrite_locked(
+        &self,
+        guard: &mut QueueData<T, CAPACITY>,
+        input: &T,
+    ) -> Result<(), QueueError> {
+        if guard.queue.capacity() == 0 {
+            return Err(QueueError::QueueFull);
+        }
+
+        if self.is_full() {
+            guard.on_overwrite();
+        }
+
+        guard.queue.push_ref_overwrite(input)?;
+        guard.on_push(1);
+
+        // This size update is done in multiple steps, but is safe due to being in the
+        // scope of where we're holding the mutex on the other protected data.
+        if self.unpublished() > 0 {
+            // The new element joins the open group, consuming a visible slot if full
+            if self.is_full() && self.size() > 0 {
+                self.size.fetch_sub(1, Ordering::Relaxed);
+            }
+            let new_unpublished = min(self.unpublished() + 1, CAPACITY - self.size());
+            self.unpublished.store(new_unpublished, Ordering::Relaxed);
+        } else {
+            let new_size = min(self.size.load(Ordering::Relaxed) + 1, CAPACITY);
+            self.size.store(new_size, Ordering::Relaxed);
+        }
+        guard.on_resize(self.size());
+
+        Ok(())
+    }
+
+    // Record a caller of a blocking wrapper, e.g. `BlockingQueue`, starting to wait for room or
+    // for an element.
+    pub(crate) fn on_wait(&self) {
+        #[cfg(feature = "tracing")]
+        if let Ok(guard) = self.protected_data.lock() {
+            if let Some(tracer) = &guard.tracer {
+                tracer.on_wait(guard.queue.size());
+            }
+        }
+    }
+
+    // Record a caller of a blocking wrapper being woken up after waiting.
+    pub(crate) fn on_wait_done(&self) {
+        #[cfg(feature = "tracing")]
+        if let Ok(guard) = self.protected_data.lock() {
+            if let Some(tracer) = &guard.tracer {
+                tracer.on_wait_done(guard.queue.size());
+            }
+        }
+    }
+
+    // Account for newly written elements. Must be called while holding the lock, whose guard is
+    // passed in.
+    fn commit(&self, guard: &QueueData<T, CAPACITY>, count: usize) {
+        if self.unpublished() > 0 {
+            self.unpublished.fetch_add(count, Ordering::Relaxed);
+        } else {
+            self.size.fetch_add(count, Ordering::Relaxed);
+            guard.on_resize(self.size());
+        }
+    }
+}
+
+impl<T: Copy + PartialEq, const CAPACITY: usize> ThreadSafeTypedQueue<T, CAPACITY> {
+    /// Check if the queue contains an element equal to `value`.
+    pub fn contains(&self, value: &T) -> Result<bool, QueueError> {
+        Ok(self.position(|element| element == value)?.is_some())
+    }
+
+    /// Push an element to the queue by reference, unless it is equal to the newest element
+    /// (published or not). Returns whether the element was pushed. Fails if queue is full and
+    /// the element would have been pushed.
+    pub fn push_dedup(&self, input: &T) -> Result<bool, QueueError> {
+        let mut guard = self.protected_data.lock()?;
+        if self.is_closed() {
+            return Err(QueueError::Closed);
+        }
+
+        if guard.queue.back().is_ok_and(|back| back == input) {
+            return Ok(false);
+        }
+
+        guard.queue.push_ref(input)?;
+        guard.on_push(1);
+        self.commit(&guard, 1);
+
+        Ok(true)
+    }
+}
+
+// Takes the lock and formats the elements visible to consumers from front to back, separated by
+// commas.
+impl<T: Copy + fmt::Display, const CAPACITY: usize> fmt::Display
+    for ThreadSafeTypedQueue<T, CAPACITY>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.display_with(", "), f)
+    }
+}
+
+// Formatter for the elements of a thread-safe queue with a custom separator. Takes the lock
+// while formatting.
+pub struct DisplayWith<'a, T: Copy, const CAPACITY: usize> {
+    queue: &'a ThreadSafeTypedQueue<T, CAPACITY>,
+    separator: &'a str,
+}
+
+impl<'a, T: Copy + fmt::Display, const CAPACITY: usize> fmt::Display
+    for DisplayWith<'a, T, CAPACITY>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let guard = self.queue.protected_data.lock().map_err(|_| fmt::Error)?;
+        let visible = guard.queue.iter().take(self.queue.size());
+        basic_typed_queue::write_separated(f, visible, self.separator)
+    }
+}
+
+// Same compact form as the basic queue, for the elements visible to consumers. Takes the lock.
+#[cfg(feature = "defmt")]
+impl<T: Copy + defmt::Format, const CAPACITY: usize> defmt::Format
+    for ThreadSafeTypedQueue<T, CAPACITY>
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self.protected_data.lock() {
+            Ok(guard) => {
+                defmt::write!(f, "{=usize}/{=usize} ", self.size(), CAPACITY);
+                let visible = guard.queue.iter().take(self.size());
+                crate::defmt_format::write_elements(f, visible);
+            }
+            Err(..) => defmt::write!(f, "<poisoned>"),
+        }
+    }
+}
+
+// Takes the lock and prints the elements visible to consumers, from front to back.
+impl<T: Copy + fmt::Debug, const CAPACITY: usize> fmt::Debug for ThreadSafeTypedQueue<T, CAPACITY> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.protected_data.lock() {
+            Ok(guard) => f
+                .debug_list()
+                .entries(guard.queue.iter().take(self.size()))
+                .finish(),
+            Err(..) => f.write_str("[<poisoned>]"),
+        }
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize> Default for ThreadSafeTypedQueue<T, CAPACITY> {
+    fn default() -> Self {
+        ThreadSafeTypedQueue::new()
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize> TypedQueue<T> for ThreadSafeTypedQueue<T, CAPACITY> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        (&*self).push(input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        (&*self).push_overwrite(input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        (&*self).push_ref(input)
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        (&*self).push_ref_overwrite(input)
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        (&*self).pop()
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        (&*self).pop_ref(output)
+    }
+
+    fn pop_into_uninit(&mut self, output: &mut MaybeUninit<T>) -> Result<(), QueueError> {
+        (&*self).pop_into_uninit(output)
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        (&*self).clear()
+    }
+
+    fn is_full(&self) -> bool {
+        (&self).is_full()
+    }
+
+    fn is_empty(&self) -> bool {
+        (&self).is_empty()
+    }
+
+    fn size(&self) -> usize {
+        (&self).size()
+    }
+
+    fn capacity(&self) -> usize {
+        CAPACITY
+    }
+}
+
+// The queue is also implemented for shared references, so that multiple threads can push and pop
+// concurrently (e.g. through an `Arc`) without wrapping the queue in another lock.
+impl<T: Copy, const CAPACITY: usize> TypedQueue<T> for &ThreadSafeTypedQueue<T, CAPACITY> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref(&input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref_overwrite(&input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        match self.protected_data.lock() {
+            Ok(mut guard) => self.push_locked(&mut guard, input),
+            Err(..) => Err(QueueError::MutexPoisoned),
+        }
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        match self.protected_data.lock() {
+            Ok(mut guard) => {
+                if self.is_closed() {
+                    return Err(QueueError::Closed);
+                }
+
+                self.push_overwrite_locked(&mut guard, input)
+            }
+            Err(..) => Err(QueueError::MutexPoisoned),
+        }
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        let mut value = MaybeUninit::<T>::uninit();
+        self.pop_into_uninit(&mut value)?;
+        // `pop_into_uninit()` only succeeds after writing the value
+        Ok(unsafe { value.assume_init() })
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        *output = self.pop()?;
+        Ok(())
+    }
+
+    fn pop_into_uninit(&mut self, output: &mut MaybeUninit<T>) -> Result<(), QueueError> {
+        let mut guard = self.protected_data.lock()?;
+        self.pop_locked(&mut guard, output)
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        match self.protected_data.lock() {
+            Ok(mut guard) => self.clear_locked(&mut guard),
+            Err(..) => Err(QueueError::MutexPoisoned),
+        }
+    }
+
+    // There's no value in protecting the functions below, as the calling thread could be
+    // pre-empted by another thread that changes the state of the queue immediately after exiting
+    // any of these functions and dropping the would-be lock.
+
+    fn is_full(&self) -> bool {
+        self.occupied() == CAPACITY
+    }
+
+    fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    fn size(&self) -> usize {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    fn capacity(&self) -> usize {
+        CAPACITY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QueueStats, ThreadSafeTypedQueue};
+    use crate::typed_queue::{OverflowPolicy, QueueError, TypedQueue};
+    use std::mem::MaybeUninit;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn push_pop() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok())
+        }
+
+        for n in 0..SIZE {
+            let output = queue.pop();
+            assert!(output.is_ok());
+            assert_eq!(output.unwrap(), n as u32);
+        }
+    }
+
+    #[test]
+    fn push_overwrite_pop() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok())
+        }
+
+        // Overwrite oldest element (0) with SIZE
+        assert!(queue.push_overwrite(SIZE as u32).is_ok());
+        assert!(queue.is_full());
+
+        // Survivors keep their FIFO order, followed by the new element
+        for n in 1..=SIZE {
+            let output = queue.pop();
+            assert!(output.is_ok());
+            assert_eq!(output.unwrap(), n as u32);
+        }
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn push_ref_pop_ref() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+
+        for n in 0..SIZE {
+            assert!(queue.push_ref(&(n as u32)).is_ok())
+        }
+
+        for n in 0..SIZE {
+            let mut output: u32 = 0;
+            let res = queue.pop_ref(&mut output);
+            assert!(res.is_ok());
+            assert_eq!(output, n as u32);
+        }
+    }
+
+    #[test]
+    fn push_ref_overwrite_pop() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+
+        for n in 0..SIZE {
+            assert!(queue.push_ref(&(n as u32)).is_ok())
+        }
+
+        // Overwrite oldest elements (0 and 1) with SIZE and SIZE + 1
+        assert!(queue.push_ref_overwrite(&(SIZE as u32)).is_ok());
+        assert!(queue.push_ref_overwrite(&(SIZE as u32 + 1)).is_ok());
+
+        // Survivors keep their FIFO order, followed by the new elements
+        for n in 2..SIZE + 2 {
+            let mut output: u32 = 0;
+            let res = queue.pop_ref(&mut output);
+            assert!(res.is_ok());
+            assert_eq!(output, n as u32);
+        }
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn pop_into_uninit() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        let mut slot = MaybeUninit::<u32>::uninit();
+        assert_eq!(
+            queue.pop_into_uninit(&mut slot).unwrap_err(),
+            QueueError::QueueEmpty
+        );
+
+        assert!(queue.push(1).is_ok());
+        assert!(queue.pop_into_uninit(&mut slot).is_ok());
+        assert_eq!(unsafe { slot.assume_init() }, 1);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn wrap() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+
+        // push/pop half capacity to move head/tail to SIZE/2
+        for n in 0..SIZE / 2 {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        for _ in 0..SIZE / 2 {
+            assert!(queue.pop().is_ok());
+        }
+
+        // Now perform full push/pop check to verify wrap logic
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok())
+        }
+
+        for n in 0..SIZE {
+            let output = queue.pop();
+            assert!(output.is_ok());
+            assert_eq!(output.unwrap(), n as u32);
+        }
+    }
+
+    #[test]
+    fn front_back() {
+        // TODO: Test for thread-safe front/back impl
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.front().unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(queue.back().unwrap_err(), QueueError::QueueEmpty);
+
+        const VALUE: u32 = 123;
+        assert!(queue.push(VALUE).is_ok());
+
+        assert_eq!(*queue.front().unwrap(), VALUE);
+        assert_eq!(*queue.back().unwrap(), VALUE);
 
         let front_ref_guard = queue.front().unwrap();
         assert_eq!(*front_ref_guard, VALUE);
 
-        // Borrow checker prevents mutating while holding reference
-        // let res = queue.pop();  // not allowed
-        println!("{}", *front_ref_guard);
+        // Borrow checker prevents mutating while holding reference
+        // let res = queue.pop();  // not allowed
+        println!("{}", *front_ref_guard);
+    }
+
+    #[test]
+    fn peek_at() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.peek_at(0).unwrap_err(), QueueError::QueueEmpty);
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        assert_eq!(*queue.peek_at(0).unwrap(), 0);
+        assert_eq!(*queue.peek_at(SIZE - 1).unwrap(), (SIZE - 1) as u32);
+        assert_eq!(queue.peek_at(SIZE).unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(queue.size(), SIZE);
+    }
+
+    #[test]
+    fn with_front_back() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert_eq!(
+            queue.with_front(|_| ()).unwrap_err(),
+            QueueError::QueueEmpty
+        );
+        assert_eq!(queue.with_back(|_| ()).unwrap_err(), QueueError::QueueEmpty);
+
+        assert!(queue.push(1).is_ok());
+        assert!(queue.push(2).is_ok());
+        assert!(queue.push_unpublished(3).is_ok());
+
+        assert_eq!(queue.with_front(|&value| value * 10).unwrap(), 10);
+        assert_eq!(queue.with_back(|&value| value * 10).unwrap(), 20);
+
+        // The lock is released as soon as the closure returns
+        assert_eq!(queue.pop().unwrap(), 1);
+    }
+
+    #[test]
+    fn transaction() {
+        let queue = Arc::new(ThreadSafeTypedQueue::<u64, SIZE>::default());
+        for _ in 0..SIZE {
+            assert!((&*queue).push(1).is_ok());
+        }
+
+        // Concurrently pop pairs and push back their sum, until a single element is left
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || loop {
+                    let done = queue
+                        .transaction(|view| {
+                            if view.size() < 2 {
+                                return true;
+                            }
+                            let sum = view.pop().unwrap() + view.pop().unwrap();
+                            assert!(view.push(sum).is_ok());
+                            false
+                        })
+                        .unwrap();
+                    if done {
+                        break;
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let total = queue
+            .transaction(|view| {
+                assert_eq!(view.front(), view.back());
+                assert_eq!(view.get(1), None);
+                view.pop()
+            })
+            .unwrap();
+        assert_eq!(total.unwrap(), SIZE as u64);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn iter() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert!(queue.iter().unwrap().is_empty());
+
+        // Move head/tail so that the contents wrap around the end of the buffer
+        for n in 0..SIZE / 2 {
+            assert!(queue.push(n as u32).is_ok());
+            assert!(queue.pop().is_ok());
+        }
+        for n in 0..SIZE as u32 - 1 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(queue.push_unpublished(100).is_ok());
+
+        // Only visible elements are yielded
+        let guard = queue.iter().unwrap();
+        assert_eq!(guard.len(), SIZE - 1);
+        assert!(guard.iter().copied().eq(0..SIZE as u32 - 1));
+        let mut sum = 0;
+        for value in &guard {
+            sum += value;
+        }
+        assert_eq!(sum, (0..SIZE as u32 - 1).sum());
+        assert_eq!(format!("{:?}", guard).matches(',').count(), SIZE - 2);
+    }
+
+    #[test]
+    fn swap_contents() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        let mut other = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 / 2 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(queue.push_unpublished(100).is_ok());
+        assert!(other.push(200).is_ok());
+
+        assert!(queue.swap_contents(&other).is_ok());
+        assert!(queue.swap_contents(&queue).is_ok());
+        assert_eq!((queue.size(), queue.unpublished()), (1, 0));
+        assert_eq!((other.size(), other.unpublished()), (SIZE / 2, 1));
+        assert_eq!(queue.pop().unwrap(), 200);
+
+        assert!(other.publish_after().is_ok());
+        for n in 0..SIZE as u32 / 2 {
+            assert_eq!(other.pop().unwrap(), n);
+        }
+        assert_eq!(other.pop().unwrap(), 100);
+    }
+
+    #[test]
+    fn take() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 / 2 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(queue.push_unpublished(100).is_ok());
+
+        let mut taken = queue.take().unwrap();
+        assert_eq!(taken.size(), SIZE / 2);
+        for n in 0..SIZE as u32 / 2 {
+            assert_eq!(taken.pop().unwrap(), n);
+        }
+        assert_eq!(queue.stats().unwrap().popped, SIZE as u64 / 2);
+
+        // Unpublished elements stay behind
+        assert!(queue.is_empty());
+        assert!(queue.publish_after().is_ok());
+        assert_eq!(queue.pop().unwrap(), 100);
+    }
+
+    #[test]
+    fn for_each() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        for n in 1..=SIZE as u32 / 2 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(queue.push_unpublished(100).is_ok());
+
+        let mut sum = 0;
+        assert!(queue.for_each(|value| sum += value).is_ok());
+        assert_eq!(sum, (1..=SIZE as u32 / 2).sum());
+        assert_eq!(queue.size(), SIZE / 2);
+    }
+
+    #[test]
+    fn snapshot() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 / 2 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(queue.push_unpublished(100).is_ok());
+
+        #[cfg(feature = "alloc")]
+        assert_eq!(
+            queue.snapshot().unwrap(),
+            (0..SIZE as u32 / 2).collect::<Vec<_>>()
+        );
+
+        let mut output = [0; 4];
+        assert_eq!(queue.snapshot_into(&mut output).unwrap(), 4);
+        assert_eq!(output, [0, 1, 2, 3]);
+        let mut output = [0; SIZE];
+        assert_eq!(queue.snapshot_into(&mut output).unwrap(), SIZE / 2);
+
+        // The contents are left in place
+        assert_eq!(queue.size(), SIZE / 2);
+
+        // Contents wrapping around the end of the buffer are copied in order
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        for n in 0..SIZE as u32 / 2 {
+            assert_eq!(queue.pop(), Ok(n));
+            assert!(queue.push(SIZE as u32 + n).is_ok());
+        }
+        assert_eq!(queue.snapshot_into(&mut output).unwrap(), SIZE);
+        assert!(output
+            .iter()
+            .copied()
+            .eq(SIZE as u32 / 2..SIZE as u32 * 3 / 2));
+    }
+
+    #[test]
+    fn contains_position() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert!(!queue.contains(&0).unwrap());
+
+        for n in 0..SIZE / 2 {
+            assert!(queue.push(n as u32 * 10).is_ok());
+        }
+        assert!(queue.push_unpublished(5).is_ok());
+
+        assert!(queue.contains(&10).unwrap());
+        assert_eq!(queue.position(|&value| value >= 30).unwrap(), Some(3));
+
+        // Unpublished elements are not visible
+        assert!(!queue.contains(&5).unwrap());
+        assert!(queue.publish_after().is_ok());
+        assert!(queue.contains(&5).unwrap());
+    }
+
+    #[test]
+    fn reserve_publish() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert!(queue.push(0).is_ok());
+
+        let mut batch = queue.reserve(SIZE - 1).unwrap();
+        for n in 1..SIZE {
+            assert_eq!(batch.remaining(), SIZE - n);
+            assert!(batch.push(n as u32).is_ok());
+        }
+        assert_eq!(batch.push(0).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(batch.written(), SIZE - 1);
+
+        // Nothing is visible until the batch is published
+        assert_eq!(queue.size(), 1);
+        batch.publish();
+        assert!(queue.is_full());
+
+        for n in 0..SIZE {
+            assert_eq!(queue.pop().unwrap(), n as u32);
+        }
+    }
+
+    #[test]
+    fn reserve_discard() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.reserve(SIZE + 1).unwrap_err(), QueueError::QueueFull);
+
+        let mut batch = queue.reserve(2).unwrap();
+        assert!(batch.push(1).is_ok());
+        assert!(batch.push(2).is_ok());
+        drop(batch);
+
+        // Dropping an unpublished batch leaves the queue untouched
+        assert!(queue.is_empty());
+        assert!(queue.push(3).is_ok());
+        assert_eq!(queue.pop().unwrap(), 3);
+    }
+
+    #[test]
+    fn reserve_zero_capacity() {
+        let queue = ThreadSafeTypedQueue::<u32, 0>::new();
+        assert_eq!(queue.reserve(1).unwrap_err(), QueueError::QueueFull);
+
+        // An empty batch can still be published
+        let batch = queue.reserve(0).unwrap();
+        assert_eq!(batch.remaining(), 0);
+        batch.publish();
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn reserve_push_unchecked() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+
+        let mut batch = queue.reserve(SIZE).unwrap();
+        for n in 0..SIZE {
+            unsafe { batch.push_unchecked(n as u32) };
+        }
+        batch.publish();
+
+        for n in 0..SIZE {
+            assert_eq!(queue.pop().unwrap(), n as u32);
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "push_unchecked() called on an exhausted batch reservation")]
+    fn reserve_push_unchecked_exhausted() {
+        let queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        let mut batch = queue.reserve(1).unwrap();
+        unsafe {
+            batch.push_unchecked(0);
+            batch.push_unchecked(1);
+        }
+    }
+
+    #[test]
+    fn push_pop_slice() {
+        let queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        let input: Vec<u32> = (0..SIZE as u32 + 4).collect();
+        assert_eq!(queue.push_slice(&input).unwrap(), SIZE);
+        assert!(queue.is_full());
+
+        let mut output = [0u32; 4];
+        assert_eq!(queue.pop_slice(&mut output).unwrap(), 4);
+        assert_eq!(output, [0, 1, 2, 3]);
+
+        let mut output = [0u32; SIZE];
+        assert_eq!(queue.pop_slice(&mut output).unwrap(), SIZE - 4);
+        assert_eq!(output[..SIZE - 4], input[4..SIZE]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn drain() {
+        let queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        let input: Vec<u32> = (0..SIZE as u32).collect();
+        assert_eq!(queue.push_slice(&input).unwrap(), SIZE);
+
+        let mut output = Vec::new();
+        assert_eq!(queue.drain(|value| output.push(value)).unwrap(), SIZE);
+        assert_eq!(output, input);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn pop_into() {
+        let queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        let input: Vec<u32> = (0..SIZE as u32).collect();
+        assert_eq!(queue.push_slice(&input).unwrap(), SIZE);
+
+        let mut output = vec![100];
+        assert_eq!(queue.pop_into(&mut output, SIZE / 2).unwrap(), SIZE / 2);
+        assert_eq!(queue.pop_into(&mut output, SIZE).unwrap(), SIZE / 2);
+        assert_eq!(queue.pop_into(&mut output, SIZE).unwrap(), 0);
+        assert_eq!(output[0], 100);
+        assert_eq!(output[1..], input[..]);
+    }
+
+    #[test]
+    fn yield_interval() {
+        let queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.yield_interval(), 0);
+        queue.set_yield_interval(3);
+        assert_eq!(queue.yield_interval(), 3);
+
+        // Chunked bulk operations still move every element in order
+        let input: Vec<u32> = (0..SIZE as u32).collect();
+        assert_eq!(queue.push_slice(&input).unwrap(), SIZE);
+
+        let mut output = [0u32; SIZE / 2];
+        assert_eq!(queue.pop_slice(&mut output).unwrap(), SIZE / 2);
+        assert_eq!(output[..], input[..SIZE / 2]);
+
+        let mut output = Vec::new();
+        assert_eq!(queue.drain(|value| output.push(value)).unwrap(), SIZE / 2);
+        assert_eq!(output[..], input[SIZE / 2..]);
+    }
+
+    #[test]
+    fn latency_probe() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert!(!queue.latency_probe().unwrap().is_enabled());
+        assert!(queue.enable_latency_probe(2).is_ok());
+
+        for n in 0..SIZE {
+            assert!(queue.push(n as u32).is_ok());
+        }
+        for _ in 0..SIZE {
+            assert!(queue.pop().is_ok());
+        }
+
+        // Only one marker is in flight at a time
+        let probe = queue.latency_probe().unwrap();
+        assert_eq!(probe.sample_count(), 1);
+        assert!(probe.percentile(0.99).is_some());
+
+        let input: Vec<u32> = (0..4).collect();
+        let mut output = [0u32; 4];
+        for _ in 0..4 {
+            assert_eq!(queue.push_slice(&input).unwrap(), 4);
+            assert_eq!(queue.pop_slice(&mut output).unwrap(), 4);
+        }
+        assert_eq!(queue.latency_probe().unwrap().sample_count(), 5);
+    }
+
+    #[test]
+    fn latency_probe_restart() {
+        // Elements queued before the probe is enabled are not sampled
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        for n in 0..3 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(queue.enable_latency_probe(1).is_ok());
+        assert!(queue.push(100).is_ok());
+        for _ in 0..3 {
+            assert!(queue.pop().is_ok());
+        }
+        assert_eq!(queue.latency_probe().unwrap().sample_count(), 0);
+        assert_eq!(queue.pop(), Ok(100));
+        assert_eq!(queue.latency_probe().unwrap().sample_count(), 1);
+
+        // A marker swapped out with the contents is not sampled by the queue it left
+        let other = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert!(queue.push(1).is_ok());
+        thread::sleep(Duration::from_millis(50));
+        assert!(queue.swap_contents(&other).is_ok());
+        assert!(queue.push(2).is_ok());
+        assert_eq!(queue.pop(), Ok(2));
+        let probe = queue.latency_probe().unwrap();
+        assert_eq!(probe.sample_count(), 2);
+        assert!(probe.percentile(1.0).unwrap() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn publish_after() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert!(queue.push(0).is_ok());
+
+        // Elements of a partially written group are hidden from consumers
+        assert!(queue.push_unpublished(1).is_ok());
+        assert!(queue.push_unpublished(2).is_ok());
+        assert_eq!(queue.size(), 1);
+        assert_eq!(queue.unpublished(), 2);
+        assert_eq!(*queue.back().unwrap(), 0);
+        assert_eq!(queue.pop().unwrap(), 0);
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+
+        // Regular pushes while a group is open join the group
+        assert!(queue.push(3).is_ok());
+        assert_eq!(queue.unpublished(), 3);
+
+        assert!(queue.publish_after().is_ok());
+        assert_eq!(queue.size(), 3);
+        assert_eq!(queue.unpublished(), 0);
+        for n in 1..=3 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+    }
+
+    #[test]
+    fn publish_after_full() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE {
+            assert!(queue.push_unpublished(n as u32).is_ok());
+        }
+
+        assert!(queue.is_full());
+        assert!(queue.is_empty());
+        assert_eq!(queue.push(0).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(queue.reserve(1).unwrap_err(), QueueError::QueueFull);
+
+        assert!(queue.publish_after().is_ok());
+        assert_eq!(queue.size(), SIZE);
+    }
+
+    #[test]
+    fn shared_push_pop() {
+        let queue = Arc::new(ThreadSafeTypedQueue::<u32, SIZE>::default());
+        const COUNT: u32 = 1000;
+
+        let producer = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                let mut queue = &*queue;
+                for n in 0..COUNT {
+                    while queue.push(n).is_err() {
+                        thread::yield_now();
+                    }
+                }
+            })
+        };
+
+        let mut queue = &*queue;
+        for n in 0..COUNT {
+            loop {
+                if let Ok(value) = queue.pop() {
+                    assert_eq!(value, n);
+                    break;
+                }
+                thread::yield_now();
+            }
+        }
+
+        producer.join().unwrap();
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn clear() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE / 2 {
+            assert!(queue.push(n as u32).is_ok());
+        }
+        assert!(queue.push_unpublished(0).is_ok());
+
+        assert!(queue.clear().is_ok());
+        assert!(queue.is_empty());
+        assert_eq!(queue.unpublished(), 0);
+        assert_eq!(queue.front().unwrap_err(), QueueError::QueueEmpty);
+
+        assert!(queue.push(1).is_ok());
+        assert_eq!(queue.pop().unwrap(), 1);
+    }
+
+    #[test]
+    fn push_unique_by() {
+        let mut queue = ThreadSafeTypedQueue::<(u8, u32), SIZE>::default();
+        let key = |&(id, _): &(u8, u32)| id;
+        assert_eq!(queue.push_unique_by(key, (1, 10)), Ok(true));
+        assert!(queue.push_unpublished((2, 20)).is_ok());
+        assert_eq!(queue.push_unique_by(key, (2, 21)), Ok(false));
+        assert_eq!(queue.push_or_replace_by(key, (1, 11)), Ok(Some((1, 10))));
+        assert_eq!(queue.push_or_replace_by(key, (3, 30)), Ok(None));
+
+        assert!(queue.publish_after().is_ok());
+        assert_eq!(queue.size(), 3);
+        let stats = queue.stats().unwrap();
+        assert_eq!((stats.pushed, stats.discarded), (4, 1));
+        assert_eq!(queue.pop().unwrap(), (1, 11));
+
+        assert!(queue.close().is_ok());
+        assert_eq!(queue.push_unique_by(key, (4, 0)), Err(QueueError::Closed));
+    }
+
+    #[test]
+    fn push_or_replace_latency_probe() {
+        let mut queue = ThreadSafeTypedQueue::<(u8, u32), SIZE>::default();
+        let key = |&(id, _): &(u8, u32)| id;
+        assert!(queue.enable_latency_probe(1).is_ok());
+        assert!(queue.push((1, 10)).is_ok());
+        assert!(queue.push((2, 20)).is_ok());
+
+        // Replacing the second element leaves the marker on the front one
+        assert_eq!(queue.push_or_replace_by(key, (2, 21)), Ok(Some((2, 20))));
+        assert_eq!(queue.pop().unwrap(), (1, 10));
+        assert_eq!(queue.latency_probe().unwrap().sample_count(), 1);
+
+        // The next marker lands on the element pushed after the replacement, not one early
+        assert!(queue.push((3, 30)).is_ok());
+        assert_eq!(queue.pop().unwrap(), (2, 21));
+        assert_eq!(queue.latency_probe().unwrap().sample_count(), 1);
+        assert_eq!(queue.pop().unwrap(), (3, 30));
+        assert_eq!(queue.latency_probe().unwrap().sample_count(), 2);
+    }
+
+    #[test]
+    fn push_dedup() {
+        let queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.push_dedup(&1), Ok(true));
+        assert_eq!(queue.push_dedup(&1), Ok(false));
+        assert!(queue.push_unpublished(2).is_ok());
+        assert_eq!(queue.push_dedup(&2), Ok(false));
+        assert_eq!(queue.push_dedup(&1), Ok(true));
+
+        assert!(queue.publish_after().is_ok());
+        assert_eq!(queue.size(), 3);
+        assert_eq!(queue.stats().unwrap().pushed, 3);
+    }
+
+    #[test]
+    fn prefault() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert!(queue.push(1).is_ok());
+        assert!(queue.prefault().is_ok());
+        assert_eq!(queue.pop().unwrap(), 1);
+    }
+
+    #[test]
+    fn debug() {
+        let queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert!((&queue).push(1).is_ok());
+        assert!((&queue).push(2).is_ok());
+        assert!(queue.push_unpublished(3).is_ok());
+        assert_eq!(format!("{:?}", queue), "[1, 2]");
+    }
+
+    #[test]
+    fn display() {
+        let queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert!((&queue).push(1).is_ok());
+        assert!((&queue).push(2).is_ok());
+        assert!(queue.push_unpublished(3).is_ok());
+        assert_eq!(queue.to_string(), "1, 2");
+        assert_eq!(format!("[{}]", queue.display_with(";")), "[1;2]");
+    }
+
+    #[test]
+    fn close() {
+        let queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert!((&queue).push(1).is_ok());
+        assert!(!queue.is_closed());
+
+        assert!(queue.close().is_ok());
+        assert!(queue.is_closed());
+        assert_eq!((&queue).push(2).unwrap_err(), QueueError::Closed);
+        assert_eq!((&queue).push_overwrite(2).unwrap_err(), QueueError::Closed);
+        assert_eq!(queue.push_slice(&[2]).unwrap_err(), QueueError::Closed);
+        assert_eq!(queue.push_unpublished(2).unwrap_err(), QueueError::Closed);
+        assert_eq!(queue.reserve(1).unwrap_err(), QueueError::Closed);
+
+        // Remaining elements can still be popped
+        assert_eq!((&queue).pop().unwrap(), 1);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn finish() {
+        let queue = Arc::new(ThreadSafeTypedQueue::<u32, SIZE>::default());
+        for n in 0..4 {
+            assert!((&*queue).push(n).is_ok());
+        }
+        assert_eq!((&*queue).pop().unwrap(), 0);
+        assert!(queue.push_unpublished(4).is_ok());
+
+        let producer = Arc::clone(&queue);
+        let (remaining, stats) = queue.finish().unwrap();
+        assert_eq!(remaining, vec![1, 2, 3, 4]);
+        assert_eq!(
+            stats,
+            QueueStats {
+                pushed: 5,
+                popped: 5,
+                discarded: 0,
+            }
+        );
+
+        assert!(queue.is_empty());
+        assert_eq!(queue.unpublished(), 0);
+        assert_eq!((&*producer).push(5).unwrap_err(), QueueError::Closed);
+        assert_eq!((&*queue).pop().unwrap_err(), QueueError::QueueEmpty);
+    }
+
+    #[test]
+    fn stats() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 + 1 {
+            assert!(queue.push_overwrite(n).is_ok());
+        }
+        assert!(queue.pop().is_ok());
+        assert!(queue.clear().is_ok());
+
+        assert_eq!(
+            queue.stats().unwrap(),
+            QueueStats {
+                pushed: SIZE as u64 + 1,
+                popped: 1,
+                discarded: SIZE as u64,
+            }
+        );
+    }
+
+    #[test]
+    fn push_evict() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 {
+            assert_eq!(queue.push_evict(n).unwrap(), None);
+        }
+
+        assert_eq!(queue.push_evict(100).unwrap(), Some(0));
+        assert_eq!(queue.stats().unwrap().discarded, 1);
+        for n in 1..SIZE as u32 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert_eq!(queue.pop().unwrap(), 100);
+
+        assert!(queue.close().is_ok());
+        assert_eq!(queue.push_evict(0).unwrap_err(), QueueError::Closed);
+    }
+
+    #[test]
+    fn zero_capacity_overwrite() {
+        let mut queue = ThreadSafeTypedQueue::<u32, 0>::default();
+        assert_eq!(queue.push_overwrite(1).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(queue.push_evict(1).unwrap_err(), QueueError::QueueFull);
+
+        // The lock is not poisoned and nothing was counted as discarded
+        assert_eq!(queue.stats().unwrap().discarded, 0);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn push_with() {
+        let mut queue = ThreadSafeTypedQueue::<[u32; 4], SIZE>::default();
+        for n in 0..SIZE as u32 {
+            let res = unsafe {
+                queue.push_with(|slot| {
+                    slot.write([n; 4]);
+                    Ok::<(), QueueError>(())
+                })
+            };
+            assert!(res.is_ok());
+        }
+        let res = unsafe { queue.push_with(|_| Ok::<(), QueueError>(())) };
+        assert_eq!(res.unwrap_err(), QueueError::QueueFull);
+        assert_eq!(queue.size(), SIZE);
+
+        for n in 0..SIZE as u32 {
+            assert_eq!(queue.pop().unwrap(), [n; 4]);
+        }
+    }
+
+    #[test]
+    fn claim_publish() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+
+        let mut slot = queue.claim().unwrap();
+        unsafe { slot.as_mut_ptr().write(1) };
+        assert!(queue.is_empty());
+        unsafe { slot.publish() };
+        assert_eq!(queue.size(), 1);
+
+        // Abandoned claims leave no trace
+        assert!(queue.claim().is_ok());
+        assert_eq!(queue.size(), 1);
+
+        for n in 2..SIZE as u32 + 1 {
+            queue.claim().unwrap().write(n);
+        }
+        assert_eq!(queue.claim().unwrap_err(), QueueError::QueueFull);
+        for n in 1..SIZE as u32 + 1 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert_eq!(queue.stats().unwrap().pushed, SIZE as u64);
+    }
+
+    #[test]
+    fn overflow_policy() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.overflow_policy(), OverflowPolicy::Reject);
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert_eq!(queue.push(100).unwrap_err(), QueueError::QueueFull);
+
+        queue.set_overflow_policy(OverflowPolicy::DropNewest);
+        assert!(queue.push(100).is_ok());
+        assert_eq!(queue.size(), SIZE);
+
+        queue.set_overflow_policy(OverflowPolicy::OverwriteOldest);
+        assert_eq!(queue.overflow_policy(), OverflowPolicy::OverwriteOldest);
+        assert!(queue.push(101).is_ok());
+        assert_eq!(queue.size(), SIZE);
+        for n in 1..SIZE as u32 {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert_eq!(queue.pop().unwrap(), 101);
+
+        let stats = queue.stats().unwrap();
+        assert_eq!((stats.pushed, stats.discarded), (SIZE as u64 + 2, 2));
+    }
+
+    #[test]
+    fn overflow_decimate() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        queue.set_overflow_policy(OverflowPolicy::Decimate);
+        assert_eq!(queue.overflow_policy(), OverflowPolicy::Decimate);
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+
+        assert!(queue.push(100).is_ok());
+        assert_eq!(queue.size(), SIZE / 2 + 1);
+        for n in 0..SIZE as u32 / 2 {
+            assert_eq!(queue.pop().unwrap(), n * 2);
+        }
+        assert_eq!(queue.pop().unwrap(), 100);
+
+        let stats = queue.stats().unwrap();
+        assert_eq!(stats.discarded, SIZE as u64 / 2);
+
+        // Unpublished elements are never discarded
+        for n in 0..SIZE as u32 {
+            assert!(queue.push_unpublished(n).is_ok());
+        }
+        assert_eq!(queue.push(100).unwrap_err(), QueueError::QueueFull);
+    }
+
+    #[test]
+    fn overflow_decimate_latency_probe() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        queue.set_overflow_policy(OverflowPolicy::Decimate);
+        for n in 0..10 {
+            assert!(queue.push(n).is_ok());
+        }
+        assert!(queue.enable_latency_probe(1).is_ok());
+        for n in 10..SIZE as u32 {
+            assert!(queue.push(n).is_ok());
+        }
+
+        // The marker on element 10 moves when decimating, so it is dropped rather than sampled
+        // when another element reaches its old position
+        assert!(queue.push(100).is_ok());
+        for n in 0..SIZE as u32 / 2 {
+            assert_eq!(queue.pop().unwrap(), n * 2);
+            assert_eq!(queue.latency_probe().unwrap().sample_count(), 0);
+        }
+
+        // Elements pushed after decimating are sampled again
+        assert_eq!(queue.pop().unwrap(), 100);
+        assert_eq!(queue.latency_probe().unwrap().sample_count(), 1);
+    }
+
+    #[test]
+    fn soft_capacity() {
+        let mut queue = ThreadSafeTypedQueue::<u32, SIZE>::default();
+        assert_eq!(queue.soft_capacity(), SIZE);
+        queue.set_soft_capacity(SIZE * 2);
+        assert_eq!(queue.soft_capacity(), SIZE);
+
+        queue.set_soft_capacity(SIZE / 2);
+        for n in 0..SIZE {
+            assert_eq!(queue.is_above_soft_limit(), n > SIZE / 2);
+            assert!(queue.push(n as u32).is_ok());
+        }
+
+        // Bursts may use the whole hard capacity
+        assert!(queue.is_above_soft_limit());
+        assert_eq!(queue.push(0).unwrap_err(), QueueError::QueueFull);
     }
 
     #[test]
@@ -0,0 +1,136 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::latency_probe::{self, PROBE_WINDOW};
+use crate::presets::Timestamped;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Wrapper timestamping every element on push and measuring how long it spent in the queue when
+// popped, keeping the most recent `PROBE_WINDOW` latencies for percentile estimates. Unlike the
+// sampling `LatencyProbe` of the thread-safe queue, every element is measured, at the cost of
+// storing its timestamp alongside it. Works with shared queues too, e.g.
+// `TimedQueue<T, &ThreadSafeTypedQueue<Timestamped<T>, N>>`, though each wrapper only sees the
+// latencies of the elements it pops itself.
+pub struct TimedQueue<T: Copy, Q: TypedQueue<Timestamped<T>>> {
+    queue: Q,
+    samples: BasicTypedQueue<Duration, PROBE_WINDOW>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy, Q: TypedQueue<Timestamped<T>>> TimedQueue<T, Q> {
+    /// Wrap a queue of timestamped elements.
+    pub fn new(queue: Q) -> Self {
+        TimedQueue {
+            queue,
+            samples: BasicTypedQueue::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Push an element to the queue, stamped with the current time. Fails if queue is full.
+    pub fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.queue.push(Timestamped::now(input))
+    }
+
+    /// Push an element to the queue, stamped with the current time. Overwrite the oldest value if
+    /// the queue is full.
+    pub fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.queue.push_overwrite(Timestamped::now(input))
+    }
+
+    /// Pop an element from the queue, together with the time it spent queued, which is added to
+    /// the rolling window. Fails if queue is empty.
+    pub fn pop(&mut self) -> Result<(T, Duration), QueueError> {
+        let element = self.queue.pop()?;
+        let latency = element.timestamp.elapsed();
+        let _ = self.samples.push_overwrite(latency);
+
+        Ok((element.value, latency))
+    }
+
+    /// Estimate the given percentile (0.0 to 1.0) of the recent time-in-queue latencies, or
+    /// `None` if no element has been popped yet.
+    pub fn latency(&self, percentile: f64) -> Option<Duration> {
+        latency_probe::window_percentile(&self.samples, percentile)
+    }
+
+    /// Estimate the median of the recent time-in-queue latencies.
+    pub fn p50(&self) -> Option<Duration> {
+        self.latency(0.5)
+    }
+
+    /// Estimate the 99th percentile of the recent time-in-queue latencies.
+    pub fn p99(&self) -> Option<Duration> {
+        self.latency(0.99)
+    }
+
+    /// Get the number of latency samples currently in the rolling window.
+    pub fn sample_count(&self) -> usize {
+        self.samples.size()
+    }
+
+    /// Get a reference to the underlying queue.
+    pub fn inner(&self) -> &Q {
+        &self.queue
+    }
+
+    /// Get a mutable reference to the underlying queue.
+    pub fn inner_mut(&mut self) -> &mut Q {
+        &mut self.queue
+    }
+
+    /// Unwrap the underlying queue.
+    pub fn into_inner(self) -> Q {
+        self.queue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimedQueue;
+    use crate::basic_typed_queue::BasicTypedQueue;
+    use crate::latency_probe::PROBE_WINDOW;
+    use crate::presets::Timestamped;
+    use crate::typed_queue::{QueueError, TypedQueue};
+    use std::thread;
+    use std::time::Duration;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn push_pop() {
+        let mut queue = TimedQueue::new(BasicTypedQueue::<Timestamped<u32>, SIZE>::default());
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(queue.p50(), None);
+
+        assert!(queue.push(1).is_ok());
+        thread::sleep(Duration::from_millis(5));
+        assert!(queue.push(2).is_ok());
+
+        let (value, latency) = queue.pop().unwrap();
+        assert_eq!(value, 1);
+        assert!(latency >= Duration::from_millis(5));
+        let (value, second) = queue.pop().unwrap();
+        assert_eq!(value, 2);
+        assert!(second < latency);
+
+        assert_eq!(queue.sample_count(), 2);
+        assert_eq!(queue.p50(), Some(latency));
+        assert_eq!(queue.latency(0.0), Some(second));
+        assert!(queue.inner().is_empty());
+    }
+
+    #[test]
+    fn rolling_window() {
+        let mut queue = TimedQueue::new(BasicTypedQueue::<Timestamped<u32>, SIZE>::default());
+        for n in 0..PROBE_WINDOW as u32 * 2 {
+            assert!(queue.push_overwrite(n).is_ok());
+            assert!(queue.pop().is_ok());
+        }
+
+        assert_eq!(queue.sample_count(), PROBE_WINDOW);
+        assert!(queue.p99().unwrap() >= queue.p50().unwrap());
+    }
+}
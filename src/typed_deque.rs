@@ -0,0 +1,230 @@
+use std::mem::MaybeUninit;
+
+use crate::basic_typed_queue::{BasicTypedQueue, Iter};
+use crate::storage::Storage;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Double-ended queue over the same fixed ring as `BasicTypedQueue`, allowing pushes and pops at
+// both ends, e.g. for work stealing or sliding windows. Elements are indexed from the front.
+// Through `TypedQueue` it behaves as a FIFO queue, pushing at the back and popping from the
+// front. Not thread-safe.
+pub struct TypedDeque<T: Copy, const CAPACITY: usize, S: Storage<T> = [MaybeUninit<T>; CAPACITY]> {
+    queue: BasicTypedQueue<T, CAPACITY, S>,
+}
+
+impl<T: Copy, const CAPACITY: usize> TypedDeque<T, CAPACITY> {
+    /// Create a new inline deque for the specified type and of the specified capacity.
+    pub fn new() -> Self {
+        TypedDeque {
+            queue: BasicTypedQueue::new(),
+        }
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> TypedDeque<T, CAPACITY, S> {
+    /// Create a new empty deque over the given storage. The capacity of the deque is the capacity
+    /// of the storage.
+    pub fn from_storage(storage: S) -> Self {
+        TypedDeque {
+            queue: BasicTypedQueue::from_storage(storage),
+        }
+    }
+
+    /// Push an element at the front. Fails if deque is full.
+    pub fn push_front(&mut self, input: T) -> Result<(), QueueError> {
+        self.queue.push_front(input)
+    }
+
+    /// Push an element at the back. Fails if deque is full.
+    pub fn push_back(&mut self, input: T) -> Result<(), QueueError> {
+        self.queue.push(input)
+    }
+
+    /// Push an element at the front, evicting the element at the back if the deque is full.
+    /// Returns the evicted element, if any. Fails only if the deque has no capacity at all.
+    pub fn push_front_evict(&mut self, input: T) -> Result<Option<T>, QueueError> {
+        let evicted = if self.queue.is_full() {
+            self.queue.pop_back().ok()
+        } else {
+            None
+        };
+        self.queue.push_front(input)?;
+
+        Ok(evicted)
+    }
+
+    /// Push an element at the back, evicting the element at the front if the deque is full.
+    /// Returns the evicted element, if any. Fails only if the deque has no capacity at all.
+    pub fn push_back_evict(&mut self, input: T) -> Result<Option<T>, QueueError> {
+        self.queue.push_evict(input)
+    }
+
+    /// Pop the element at the front. Fails if deque is empty.
+    pub fn pop_front(&mut self) -> Result<T, QueueError> {
+        self.queue.pop()
+    }
+
+    /// Pop the element at the back. Fails if deque is empty.
+    pub fn pop_back(&mut self) -> Result<T, QueueError> {
+        self.queue.pop_back()
+    }
+
+    /// Try to get an immutable reference to the element at the front.
+    pub fn front(&self) -> Result<&T, QueueError> {
+        self.queue.front()
+    }
+
+    /// Try to get an immutable reference to the element at the back.
+    pub fn back(&self) -> Result<&T, QueueError> {
+        self.queue.back()
+    }
+
+    /// Get a reference to the element at `index` from the front, if any.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.queue.get(index)
+    }
+
+    /// Get a mutable reference to the element at `index` from the front, if any.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.queue.get_mut(index)
+    }
+
+    /// Iterate over the elements from front to back.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.queue.iter()
+    }
+
+    /// Get the contents of the deque as a pair of slices from front to back.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        self.queue.as_slices()
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize> Default for TypedDeque<T, CAPACITY> {
+    fn default() -> Self {
+        TypedDeque::new()
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> TypedQueue<T> for TypedDeque<T, CAPACITY, S> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.queue.push(input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.queue.push_overwrite(input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        self.queue.push_ref(input)
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        self.queue.push_ref_overwrite(input)
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        self.queue.pop()
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        self.queue.pop_ref(output)
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        self.queue.clear()
+    }
+
+    fn is_full(&self) -> bool {
+        self.queue.is_full()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    fn size(&self) -> usize {
+        self.queue.size()
+    }
+
+    fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypedDeque;
+    use crate::typed_queue::{QueueError, TypedQueue};
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn both_ends() {
+        let mut deque = TypedDeque::<u32, SIZE>::default();
+        assert_eq!(deque.pop_back().unwrap_err(), QueueError::QueueEmpty);
+
+        // Pushing at the front wraps the head below zero straight away
+        assert!(deque.push_front(1).is_ok());
+        assert!(deque.push_front(0).is_ok());
+        assert!(deque.push_back(2).is_ok());
+        assert!(deque.push_back(3).is_ok());
+        assert_eq!(deque.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3]);
+        assert_eq!((*deque.front().unwrap(), *deque.back().unwrap()), (0, 3));
+        assert_eq!(deque.get(1), Some(&1));
+
+        assert_eq!(deque.pop_back().unwrap(), 3);
+        assert_eq!(deque.pop_front().unwrap(), 0);
+        assert_eq!(deque.pop_back().unwrap(), 2);
+        assert_eq!(deque.pop_back().unwrap(), 1);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn full() {
+        let mut deque = TypedDeque::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 / 2 {
+            assert!(deque.push_front(n).is_ok());
+            assert!(deque.push_back(n).is_ok());
+        }
+        assert_eq!(deque.push_front(0).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(deque.push_back(0).unwrap_err(), QueueError::QueueFull);
+
+        // Evicting pushes drop the element at the opposite end
+        assert_eq!(
+            deque.push_front_evict(100).unwrap(),
+            Some(SIZE as u32 / 2 - 1)
+        );
+        assert_eq!(deque.push_back_evict(101).unwrap(), Some(100));
+        assert_eq!(*deque.front().unwrap(), SIZE as u32 / 2 - 1);
+        assert_eq!(*deque.back().unwrap(), 101);
+
+        // Emptying from the back returns the elements in reverse order
+        let mut popped = Vec::new();
+        while let Ok(value) = deque.pop_back() {
+            popped.push(value);
+        }
+        assert_eq!(popped.len(), SIZE);
+        assert_eq!(popped[0], 101);
+        assert_eq!(popped[SIZE - 1], SIZE as u32 / 2 - 1);
+    }
+
+    #[test]
+    fn fifo_trait() {
+        let mut deque = TypedDeque::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 {
+            assert!(deque.push(n).is_ok());
+        }
+        assert!(deque.push_overwrite(SIZE as u32).is_ok());
+        for n in 1..=SIZE as u32 {
+            assert_eq!(deque.pop().unwrap(), n);
+        }
+
+        let mut deque = TypedDeque::<u32, 0>::default();
+        assert_eq!(
+            deque.push_front_evict(1).unwrap_err(),
+            QueueError::QueueFull
+        );
+    }
+}
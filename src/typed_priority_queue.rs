@@ -0,0 +1,266 @@
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+
+use crate::storage::Storage;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Fixed-capacity binary max-heap: pop returns the greatest element. Like `BasicTypedQueue`,
+// elements are stored inline by default and other backing stores can be used through the
+// `Storage` parameter, so nothing is allocated. Push and pop are O(log N). Equal elements are
+// popped in no particular order; `StablePriorityQueue` keeps FIFO order within a priority level
+// instead. Not thread-safe.
+pub struct TypedPriorityQueue<
+    T: Copy + Ord,
+    const CAPACITY: usize,
+    S: Storage<T> = [MaybeUninit<T>; CAPACITY],
+> {
+    size: usize,
+    storage: S,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy + Ord, const CAPACITY: usize> TypedPriorityQueue<T, CAPACITY> {
+    /// Create a new inline priority queue for the specified type and of the specified capacity.
+    pub fn new() -> Self {
+        TypedPriorityQueue::from_storage([MaybeUninit::uninit(); CAPACITY])
+    }
+}
+
+impl<T: Copy + Ord, const CAPACITY: usize, S: Storage<T>> TypedPriorityQueue<T, CAPACITY, S> {
+    /// Create a new empty priority queue over the given storage. The capacity of the queue is the
+    /// capacity of the storage.
+    pub fn from_storage(storage: S) -> Self {
+        TypedPriorityQueue {
+            size: 0,
+            storage,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Try to get an immutable reference to the greatest element, the one popped next.
+    pub fn peek(&self) -> Result<&T, QueueError> {
+        if self.size == 0 {
+            return Err(QueueError::QueueEmpty);
+        }
+
+        Ok(unsafe { self.storage.as_slice()[0].assume_init_ref() })
+    }
+
+    /// Push an element, evicting the smallest element if the queue is full. Returns the evicted
+    /// element, which is `input` itself if it is not greater than every queued element. Fails
+    /// only if the queue has no capacity at all.
+    pub fn push_evict(&mut self, input: T) -> Result<Option<T>, QueueError> {
+        if !self.is_full() {
+            self.push_ref(&input)?;
+            return Ok(None);
+        }
+        if self.size == 0 {
+            return Err(QueueError::QueueFull);
+        }
+
+        // The smallest element is a leaf, and leaves make up the second half of the heap
+        let (index, smallest) = (self.size / 2..self.size)
+            .map(|index| (index, self.get(index)))
+            .min_by_key(|&(_, value)| value)
+            .unwrap();
+        if input <= smallest {
+            return Ok(Some(input));
+        }
+
+        self.set(index, input);
+        self.sift_up(index);
+
+        Ok(Some(smallest))
+    }
+
+    /// Iterate over the elements in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.storage.as_slice()[..self.size]
+            .iter()
+            .map(|slot| unsafe { slot.assume_init_ref() })
+    }
+
+    fn get(&self, index: usize) -> T {
+        unsafe { self.storage.as_slice()[index].assume_init() }
+    }
+
+    fn set(&mut self, index: usize, value: T) {
+        self.storage.as_mut_slice()[index].write(value);
+    }
+
+    // Move the element at `index` up until its parent is not smaller.
+    fn sift_up(&mut self, mut index: usize) {
+        let value = self.get(index);
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            let parent_value = self.get(parent);
+            if parent_value >= value {
+                break;
+            }
+            self.set(index, parent_value);
+            index = parent;
+        }
+        self.set(index, value);
+    }
+
+    // Move the element at `index` down until neither of its children is greater.
+    fn sift_down(&mut self, mut index: usize) {
+        let value = self.get(index);
+        loop {
+            let left = 2 * index + 1;
+            if left >= self.size {
+                break;
+            }
+            let right = left + 1;
+            let child = if right < self.size && self.get(right) > self.get(left) {
+                right
+            } else {
+                left
+            };
+
+            let child_value = self.get(child);
+            if child_value <= value {
+                break;
+            }
+            self.set(index, child_value);
+            index = child;
+        }
+        self.set(index, value);
+    }
+}
+
+impl<T: Copy + Ord, const CAPACITY: usize> Default for TypedPriorityQueue<T, CAPACITY> {
+    fn default() -> Self {
+        TypedPriorityQueue::new()
+    }
+}
+
+// Overwriting pushes evict the smallest element rather than the oldest one.
+impl<T: Copy + Ord, const CAPACITY: usize, S: Storage<T>> TypedQueue<T>
+    for TypedPriorityQueue<T, CAPACITY, S>
+{
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_ref(&input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.push_evict(input).map(|_| ())
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        if self.is_full() {
+            return Err(QueueError::QueueFull);
+        }
+
+        self.set(self.size, *input);
+        self.size += 1;
+        self.sift_up(self.size - 1);
+
+        Ok(())
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        self.push_overwrite(*input)
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        let greatest = *self.peek()?;
+
+        self.size -= 1;
+        if self.size > 0 {
+            self.set(0, self.get(self.size));
+            self.sift_down(0);
+        }
+
+        Ok(greatest)
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        *output = self.pop()?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        self.size = 0;
+        Ok(())
+    }
+
+    fn is_full(&self) -> bool {
+        self.size == self.capacity()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn capacity(&self) -> usize {
+        self.storage.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypedPriorityQueue;
+    use crate::typed_queue::{QueueError, TypedQueue};
+    use std::mem::MaybeUninit;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn priority_order() {
+        let mut queue = TypedPriorityQueue::<u32, SIZE>::default();
+        assert_eq!(queue.pop().unwrap_err(), QueueError::QueueEmpty);
+
+        // Pseudo-random permutation of 0..SIZE
+        for n in 0..SIZE as u32 {
+            assert!(queue.push(n * 7 % SIZE as u32).is_ok());
+        }
+        assert_eq!(queue.push(0).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(*queue.peek().unwrap(), SIZE as u32 - 1);
+        assert_eq!(queue.iter().sum::<u32>(), (0..SIZE as u32).sum());
+
+        for n in (0..SIZE as u32).rev() {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn push_evict() {
+        let mut queue = TypedPriorityQueue::<u32, 4>::default();
+        for n in [5, 1, 9, 3] {
+            assert_eq!(queue.push_evict(n).unwrap(), None);
+        }
+
+        // The smallest element is evicted, unless the new one is even smaller
+        assert_eq!(queue.push_evict(4).unwrap(), Some(1));
+        assert_eq!(queue.push_evict(2).unwrap(), Some(2));
+        assert!(queue.push_overwrite(10).is_ok());
+        for n in [10, 9, 5, 4] {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+
+        let mut queue = TypedPriorityQueue::<u32, 0>::default();
+        assert_eq!(queue.push_evict(1).unwrap_err(), QueueError::QueueFull);
+    }
+
+    #[test]
+    fn slice_storage() {
+        let mut buffer = [MaybeUninit::uninit(); SIZE];
+        let mut queue = TypedPriorityQueue::<i32, 0, _>::from_storage(&mut buffer[..]);
+        assert_eq!(queue.capacity(), SIZE);
+
+        for n in [-3, 8, 0, 8, 2] {
+            assert!(queue.push(n).is_ok());
+        }
+        for n in [8, 8, 2, 0, -3] {
+            assert_eq!(queue.pop().unwrap(), n);
+        }
+        assert!(queue.clear().is_ok());
+    }
+}
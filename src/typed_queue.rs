@@ -1,3 +1,9 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::mem::MaybeUninit;
+use std::sync::PoisonError;
+
 // Trait for a fixed-capacity queue that stores with a generic type in FIFO fashion. Provides
 // overwriting and non-overwriting APIs.
 pub trait TypedQueue<T: Copy> {
@@ -19,6 +25,17 @@ pub trait TypedQueue<T: Copy> {
     /// Pop an element from the queue by reference. Fails if queue is empty.
     fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError>;
 
+    /// Pop an element from the queue into uninitialized storage, which is only written to (and
+    /// initialized) on success. Fails if queue is empty. The default implementation goes through
+    /// `pop()`, so implementations building `pop()` on this method must override it.
+    fn pop_into_uninit(&mut self, output: &mut MaybeUninit<T>) -> Result<(), QueueError> {
+        output.write(self.pop()?);
+        Ok(())
+    }
+
+    /// Remove all elements from the queue.
+    fn clear(&mut self) -> Result<(), QueueError>;
+
     /// Check if the queue is full.
     fn is_full(&self) -> bool;
 
@@ -32,8 +49,83 @@ pub trait TypedQueue<T: Copy> {
     fn capacity(&self) -> usize;
 }
 
-/// Enum indicating why a queue operation failed.
+// Push an element, popping the oldest ones into `evict` for as long as the queue is full, as
+// wrappers that keep or count evicted elements do. Fails with `QueueError::QueueFull` if the queue
+// is full but nothing can be popped, e.g. because the slots of a shared queue are held by
+// unpublished elements, rather than spinning until they are published.
+pub(crate) fn push_evicting<T: Copy, Q: TypedQueue<T>>(
+    queue: &mut Q,
+    input: &T,
+    mut evict: impl FnMut(T) -> Result<(), QueueError>,
+) -> Result<(), QueueError> {
+    loop {
+        match queue.push_ref(input) {
+            // Another consumer may have made room in the meantime, in which case the push is retried
+            Err(QueueError::QueueFull) if queue.capacity() > 0 => {
+                let evicted = queue.pop().map_err(|_| QueueError::QueueFull)?;
+                evict(evicted)?;
+            }
+            result => return result,
+        }
+    }
+}
+
+// Pop from `count` queues in turn, starting with the one at `*next`, until one of them yields an
+// element or fails for a reason other than being empty, as multiplexers serving several queues
+// do. `pop` returns `None` for an index without a queue. `*next` is then moved past the queue
+// served, so that it is tried last by the following call. Returns the index of that queue along
+// with its result. Fails with `QueueError::QueueEmpty` if every queue is empty.
+pub(crate) fn pop_round_robin<R>(
+    next: &mut usize,
+    count: usize,
+    mut pop: impl FnMut(usize) -> Option<Result<R, QueueError>>,
+) -> Result<(usize, R), QueueError> {
+    for offset in 0..count {
+        let index = (*next + offset) % count;
+        match pop(index) {
+            None | Some(Err(QueueError::QueueEmpty)) => continue,
+            Some(result) => {
+                *next = (index + 1) % count;
+                return result.map(|value| (index, value));
+            }
+        }
+    }
+
+    Err(QueueError::QueueEmpty)
+}
+
+/// What a queue's `push()` does when the queue is full.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// Fail with `QueueError::QueueFull`, leaving the queue unchanged.
+    #[default]
+    Reject,
+    /// Evict the oldest element to make room, as `push_overwrite()` does.
+    OverwriteOldest,
+    /// Silently discard the element being pushed, reporting success.
+    DropNewest,
+    /// Discard every other element, starting with the second oldest, to make room. The queue
+    /// keeps covering the whole time span of its contents at a lower resolution, for sampling
+    /// workloads where gaps are worse than a reduced rate. Each decimation halves the resolution
+    /// of the elements already queued, so the oldest ones are the most thinned out.
+    Decimate,
+}
+
+// Lifetime counters of a queue, kept by thread-safe and instrumented queues.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct QueueStats {
+    /// Number of elements pushed, including ones later discarded.
+    pub pushed: u64,
+    /// Number of elements popped by consumers.
+    pub popped: u64,
+    /// Number of elements discarded by overwriting pushes or `clear()`.
+    pub discarded: u64,
+}
+
+/// Enum indicating why a queue operation failed. New variants may be added as queue subsystems
+/// grow, so matches must include a wildcard arm.
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum QueueError {
     /// The pop operation has failed due to the queue being empty.
     QueueEmpty,
@@ -41,4 +133,118 @@ pub enum QueueError {
     QueueFull,
     /// Another thread panicked while holding the queue's mutex.
     MutexPoisoned,
+    /// A blocking operation did not complete before its deadline.
+    Timeout,
+    /// The queue has been closed and accepts no further elements.
+    Closed,
+    /// The operation would have to block, but was requested not to.
+    WouldBlock,
+    /// An I/O operation on the queue's backing store failed with the given kind of error. Unlike
+    /// most error wrappers, this only keeps the kind of the original `io::Error`, which is dropped
+    /// along with its message and source, so that `QueueError` stays comparable and cheap to
+    /// clone; log the `io::Error` before converting it where those details matter.
+    Io(io::ErrorKind),
+    /// The element in the queue is not of the requested type.
+    TypeMismatch,
+    /// There is no queue to route the element to or from, e.g. because its topic is not routed.
+    NoRoute,
+    /// The element cannot be ordered against others, e.g. a floating-point NaN.
+    Unordered,
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueError::QueueEmpty => write!(f, "queue is empty"),
+            QueueError::QueueFull => write!(f, "queue is full"),
+            QueueError::MutexPoisoned => write!(f, "queue mutex is poisoned"),
+            QueueError::Timeout => write!(f, "queue operation timed out"),
+            QueueError::Closed => write!(f, "queue is closed"),
+            QueueError::WouldBlock => write!(f, "queue operation would block"),
+            QueueError::Io(kind) => write!(f, "queue I/O error: {}", kind),
+            QueueError::TypeMismatch => write!(f, "queue element has a different type"),
+            QueueError::NoRoute => write!(f, "no queue to route to"),
+            QueueError::Unordered => write!(f, "queue element cannot be ordered"),
+        }
+    }
+}
+
+impl Error for QueueError {}
+
+impl<G> From<PoisonError<G>> for QueueError {
+    fn from(_: PoisonError<G>) -> Self {
+        QueueError::MutexPoisoned
+    }
+}
+
+// Keeps only the kind of the error, see `QueueError::Io`.
+impl From<io::Error> for QueueError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::TimedOut => QueueError::Timeout,
+            io::ErrorKind::WouldBlock => QueueError::WouldBlock,
+            kind => QueueError::Io(kind),
+        }
+    }
+}
+
+impl From<QueueError> for io::Error {
+    fn from(error: QueueError) -> Self {
+        let kind = match error {
+            QueueError::Timeout => io::ErrorKind::TimedOut,
+            QueueError::WouldBlock | QueueError::QueueEmpty | QueueError::QueueFull => {
+                io::ErrorKind::WouldBlock
+            }
+            QueueError::Closed => io::ErrorKind::BrokenPipe,
+            QueueError::Io(kind) => kind,
+            QueueError::MutexPoisoned => io::ErrorKind::Other,
+            QueueError::TypeMismatch => io::ErrorKind::InvalidData,
+            QueueError::NoRoute => io::ErrorKind::NotFound,
+            QueueError::Unordered => io::ErrorKind::InvalidInput,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueueError;
+    use std::io;
+    use std::sync::Mutex;
+
+    #[test]
+    fn display() {
+        assert_eq!(QueueError::QueueFull.to_string(), "queue is full");
+        assert_eq!(
+            QueueError::Io(io::ErrorKind::NotFound).to_string(),
+            format!("queue I/O error: {}", io::ErrorKind::NotFound)
+        );
+    }
+
+    #[test]
+    fn from_poison() {
+        let mutex = Mutex::new(0);
+        let _ = std::thread::scope(|s| {
+            s.spawn(|| {
+                let _guard = mutex.lock().unwrap();
+                panic!("poison the mutex");
+            })
+            .join()
+        });
+
+        let error: QueueError = mutex.lock().unwrap_err().into();
+        assert_eq!(error, QueueError::MutexPoisoned);
+    }
+
+    #[test]
+    fn io_round_trip() {
+        let error: QueueError = io::Error::from(io::ErrorKind::TimedOut).into();
+        assert_eq!(error, QueueError::Timeout);
+
+        let error: QueueError = io::Error::from(io::ErrorKind::NotFound).into();
+        assert_eq!(error, QueueError::Io(io::ErrorKind::NotFound));
+
+        let error: io::Error = QueueError::Closed.into();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+    }
 }
@@ -0,0 +1,161 @@
+use std::mem::MaybeUninit;
+
+use crate::basic_typed_queue::{BasicTypedQueue, Iter};
+use crate::storage::Storage;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Fixed-capacity LIFO stack, the sibling of `BasicTypedQueue` for last-in first-out use. It is
+// built on the same ring, so that overwriting pushes can evict the bottom element in O(1).
+// Through `TypedQueue`, `pop()` returns the newest element. Not thread-safe.
+pub struct TypedStack<T: Copy, const CAPACITY: usize, S: Storage<T> = [MaybeUninit<T>; CAPACITY]> {
+    ring: BasicTypedQueue<T, CAPACITY, S>,
+}
+
+impl<T: Copy, const CAPACITY: usize> TypedStack<T, CAPACITY> {
+    /// Create a new inline stack for the specified type and of the specified capacity.
+    pub fn new() -> Self {
+        TypedStack {
+            ring: BasicTypedQueue::new(),
+        }
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> TypedStack<T, CAPACITY, S> {
+    /// Create a new empty stack over the given storage. The capacity of the stack is the capacity
+    /// of the storage.
+    pub fn from_storage(storage: S) -> Self {
+        TypedStack {
+            ring: BasicTypedQueue::from_storage(storage),
+        }
+    }
+
+    /// Try to get an immutable reference to the top element, the one popped next.
+    pub fn peek(&self) -> Result<&T, QueueError> {
+        self.ring.back()
+    }
+
+    /// Try to get a mutable reference to the top element.
+    pub fn peek_mut(&mut self) -> Result<&mut T, QueueError> {
+        let top = self
+            .ring
+            .size()
+            .checked_sub(1)
+            .ok_or(QueueError::QueueEmpty)?;
+        self.ring.get_mut(top).ok_or(QueueError::QueueEmpty)
+    }
+
+    /// Iterate over the elements from the bottom to the top of the stack.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.ring.iter()
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize> Default for TypedStack<T, CAPACITY> {
+    fn default() -> Self {
+        TypedStack::new()
+    }
+}
+
+// Overwriting pushes evict the bottom element, the oldest one.
+impl<T: Copy, const CAPACITY: usize, S: Storage<T>> TypedQueue<T> for TypedStack<T, CAPACITY, S> {
+    fn push(&mut self, input: T) -> Result<(), QueueError> {
+        self.ring.push(input)
+    }
+
+    fn push_overwrite(&mut self, input: T) -> Result<(), QueueError> {
+        self.ring.push_overwrite(input)
+    }
+
+    fn push_ref(&mut self, input: &T) -> Result<(), QueueError> {
+        self.ring.push_ref(input)
+    }
+
+    fn push_ref_overwrite(&mut self, input: &T) -> Result<(), QueueError> {
+        self.ring.push_ref_overwrite(input)
+    }
+
+    fn pop(&mut self) -> Result<T, QueueError> {
+        self.ring.pop_back()
+    }
+
+    fn pop_ref(&mut self, output: &mut T) -> Result<(), QueueError> {
+        *output = self.pop()?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), QueueError> {
+        self.ring.clear()
+    }
+
+    fn is_full(&self) -> bool {
+        self.ring.is_full()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    fn size(&self) -> usize {
+        self.ring.size()
+    }
+
+    fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypedStack;
+    use crate::typed_queue::{QueueError, TypedQueue};
+    use std::mem::MaybeUninit;
+
+    // Arbitrary stack size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn lifo() {
+        let mut stack = TypedStack::<u32, SIZE>::default();
+        assert_eq!(stack.pop().unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(stack.peek().unwrap_err(), QueueError::QueueEmpty);
+
+        for n in 0..SIZE as u32 {
+            assert!(stack.push(n).is_ok());
+        }
+        assert_eq!(stack.push(0).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(*stack.peek().unwrap(), SIZE as u32 - 1);
+
+        *stack.peek_mut().unwrap() = 100;
+        assert_eq!(stack.pop().unwrap(), 100);
+        for n in (0..SIZE as u32 - 1).rev() {
+            assert_eq!(stack.pop().unwrap(), n);
+        }
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn push_overwrite() {
+        let mut stack = TypedStack::<u32, SIZE>::default();
+        for n in 0..SIZE as u32 + 2 {
+            assert!(stack.push_overwrite(n).is_ok());
+        }
+
+        // The two bottom elements were evicted
+        assert_eq!(stack.size(), SIZE);
+        assert_eq!(stack.iter().next(), Some(&2));
+        assert_eq!(stack.pop().unwrap(), SIZE as u32 + 1);
+
+        assert!(stack.clear().is_ok());
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn slice_storage() {
+        let mut buffer = [MaybeUninit::uninit(); SIZE];
+        let mut stack = TypedStack::<u32, 0, _>::from_storage(&mut buffer[..]);
+        assert_eq!(stack.capacity(), SIZE);
+        assert!(stack.push(1).is_ok());
+        assert!(stack.push(2).is_ok());
+        assert_eq!(stack.pop().unwrap(), 2);
+    }
+}
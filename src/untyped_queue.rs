@@ -0,0 +1,180 @@
+use std::any::Any;
+
+use crate::basic_typed_queue::BasicTypedQueue;
+use crate::typed_queue::{QueueError, TypedQueue};
+
+// Owning pointer to a boxed message. Queues require `Copy` elements, so messages are stored as
+// raw pointers and only turned back into boxes once popped, or when the queue is dropped.
+#[derive(Copy, Clone)]
+struct AnyHandle(*mut (dyn Any + Send));
+
+// Type-erased queue of up to `N` messages of any type, for plugin-style systems where
+// heterogeneous messages flow through one queue. Each message is boxed on push, and consumers
+// either pop it as the type they expect with `pop_as()` or inspect the box themselves. Messages
+// still queued when the queue is dropped are dropped with it. Not thread-safe, but may be moved
+// to another thread.
+pub struct UntypedQueue<const N: usize> {
+    queue: BasicTypedQueue<AnyHandle, N>,
+}
+
+// The queue owns its messages, which are all `Send`.
+unsafe impl<const N: usize> Send for UntypedQueue<N> {}
+
+impl<const N: usize> UntypedQueue<N> {
+    /// Create a new untyped queue.
+    pub fn new() -> Self {
+        UntypedQueue {
+            queue: BasicTypedQueue::new(),
+        }
+    }
+
+    /// Push a message of any type. Fails if queue is full.
+    pub fn push_any<T: Any + Send>(&mut self, value: T) -> Result<(), QueueError> {
+        self.push_boxed(Box::new(value))
+    }
+
+    /// Push an already boxed message. Fails if queue is full, in which case the message is
+    /// dropped.
+    pub fn push_boxed(&mut self, value: Box<dyn Any + Send>) -> Result<(), QueueError> {
+        if self.queue.is_full() {
+            return Err(QueueError::QueueFull);
+        }
+
+        self.queue.push(AnyHandle(Box::into_raw(value)))
+    }
+
+    /// Pop the oldest message if it is of type `T`. Fails if queue is empty, or with
+    /// `QueueError::TypeMismatch` if the message is of another type, leaving it in the queue.
+    pub fn pop_as<T: Any>(&mut self) -> Result<T, QueueError> {
+        if !self.front_is::<T>() {
+            return if self.queue.is_empty() {
+                Err(QueueError::QueueEmpty)
+            } else {
+                Err(QueueError::TypeMismatch)
+            };
+        }
+
+        let message = self.pop()?;
+        message
+            .downcast::<T>()
+            .map(|value| *value)
+            .map_err(|_| QueueError::TypeMismatch)
+    }
+
+    /// Pop the oldest message, whatever its type. Fails if queue is empty.
+    pub fn pop(&mut self) -> Result<Box<dyn Any + Send>, QueueError> {
+        let handle = self.queue.pop()?;
+        Ok(unsafe { Box::from_raw(handle.0) })
+    }
+
+    /// Check if the oldest message is of type `T`. Returns `false` if queue is empty.
+    pub fn front_is<T: Any>(&self) -> bool {
+        self.queue
+            .front()
+            .is_ok_and(|handle| unsafe { &*handle.0 }.is::<T>())
+    }
+
+    /// Drop every message in the queue.
+    pub fn clear(&mut self) {
+        while let Ok(message) = self.pop() {
+            drop(message);
+        }
+    }
+
+    /// Check if the queue is full.
+    pub fn is_full(&self) -> bool {
+        self.queue.is_full()
+    }
+
+    /// Check if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Get the number of messages in the queue.
+    pub fn size(&self) -> usize {
+        self.queue.size()
+    }
+
+    /// Get the maximum number of messages the queue can hold.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<const N: usize> Default for UntypedQueue<N> {
+    fn default() -> Self {
+        UntypedQueue::new()
+    }
+}
+
+impl<const N: usize> Drop for UntypedQueue<N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UntypedQueue;
+    use crate::typed_queue::QueueError;
+    use std::sync::Arc;
+    use std::thread;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 4;
+
+    #[derive(Debug, PartialEq)]
+    struct Reading {
+        sensor: u8,
+        value: f32,
+    }
+
+    #[test]
+    fn heterogeneous() {
+        let mut queue = UntypedQueue::<SIZE>::default();
+        assert!(queue.push_any(7u32).is_ok());
+        assert!(queue.push_any(String::from("hello")).is_ok());
+        assert!(queue
+            .push_any(Reading {
+                sensor: 1,
+                value: 0.5
+            })
+            .is_ok());
+
+        // A message popped as the wrong type stays in the queue
+        assert_eq!(queue.pop_as::<String>(), Err(QueueError::TypeMismatch));
+        assert!(queue.front_is::<u32>());
+        assert_eq!(queue.pop_as::<u32>(), Ok(7));
+        assert_eq!(queue.pop_as::<String>().unwrap(), "hello");
+
+        let message = queue.pop().unwrap();
+        assert_eq!(
+            message.downcast_ref::<Reading>(),
+            Some(&Reading {
+                sensor: 1,
+                value: 0.5
+            })
+        );
+        assert_eq!(queue.pop_as::<u32>(), Err(QueueError::QueueEmpty));
+    }
+
+    #[test]
+    fn full_and_drop() {
+        let shared = Arc::new(0);
+        let mut queue = UntypedQueue::<SIZE>::default();
+        for _ in 0..SIZE {
+            assert!(queue.push_any(Arc::clone(&shared)).is_ok());
+        }
+        assert!(queue.is_full());
+        assert_eq!(
+            queue.push_any(Arc::clone(&shared)),
+            Err(QueueError::QueueFull)
+        );
+        assert_eq!(Arc::strong_count(&shared), SIZE + 1);
+
+        // Messages are dropped with the queue, even on another thread
+        thread::spawn(move || drop(queue)).join().unwrap();
+        assert_eq!(Arc::strong_count(&shared), 1);
+    }
+}
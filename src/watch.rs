@@ -0,0 +1,166 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::typed_queue::QueueError;
+
+// Single-value slot holding only the latest value written, the degenerate capacity-1 case of an
+// overwriting queue. Every write bumps a version number, which watchers compare with the last
+// version they saw to check for changes without taking the lock. Thread-safe.
+pub struct Watch<T: Copy> {
+    value: Mutex<Option<T>>,
+    version: AtomicU64, // number of writes so far, only incremented while holding the lock
+}
+
+impl<T: Copy> Watch<T> {
+    /// Create an empty slot.
+    pub fn new() -> Self {
+        Watch {
+            value: Mutex::new(None),
+            version: AtomicU64::new(0),
+        }
+    }
+
+    /// Create a slot holding an initial value, which watchers created later do not see as a
+    /// change.
+    pub fn with_value(value: T) -> Self {
+        Watch {
+            value: Mutex::new(Some(value)),
+            version: AtomicU64::new(0),
+        }
+    }
+
+    /// Replace the value, returning the previous one, if any.
+    pub fn set(&self, value: T) -> Result<Option<T>, QueueError> {
+        let mut guard = self.value.lock()?;
+        let previous = guard.replace(value);
+        self.version.fetch_add(1, Ordering::Release);
+
+        Ok(previous)
+    }
+
+    /// Get the latest value, if any has been set.
+    pub fn get(&self) -> Result<Option<T>, QueueError> {
+        Ok(*self.value.lock()?)
+    }
+
+    /// Get the number of times the value has been set.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// Create a watcher that considers the current value as already seen.
+    pub fn watcher(&self) -> Watcher<'_, T> {
+        Watcher {
+            watch: self,
+            seen: self.version(),
+        }
+    }
+}
+
+impl<T: Copy> Default for Watch<T> {
+    fn default() -> Self {
+        Watch::new()
+    }
+}
+
+// Handle tracking which version of a `Watch` value its owner last saw.
+pub struct Watcher<'a, T: Copy> {
+    watch: &'a Watch<T>,
+    seen: u64,
+}
+
+impl<'a, T: Copy> Watcher<'a, T> {
+    /// Check if the value has been set since this watcher last saw it, without taking the lock.
+    pub fn has_changed(&self) -> bool {
+        self.watch.version() != self.seen
+    }
+
+    /// Get the latest value if it has been set since this watcher last saw it, marking it as
+    /// seen. Intermediate values set in between are not observed.
+    pub fn changed(&mut self) -> Result<Option<T>, QueueError> {
+        if !self.has_changed() {
+            return Ok(None);
+        }
+
+        self.get_and_update()
+    }
+
+    /// Get the latest value, if any, marking it as seen.
+    pub fn get_and_update(&mut self) -> Result<Option<T>, QueueError> {
+        let guard = self.watch.value.lock()?;
+        // The version only changes while the lock is held, so it matches the value read
+        self.seen = self.watch.version();
+
+        Ok(*guard)
+    }
+
+    /// Get the number of versions set since this watcher last saw the value.
+    pub fn missed(&self) -> u64 {
+        self.watch.version() - self.seen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Watch;
+    use std::thread;
+
+    #[test]
+    fn versions() {
+        let watch = Watch::<u32>::default();
+        let mut watcher = watch.watcher();
+        assert_eq!(watch.get(), Ok(None));
+        assert!(!watcher.has_changed());
+        assert_eq!(watcher.changed(), Ok(None));
+
+        assert_eq!(watch.set(1), Ok(None));
+        assert_eq!(watch.set(2), Ok(Some(1)));
+        assert_eq!(watch.version(), 2);
+
+        // Only the latest value is seen
+        assert!(watcher.has_changed());
+        assert_eq!(watcher.missed(), 2);
+        assert_eq!(watcher.changed(), Ok(Some(2)));
+        assert!(!watcher.has_changed());
+        assert_eq!(watcher.changed(), Ok(None));
+        assert_eq!(watcher.get_and_update(), Ok(Some(2)));
+    }
+
+    #[test]
+    fn initial_value() {
+        let watch = Watch::with_value(7u32);
+        let mut watcher = watch.watcher();
+        assert!(!watcher.has_changed());
+        assert_eq!(watcher.get_and_update(), Ok(Some(7)));
+
+        // Writing an equal value is still a change
+        assert!(watch.set(7).is_ok());
+        assert_eq!(watcher.changed(), Ok(Some(7)));
+    }
+
+    #[test]
+    fn concurrent_watchers() {
+        const COUNT: u32 = 10_000;
+        let watch = Watch::<u32>::default();
+
+        thread::scope(|scope| {
+            for _ in 0..2 {
+                let mut watcher = watch.watcher();
+                scope.spawn(move || {
+                    // Values are only ever seen moving forward
+                    let mut last = 0;
+                    while last != COUNT {
+                        if let Some(value) = watcher.changed().unwrap() {
+                            assert!(value > last);
+                            last = value;
+                        }
+                    }
+                });
+            }
+
+            for n in 1..=COUNT {
+                assert!(watch.set(n).is_ok());
+            }
+        });
+    }
+}
@@ -0,0 +1,271 @@
+use std::array;
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{fence, AtomicBool, AtomicIsize, Ordering};
+
+use crate::typed_queue::QueueError;
+
+// Fixed-capacity work-stealing deque (Chase-Lev). A single owner pushes and pops tasks at the
+// bottom, LIFO, while any number of stealers take tasks from the top, FIFO, without locks. The
+// owner and the stealers only race for the last remaining task, which a compare-and-swap on the
+// top index settles. Pushes fail when the deque is full instead of growing the buffer.
+pub struct WorkStealingDeque<T: Copy, const CAPACITY: usize> {
+    slots: [UnsafeCell<MaybeUninit<T>>; CAPACITY],
+    top: AtomicIsize,    // index of the next task to be stolen
+    bottom: AtomicIsize, // index of the next task to be pushed by the owner
+    owner_taken: AtomicBool,
+}
+
+// Tasks are only ever copied in and out of the slots, and a stealer only keeps a copy once its
+// compare-and-swap proves that the slot was not reused in the meantime.
+unsafe impl<T: Copy + Send, const CAPACITY: usize> Sync for WorkStealingDeque<T, CAPACITY> {}
+
+impl<T: Copy, const CAPACITY: usize> WorkStealingDeque<T, CAPACITY> {
+    /// Create a new deque for the specified type and capacity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `CAPACITY` is zero.
+    pub fn new() -> Self {
+        assert!(
+            CAPACITY > 0,
+            "work-stealing deque capacity must be non-zero"
+        );
+
+        WorkStealingDeque {
+            slots: array::from_fn(|_| UnsafeCell::new(MaybeUninit::uninit())),
+            top: AtomicIsize::new(0),
+            bottom: AtomicIsize::new(0),
+            owner_taken: AtomicBool::new(false),
+        }
+    }
+
+    /// Take the owner handle. Returns `None` if another owner handle is still alive.
+    pub fn worker(&self) -> Option<Worker<'_, T, CAPACITY>> {
+        if self.owner_taken.swap(true, Ordering::Acquire) {
+            None
+        } else {
+            Some(Worker { deque: self })
+        }
+    }
+
+    /// Create a handle stealing tasks from the top of the deque. Any number of stealers may
+    /// exist at once.
+    pub fn stealer(&self) -> Stealer<'_, T, CAPACITY> {
+        Stealer { deque: self }
+    }
+
+    /// Get the number of tasks in the deque. Only a snapshot, as stealers and the owner may be
+    /// changing it concurrently.
+    pub fn size(&self) -> usize {
+        let bottom = self.bottom.load(Ordering::Relaxed);
+        let top = self.top.load(Ordering::Relaxed);
+        (bottom - top).max(0) as usize
+    }
+
+    /// Check if the deque holds no tasks, with the same caveat as `size()`.
+    pub fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    fn slot(&self, index: isize) -> *mut MaybeUninit<T> {
+        self.slots[index as usize % CAPACITY].get()
+    }
+}
+
+impl<T: Copy, const CAPACITY: usize> Default for WorkStealingDeque<T, CAPACITY> {
+    fn default() -> Self {
+        WorkStealingDeque::new()
+    }
+}
+
+// Exclusive owner access to the bottom of a work-stealing deque. Dropping it allows a new owner
+// to be taken.
+pub struct Worker<'a, T: Copy, const CAPACITY: usize> {
+    deque: &'a WorkStealingDeque<T, CAPACITY>,
+}
+
+impl<'a, T: Copy, const CAPACITY: usize> Worker<'a, T, CAPACITY> {
+    /// Push a task at the bottom. Fails if deque is full.
+    pub fn push(&mut self, input: T) -> Result<(), QueueError> {
+        let bottom = self.deque.bottom.load(Ordering::Relaxed);
+        let top = self.deque.top.load(Ordering::Acquire);
+        if bottom - top >= CAPACITY as isize {
+            return Err(QueueError::QueueFull);
+        }
+
+        // Stealers may copy a stale slot concurrently, but then fail their compare-and-swap
+        unsafe { ptr::write_volatile(self.deque.slot(bottom), MaybeUninit::new(input)) };
+        fence(Ordering::Release);
+        self.deque.bottom.store(bottom + 1, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// Pop the most recently pushed task. Fails if deque is empty, including when a stealer won
+    /// the race for the last task.
+    pub fn pop(&mut self) -> Result<T, QueueError> {
+        let bottom = self.deque.bottom.load(Ordering::Relaxed) - 1;
+        self.deque.bottom.store(bottom, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        let top = self.deque.top.load(Ordering::Relaxed);
+
+        if top > bottom {
+            // Already empty
+            self.deque.bottom.store(bottom + 1, Ordering::Relaxed);
+            return Err(QueueError::QueueEmpty);
+        }
+
+        let value = unsafe { ptr::read_volatile(self.deque.slot(bottom)).assume_init() };
+        if top < bottom {
+            return Ok(value);
+        }
+
+        // Last task: race the stealers for it
+        let won = self
+            .deque
+            .top
+            .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+            .is_ok();
+        self.deque.bottom.store(bottom + 1, Ordering::Relaxed);
+
+        if won {
+            Ok(value)
+        } else {
+            Err(QueueError::QueueEmpty)
+        }
+    }
+
+    /// Get a stealer for the same deque, e.g. to hand to other threads.
+    pub fn stealer(&self) -> Stealer<'a, T, CAPACITY> {
+        self.deque.stealer()
+    }
+}
+
+impl<'a, T: Copy, const CAPACITY: usize> Drop for Worker<'a, T, CAPACITY> {
+    fn drop(&mut self) {
+        self.deque.owner_taken.store(false, Ordering::Release);
+    }
+}
+
+// Handle taking tasks from the top of a work-stealing deque, i.e. the oldest ones.
+#[derive(Copy, Clone)]
+pub struct Stealer<'a, T: Copy, const CAPACITY: usize> {
+    deque: &'a WorkStealingDeque<T, CAPACITY>,
+}
+
+impl<'a, T: Copy, const CAPACITY: usize> Stealer<'a, T, CAPACITY> {
+    /// Steal the oldest task, retrying while other threads win the race for it. Fails if deque
+    /// is empty.
+    pub fn steal(&self) -> Result<T, QueueError> {
+        loop {
+            let top = self.deque.top.load(Ordering::Acquire);
+            fence(Ordering::SeqCst);
+            let bottom = self.deque.bottom.load(Ordering::Acquire);
+            if top >= bottom {
+                return Err(QueueError::QueueEmpty);
+            }
+
+            let value = unsafe { ptr::read_volatile(self.deque.slot(top)) };
+            if self
+                .deque
+                .top
+                .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                return Ok(unsafe { value.assume_init() });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WorkStealingDeque;
+    use crate::typed_queue::QueueError;
+    use std::sync::Mutex;
+    use std::thread;
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    #[test]
+    fn both_ends() {
+        let deque = WorkStealingDeque::<u32, SIZE>::default();
+        let mut worker = deque.worker().unwrap();
+        let stealer = worker.stealer();
+        assert_eq!(worker.pop().unwrap_err(), QueueError::QueueEmpty);
+        assert_eq!(stealer.steal().unwrap_err(), QueueError::QueueEmpty);
+
+        for n in 0..SIZE as u32 {
+            assert!(worker.push(n).is_ok());
+        }
+        assert_eq!(worker.push(0).unwrap_err(), QueueError::QueueFull);
+        assert_eq!(deque.size(), SIZE);
+
+        // The owner works LIFO, stealers take the oldest tasks
+        assert_eq!(worker.pop().unwrap(), SIZE as u32 - 1);
+        assert_eq!(stealer.steal().unwrap(), 0);
+        assert_eq!(stealer.steal().unwrap(), 1);
+        assert_eq!(worker.pop().unwrap(), SIZE as u32 - 2);
+
+        // Slots are reused once stolen
+        assert!(worker.push(100).is_ok());
+        assert!(worker.push(101).is_ok());
+        assert_eq!(deque.size(), SIZE - 2);
+    }
+
+    #[test]
+    fn single_owner() {
+        let deque = WorkStealingDeque::<u32, SIZE>::default();
+        let worker = deque.worker();
+        assert!(worker.is_some());
+        assert!(deque.worker().is_none());
+
+        drop(worker);
+        assert!(deque.worker().is_some());
+    }
+
+    #[test]
+    fn concurrent_stealers() {
+        const COUNT: u32 = 10_000;
+        let deque = WorkStealingDeque::<u32, SIZE>::default();
+        let done = Mutex::new(Vec::new());
+
+        thread::scope(|scope| {
+            let mut worker = deque.worker().unwrap();
+            for _ in 0..3 {
+                let stealer = worker.stealer();
+                let done = &done;
+                scope.spawn(move || {
+                    let mut stolen = Vec::new();
+                    while done.lock().unwrap().len() < COUNT as usize {
+                        if let Ok(value) = stealer.steal() {
+                            stolen.push(value);
+                        }
+                        if !stolen.is_empty() {
+                            done.lock().unwrap().append(&mut stolen);
+                        }
+                    }
+                });
+            }
+
+            for n in 0..COUNT {
+                while worker.push(n).is_err() {
+                    if let Ok(value) = worker.pop() {
+                        done.lock().unwrap().push(value);
+                    }
+                }
+            }
+            while let Ok(value) = worker.pop() {
+                done.lock().unwrap().push(value);
+            }
+        });
+
+        // Every task was taken exactly once
+        let mut done = done.into_inner().unwrap();
+        done.sort_unstable();
+        assert_eq!(done, (0..COUNT).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,283 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+use crate::typed_queue::TypedQueue;
+
+/// Scaling parameters for a `WorkerPool`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PoolConfig {
+    /// Number of workers kept running even while the queue is idle.
+    pub min_workers: usize,
+    /// Upper limit on the number of workers.
+    pub max_workers: usize,
+    /// Fraction of the capacity (0.0 to 1.0) above which a worker is added.
+    pub scale_up_at: f64,
+    /// Fraction of the capacity (0.0 to 1.0) below which a worker is retired.
+    pub scale_down_at: f64,
+    /// How often occupancy is checked, and how long an idle worker waits before polling again.
+    pub poll_interval: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            min_workers: 1,
+            max_workers: 4,
+            scale_up_at: 0.75,
+            scale_down_at: 0.25,
+            poll_interval: Duration::from_millis(1),
+        }
+    }
+}
+
+// Consumer thread of a pool. A retired worker exits before its next pop, while on shutdown every
+// worker keeps popping until it finds the queue empty.
+struct Worker {
+    retired: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl Worker {
+    fn spawn<T, F, const CAPACITY: usize>(
+        queue: Arc<ThreadSafeTypedQueue<T, CAPACITY>>,
+        handler: Arc<F>,
+        shutdown: Arc<AtomicBool>,
+        poll_interval: Duration,
+    ) -> Self
+    where
+        T: Copy + Send + 'static,
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        let retired = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let retired = Arc::clone(&retired);
+            thread::spawn(move || {
+                while !retired.load(Ordering::Relaxed) {
+                    match (&*queue).pop() {
+                        Ok(value) => handler(value),
+                        Err(_) if shutdown.load(Ordering::Acquire) => break,
+                        Err(_) => thread::sleep(poll_interval),
+                    }
+                }
+            })
+        };
+
+        Worker { retired, thread }
+    }
+
+    fn retire(self) {
+        self.retired.store(true, Ordering::Relaxed);
+        let _ = self.thread.join();
+    }
+}
+
+// Batteries-included consumer pool for a shared queue. A supervisor thread runs between
+// `min_workers` and `max_workers` consumer threads, each passing popped elements to the handler,
+// and adds or retires one worker per poll interval based on queue occupancy. Dropping the pool
+// lets the workers drain the queue, then joins them; whatever they leave behind, e.g. because no
+// worker was running, is drained by the supervisor before it exits.
+pub struct WorkerPool {
+    shutdown: Arc<AtomicBool>,
+    workers: Arc<AtomicUsize>,
+    supervisor: Option<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    /// Start consuming `queue` with `handler`, scaling according to `config`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_workers` is zero or less than `min_workers`.
+    pub fn new<T, F, const CAPACITY: usize>(
+        queue: Arc<ThreadSafeTypedQueue<T, CAPACITY>>,
+        config: PoolConfig,
+        handler: F,
+    ) -> Self
+    where
+        T: Copy + Send + 'static,
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        assert!(
+            config.max_workers > 0 && config.min_workers <= config.max_workers,
+            "invalid worker pool bounds"
+        );
+
+        let handler = Arc::new(handler);
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let workers = Arc::new(AtomicUsize::new(0));
+
+        let supervisor = {
+            let shutdown = Arc::clone(&shutdown);
+            let count = Arc::clone(&workers);
+            thread::spawn(move || {
+                let spawn = || {
+                    Worker::spawn(
+                        Arc::clone(&queue),
+                        Arc::clone(&handler),
+                        Arc::clone(&shutdown),
+                        config.poll_interval,
+                    )
+                };
+
+                let mut workers: Vec<Worker> = (0..config.min_workers).map(|_| spawn()).collect();
+                count.store(workers.len(), Ordering::Relaxed);
+
+                while !shutdown.load(Ordering::Acquire) {
+                    thread::sleep(config.poll_interval);
+
+                    let occupancy = queue.size() as f64 / queue.capacity() as f64;
+                    if occupancy > config.scale_up_at && workers.len() < config.max_workers {
+                        workers.push(spawn());
+                    } else if occupancy < config.scale_down_at && workers.len() > config.min_workers
+                    {
+                        if let Some(worker) = workers.pop() {
+                            worker.retire();
+                        }
+                    }
+                    count.store(workers.len(), Ordering::Relaxed);
+                }
+
+                for worker in workers {
+                    let _ = worker.thread.join();
+                }
+                count.store(0, Ordering::Relaxed);
+
+                // With `min_workers` at zero, there may be no worker left to drain the queue
+                while let Ok(value) = (&*queue).pop() {
+                    handler(value);
+                }
+            })
+        };
+
+        WorkerPool {
+            shutdown,
+            workers,
+            supervisor: Some(supervisor),
+        }
+    }
+
+    /// Get the number of running workers.
+    pub fn workers(&self) -> usize {
+        self.workers.load(Ordering::Relaxed)
+    }
+
+    /// Let the workers drain the queue, then stop them. Equivalent to dropping the pool.
+    pub fn shutdown(self) {}
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Release);
+        if let Some(supervisor) = self.supervisor.take() {
+            let _ = supervisor.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PoolConfig, WorkerPool};
+    use crate::thread_safe_typed_queue::ThreadSafeTypedQueue;
+    use crate::typed_queue::TypedQueue;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    // Arbitrary queue size for tests
+    const SIZE: usize = 16;
+
+    // Wait up to a generous deadline for a condition driven by other threads.
+    fn wait_for<F: Fn() -> bool>(condition: F) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !condition() {
+            if Instant::now() > deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        true
+    }
+
+    #[test]
+    fn consume_all() {
+        let queue = Arc::new(ThreadSafeTypedQueue::<u64, SIZE>::default());
+        let sum = Arc::new(AtomicU64::new(0));
+
+        let pool = {
+            let sum = Arc::clone(&sum);
+            WorkerPool::new(Arc::clone(&queue), PoolConfig::default(), move |value| {
+                sum.fetch_add(value, Ordering::Relaxed);
+            })
+        };
+
+        let mut pushed = 0;
+        for n in 1..=1000 {
+            while queue.push_slice(&[n]).unwrap() == 0 {
+                thread::yield_now();
+            }
+            pushed += n;
+        }
+
+        pool.shutdown();
+        assert_eq!(sum.load(Ordering::Relaxed), pushed);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn scale_up_down() {
+        let queue = Arc::new(ThreadSafeTypedQueue::<u64, SIZE>::default());
+        let config = PoolConfig {
+            min_workers: 1,
+            max_workers: 3,
+            ..PoolConfig::default()
+        };
+
+        let pool = WorkerPool::new(Arc::clone(&queue), config, |_| {
+            thread::sleep(Duration::from_millis(20));
+        });
+        assert!(wait_for(|| pool.workers() == 1));
+
+        // A slow handler keeps the queue full until the pool has grown to its limit
+        let producer = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                for n in 0..200 {
+                    while queue.push_slice(&[n]).unwrap() == 0 {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                }
+            })
+        };
+        assert!(wait_for(|| pool.workers() == 3));
+        producer.join().unwrap();
+
+        assert!(wait_for(|| pool.workers() == 1));
+    }
+
+    #[test]
+    fn shutdown_without_workers() {
+        let queue = Arc::new(ThreadSafeTypedQueue::<u64, SIZE>::default());
+        let sum = Arc::new(AtomicU64::new(0));
+        let config = PoolConfig {
+            min_workers: 0,
+            ..PoolConfig::default()
+        };
+
+        let pool = {
+            let sum = Arc::clone(&sum);
+            WorkerPool::new(Arc::clone(&queue), config, move |value| {
+                sum.fetch_add(value, Ordering::Relaxed);
+            })
+        };
+
+        // Too few elements for the pool to start a worker
+        assert_eq!(queue.push_slice(&[1, 2]).unwrap(), 2);
+        pool.shutdown();
+        assert_eq!(sum.load(Ordering::Relaxed), 3);
+        assert!(queue.is_empty());
+    }
+}